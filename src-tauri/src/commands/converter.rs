@@ -1,24 +1,31 @@
-use crate::commands::parser::parse_content;
+use crate::commands::parser::{parse_with_options, ParseOptions};
+use crate::formats::compression::{self, Compression};
+use crate::formats::csv::{CsvArrays, CsvExportOptions};
+use crate::formats::ejson::{self, ExtendedJsonMode};
+use crate::formats::encoding::{self, TextEncoding};
+use crate::formats::numbers::{self, Portable};
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::{
+    bson, cbor, dotenv, hcl, ini, jsonc, keys, msgpack, ndjson, plist, properties, protobuf, xml,
+    yaml,
+};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::BTreeSet;
+use std::collections::HashSet;
 use tauri::command;
 
-fn flatten_value(value: &Value, prefix: String, map: &mut Map<String, Value>) {
+fn flatten_value(value: &Value, prefix: String, separator: &str, map: &mut Map<String, Value>) {
     match value {
         Value::Object(obj) => {
             for (k, v) in obj {
-                let new_prefix = if prefix.is_empty() {
-                    k.clone()
-                } else {
-                    format!("{}.{}", prefix, k)
-                };
-                flatten_value(v, new_prefix, map);
+                flatten_value(v, child_key(&prefix, k, separator), separator, map);
             }
         }
         Value::Array(arr) => {
             for (i, v) in arr.iter().enumerate() {
                 let new_prefix = format!("{}[{}]", prefix, i);
-                flatten_value(v, new_prefix, map);
+                flatten_value(v, new_prefix, separator, map);
             }
         }
         _ => {
@@ -27,44 +34,304 @@ fn flatten_value(value: &Value, prefix: String, map: &mut Map<String, Value>) {
     }
 }
 
-fn json_to_csv(value: &Value) -> Result<String, String> {
-    let mut writer = csv::Writer::from_writer(vec![]);
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn child_key(prefix: &str, key: &str, separator: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}{}{}", prefix, separator, key)
+    }
+}
+
+/// Like `flatten_value`, but writes each array into a single cell: scalars joined with
+/// `arraySeparator`, anything nested as JSON.
+fn flatten_joined(
+    value: &Value,
+    prefix: String,
+    options: &CsvExportOptions,
+    map: &mut Map<String, Value>,
+) {
+    match value {
+        Value::Object(obj) => {
+            for (k, v) in obj {
+                let key = child_key(&prefix, k, &options.path_separator);
+                flatten_joined(v, key, options, map);
+            }
+        }
+        Value::Array(arr) if arr.iter().any(|v| v.is_array() || v.is_object()) => {
+            map.insert(prefix, Value::String(value.to_string()));
+        }
+        Value::Array(arr) => {
+            let cells: Vec<String> = arr.iter().map(cell_text).collect();
+            map.insert(prefix, Value::String(cells.join(&options.array_separator)));
+        }
+        _ => {
+            map.insert(prefix, value.clone());
+        }
+    }
+}
+
+/// Merges the rows of two sibling fields. A single row repeats next to each row of the other
+/// side; otherwise rows are paired up by position, so sibling arrays share rows.
+fn combine_rows(
+    left: Vec<Map<String, Value>>,
+    right: Vec<Map<String, Value>>,
+) -> Vec<Map<String, Value>> {
+    if left.len() == 1 {
+        let base = &left[0];
+        return right
+            .into_iter()
+            .map(|row| {
+                let mut merged = base.clone();
+                merged.extend(row);
+                merged
+            })
+            .collect();
+    }
+    if right.len() == 1 {
+        return left
+            .into_iter()
+            .map(|mut row| {
+                row.extend(right[0].clone());
+                row
+            })
+            .collect();
+    }
+    let len = left.len().max(right.len());
+    let mut left = left.into_iter();
+    let mut right = right.into_iter();
+    (0..len)
+        .map(|_| {
+            let mut row = left.next().unwrap_or_default();
+            row.extend(right.next().unwrap_or_default());
+            row
+        })
+        .collect()
+}
+
+/// Flattens a value into one row per array item, with item fields named without an index.
+fn flatten_rows(value: &Value, prefix: &str, separator: &str) -> Vec<Map<String, Value>> {
+    match value {
+        Value::Object(obj) => obj.iter().fold(vec![Map::new()], |rows, (k, v)| {
+            combine_rows(
+                rows,
+                flatten_rows(v, &child_key(prefix, k, separator), separator),
+            )
+        }),
+        Value::Array(arr) => {
+            let rows: Vec<_> = arr
+                .iter()
+                .flat_map(|v| flatten_rows(v, prefix, separator))
+                .collect();
+            if rows.is_empty() {
+                vec![Map::new()]
+            } else {
+                rows
+            }
+        }
+        _ => vec![Map::from_iter([(prefix.to_string(), value.clone())])],
+    }
+}
 
-    let array = match value {
-        Value::Array(arr) => arr.clone(),
-        _ => vec![value.clone()],
+fn json_to_csv(value: &Value, options: &ConvertOptions) -> Result<String, String> {
+    let csv_options = &options.csv;
+    if !csv_options.delimiter.is_ascii() {
+        return Err(format!(
+            "CSV delimiter must be an ASCII character, got '{}'",
+            csv_options.delimiter
+        ));
+    }
+    if csv_options.path_separator.is_empty() {
+        return Err("CSV path separator must not be empty".to_string());
+    }
+    let separator = csv_options.path_separator.as_str();
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(csv_options.delimiter as u8)
+        .from_writer(vec![]);
+
+    let items = match value {
+        Value::Array(arr) => arr.iter().collect(),
+        _ => vec![value],
     };
 
     let mut flattened_rows = Vec::new();
-    let mut all_headers = BTreeSet::new();
-
-    for item in array {
-        let mut row_map = Map::new();
-        flatten_value(&item, String::new(), &mut row_map);
-        for key in row_map.keys() {
-            all_headers.insert(key.clone());
+    for item in items {
+        match csv_options.arrays {
+            CsvArrays::Columns => {
+                let mut row_map = Map::new();
+                flatten_value(item, String::new(), separator, &mut row_map);
+                flattened_rows.push(row_map);
+            }
+            CsvArrays::Join => {
+                let mut row_map = Map::new();
+                flatten_joined(item, String::new(), csv_options, &mut row_map);
+                flattened_rows.push(row_map);
+            }
+            CsvArrays::Rows => flattened_rows.extend(flatten_rows(item, "", separator)),
         }
-        flattened_rows.push(row_map);
     }
 
-    let headers: Vec<String> = all_headers.into_iter().collect();
-    writer.write_record(&headers).map_err(|e| e.to_string())?;
+    let headers = if csv_options.columns.is_empty() {
+        // Columns appear in the order their keys are first seen across rows.
+        let mut headers = Vec::new();
+        let mut seen_headers = HashSet::new();
+        for row in &flattened_rows {
+            for key in row.keys() {
+                if seen_headers.insert(key) {
+                    headers.push(key.clone());
+                }
+            }
+        }
+        if options.sort_keys {
+            headers.sort();
+        }
+        headers
+    } else {
+        csv_options.columns.clone()
+    };
+    let titles = headers
+        .iter()
+        .map(|header| csv_options.headers.get(header).unwrap_or(header));
+    writer.write_record(titles).map_err(|e| e.to_string())?;
 
     for row in flattened_rows {
-        let mut record = Vec::new();
-        for header in &headers {
-            let val = row.get(header).unwrap_or(&Value::Null);
-            record.push(match val {
-                Value::Null => String::new(),
-                Value::String(s) => s.clone(),
-                _ => val.to_string(),
-            });
-        }
-        writer.write_record(&record).map_err(|e| e.to_string())?;
+        let record = headers
+            .iter()
+            .map(|header| cell_text(row.get(header).unwrap_or(&Value::Null)));
+        writer.write_record(record).map_err(|e| e.to_string())?;
     }
 
     let buf = writer.into_inner().map_err(|e| e.to_string())?;
-    String::from_utf8(buf).map_err(|e| e.to_string())
+    let text = String::from_utf8(buf).map_err(|e| e.to_string())?;
+    Ok(if csv_options.bom {
+        format!("\u{feff}{}", text)
+    } else {
+        text
+    })
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConvertOptions {
+    /// Write an array as `---`-separated YAML documents instead of a single sequence.
+    pub multi_document: bool,
+    /// Sort object keys (and CSV columns) alphabetically instead of keeping document order.
+    pub sort_keys: bool,
+    /// Also return the output compressed as gzip or zstd.
+    #[serde(deserialize_with = "compression::output_compression")]
+    pub compression: Option<Compression>,
+    /// Descriptor and message type for protobuf input or output.
+    pub protobuf: ProtobufOptions,
+    /// Delimiter, columns and array handling for CSV output.
+    pub csv: CsvExportOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConvertedDocument {
+    pub output: String,
+    pub warnings: Vec<String>,
+    /// Set for binary targets such as MessagePack, whose bytes `output` holds as base64.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+    /// The output bytes (UTF-8 for text targets) compressed with the requested `compression`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed: Option<Vec<u8>>,
+}
+
+fn extended_json(value: &Value, mode: ExtendedJsonMode) -> Result<String, String> {
+    serde_json::to_string_pretty(&ejson::to_extended_json(value, mode)?).map_err(|e| e.to_string())
+}
+
+/// Serializes an already parsed value to `target_format`.
+pub fn serialize_value(
+    value: &Value,
+    target_format: &str,
+    options: &ConvertOptions,
+) -> Result<ConvertedDocument, String> {
+    let sorted;
+    let value = if options.sort_keys {
+        let mut copy = value.clone();
+        keys::sort_keys(&mut copy);
+        sorted = copy;
+        &sorted
+    } else {
+        value
+    };
+
+    let target = target_format.to_lowercase();
+    let binary = match target.as_str() {
+        "msgpack" | "messagepack" => Some(msgpack::to_msgpack(value)),
+        "cbor" => Some(cbor::to_cbor(value)),
+        "bson" => Some(bson::to_bson(value)?),
+        "bplist" => Some(plist::to_binary_plist(value)?),
+        "protobuf" | "pb" => Some(protobuf::to_protobuf(value, &options.protobuf)?),
+        _ => None,
+    };
+    let output = if let Some(bytes) = &binary {
+        BASE64_STANDARD.encode(bytes)
+    } else {
+        match target.as_str() {
+            "json" | "jsonc" => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+            "json5" => Ok(jsonc::to_json5(value)),
+            "ejson" | "ejson-relaxed" => extended_json(value, ExtendedJsonMode::Relaxed),
+            "ejson-canonical" => extended_json(value, ExtendedJsonMode::Canonical),
+            "ndjson" | "jsonl" => ndjson::to_ndjson(value),
+            "yaml" | "yml" if options.multi_document => yaml::to_multi_document_yaml(value),
+            "yaml" | "yml" => serde_yaml::to_string(&Portable(value)).map_err(|e| e.to_string()),
+            "toml" => toml::to_string_pretty(&Portable(value)).map_err(|e| e.to_string()),
+            "xml" => xml::to_xml(value),
+            "plist" => plist::to_xml_plist(value),
+            "ini" | "cfg" => ini::to_ini(value),
+            "env" | "dotenv" => dotenv::to_env(value),
+            "properties" => properties::to_properties(value),
+            "hcl" | "tf" | "terraform" | "tfvars" => hcl::to_hcl(value),
+            "csv" => json_to_csv(value, options),
+            _ => Err(format!("Unsupported target format: {}", target_format)),
+        }?
+    };
+
+    // JSON-based targets, XML and CSV write the original number text; the rest can only write
+    // 64-bit integers and doubles.
+    let warnings = match target.as_str() {
+        "yaml" | "yml" | "toml" | "msgpack" | "messagepack" | "cbor" | "bson" | "bplist"
+        | "hcl" | "tf" | "terraform" | "tfvars" => numbers::serializer_warnings(value, &target),
+        _ => Vec::new(),
+    };
+    let bytes = binary.as_deref().unwrap_or(output.as_bytes());
+    let compressed = match options.compression {
+        Some(method) => Some(compression::compress(bytes, method)?),
+        None => None,
+    };
+    Ok(ConvertedDocument {
+        output,
+        warnings,
+        base64: binary.is_some(),
+        compressed,
+    })
+}
+
+pub fn convert_with_options(
+    content: &str,
+    source_format: &str,
+    target_format: &str,
+    options: &ConvertOptions,
+) -> Result<ConvertedDocument, String> {
+    // Reuse the parser logic to get a generic generic Value
+    let parse_options = ParseOptions {
+        protobuf: options.protobuf.clone(),
+        ..Default::default()
+    };
+    let value = parse_with_options(content, source_format, &parse_options)
+        .map_err(|e| e.to_string())?
+        .value;
+    serialize_value(&value, target_format, options)
 }
 
 #[command]
@@ -73,17 +340,34 @@ pub fn convert_format(
     source_format: String,
     target_format: String,
 ) -> Result<String, String> {
-    // Reuse the parser logic to get a generic generic Value
-    let value = parse_content(content, source_format)?;
-
-    match target_format.to_lowercase().as_str() {
-        "json" => serde_json::to_string_pretty(&value).map_err(|e| e.to_string()),
-        "yaml" | "yml" => serde_yaml::to_string(&value).map_err(|e| e.to_string()),
-        "toml" => toml::to_string_pretty(&value).map_err(|e| e.to_string()),
-        "xml" => quick_xml::se::to_string(&value).map_err(|e| e.to_string()),
-        "csv" => json_to_csv(&value),
-        _ => Err(format!("Unsupported target format: {}", target_format)),
-    }
+    convert_with_options(
+        &content,
+        &source_format,
+        &target_format,
+        &ConvertOptions::default(),
+    )
+    .map(|converted| converted.output)
+}
+
+#[command]
+pub fn convert_document(
+    content: String,
+    source_format: String,
+    target_format: String,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertedDocument, String> {
+    convert_with_options(
+        &content,
+        &source_format,
+        &target_format,
+        &options.unwrap_or_default(),
+    )
+}
+
+/// Encodes text for saving, e.g. back into the encoding `parse_bytes` reported.
+#[command]
+pub fn encode_text(text: String, encoding: Option<TextEncoding>) -> Result<Vec<u8>, String> {
+    encoding::encode(&text, &encoding.unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -22,7 +22,397 @@ fn test_convert_json_to_csv() {
         r#"[{"user": {"name": "Alex", "age": 30}}, {"user": {"name": "Bob", "age": 25}}]"#;
     let result =
         convert_format(content.to_string(), "json".to_string(), "csv".to_string()).unwrap();
-    assert!(result.contains("user.age,user.name"));
-    assert!(result.contains("30,Alex"));
-    assert!(result.contains("25,Bob"));
+    assert!(result.contains("user.name,user.age"));
+    assert!(result.contains("Alex,30"));
+    assert!(result.contains("Bob,25"));
+}
+
+#[test]
+fn test_convert_auto_source() {
+    let content = "name = 'test'";
+    let result =
+        convert_format(content.to_string(), "auto".to_string(), "json".to_string()).unwrap();
+    assert!(result.contains(r#""name": "test""#));
+}
+
+#[test]
+fn test_convert_jsonc_to_json5() {
+    let content = "{\n  // comment\n  \"name\": \"test\",\n}";
+    let result = convert_format(
+        content.to_string(),
+        "jsonc".to_string(),
+        "json5".to_string(),
+    )
+    .unwrap();
+    assert_eq!(result, "{\n  name: \"test\",\n}\n");
+}
+
+#[test]
+fn test_convert_json_to_ndjson() {
+    let content = r#"[{"id": 1}, {"id": 2}]"#;
+    let result =
+        convert_format(content.to_string(), "json".to_string(), "jsonl".to_string()).unwrap();
+    assert_eq!(result, "{\"id\":1}\n{\"id\":2}\n");
+}
+
+#[test]
+fn test_convert_multi_document_yaml() {
+    let content = "a: 1\n---\nb: 2\n";
+    let result =
+        convert_format(content.to_string(), "yaml".to_string(), "json".to_string()).unwrap();
+    assert!(result.trim_start().starts_with('['));
+
+    let options = ConvertOptions {
+        multi_document: true,
+        ..Default::default()
+    };
+    let result = convert_document(
+        content.to_string(),
+        "yaml".to_string(),
+        "yaml".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(result.output, "---\na: 1\n---\nb: 2\n");
+}
+
+#[test]
+fn test_convert_keeps_big_numbers_exact() {
+    let content = r#"{"id": 1311768467463790320123, "amount": 0.1000000000000000055}"#;
+    let result =
+        convert_format(content.to_string(), "json".to_string(), "json".to_string()).unwrap();
+    assert!(result.contains("1311768467463790320123"));
+    assert!(result.contains("0.1000000000000000055"));
+
+    let result =
+        convert_format(content.to_string(), "json".to_string(), "csv".to_string()).unwrap();
+    assert!(result.contains("1311768467463790320123,0.1000000000000000055"));
+}
+
+#[test]
+fn test_convert_warns_when_numbers_are_rounded() {
+    let content = r#"{"id": 1311768467463790320123, "count": 2}"#;
+    let result = convert_document(
+        content.to_string(),
+        "json".to_string(),
+        "yaml".to_string(),
+        None,
+    )
+    .unwrap();
+    assert!(result.output.contains("count: 2"));
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("$.id"));
+}
+
+#[test]
+fn test_convert_preserves_key_order() {
+    let content = r#"{"zeta": 1, "alpha": {"y": true, "b": "x"}, "mid": [3]}"#;
+    for target in ["json", "yaml", "toml", "json5"] {
+        let result =
+            convert_format(content.to_string(), "json".to_string(), target.to_string()).unwrap();
+        let zeta = result.find("zeta").unwrap();
+        let alpha = result.find("alpha").unwrap();
+        let mid = result.find("mid").unwrap();
+        assert!(zeta < mid, "{target}: {result}");
+        if target != "toml" {
+            // TOML moves tables after plain keys.
+            assert!(zeta < alpha && alpha < mid, "{target}: {result}");
+        }
+    }
+}
+
+#[test]
+fn test_convert_sort_keys_option() {
+    let content = r#"[{"b": 1, "a": 2}, {"c": 3}]"#;
+    let result =
+        convert_format(content.to_string(), "json".to_string(), "csv".to_string()).unwrap();
+    assert!(result.starts_with("b,a,c\n"));
+
+    let options = ConvertOptions {
+        sort_keys: true,
+        ..Default::default()
+    };
+    let result = convert_document(
+        content.to_string(),
+        "json".to_string(),
+        "csv".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert!(result.output.starts_with("a,b,c\n"));
+}
+
+#[test]
+fn test_convert_to_binary_formats_returns_base64() {
+    let content = r#"{"a": 1}"#;
+    let result = convert_document(
+        content.to_string(),
+        "json".to_string(),
+        "msgpack".to_string(),
+        None,
+    )
+    .unwrap();
+    assert!(result.base64);
+    assert_eq!(result.output, "gaFhAQ==");
+
+    let result =
+        convert_format(content.to_string(), "json".to_string(), "cbor".to_string()).unwrap();
+    assert_eq!(result, "oWFhAQ==");
+    let back = convert_format(result, "cbor".to_string(), "json".to_string()).unwrap();
+    assert_eq!(back, "{\n  \"a\": 1\n}");
+}
+
+#[test]
+fn test_convert_binary_compresses_raw_bytes() {
+    use crate::formats::compression::decompress;
+
+    let options = ConvertOptions {
+        compression: Some(Compression::Gzip),
+        ..Default::default()
+    };
+    let result = convert_document(
+        "[true]".to_string(),
+        "json".to_string(),
+        "cbor".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    let compressed = result.compressed.unwrap();
+    assert_eq!(
+        decompress(compressed.as_slice(), None).unwrap().bytes,
+        [0x81, 0xf5]
+    );
+}
+
+#[test]
+fn test_convert_options_reject_zip_output() {
+    let error = serde_json::from_value::<ConvertOptions>(serde_json::json!({"compression": "zip"}))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("zip"), "{error}");
+    let options: ConvertOptions =
+        serde_json::from_value(serde_json::json!({"compression": "zstd"})).unwrap();
+    assert_eq!(options.compression, Some(Compression::Zstd));
+}
+
+#[test]
+fn test_convert_extended_json_modes() {
+    let content = r#"{"n": {"$numberInt": "1"}, "at": {"$date": {"$numberLong": "0"}}}"#;
+    let relaxed = convert_format(
+        content.to_string(),
+        "ejson".to_string(),
+        "ejson".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&relaxed).unwrap(),
+        serde_json::json!({"n": 1, "at": {"$date": "1970-01-01T00:00:00Z"}})
+    );
+    let canonical =
+        convert_format(relaxed, "ejson".to_string(), "ejson-canonical".to_string()).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&canonical).unwrap(),
+        serde_json::from_str::<Value>(content).unwrap()
+    );
+
+    let bson = convert_document(
+        content.to_string(),
+        "ejson".to_string(),
+        "bson".to_string(),
+        None,
+    )
+    .unwrap();
+    assert!(bson.base64);
+    let back = convert_format(bson.output, "bson".to_string(), "ejson".to_string()).unwrap();
+    assert!(back.contains("1970-01-01T00:00:00Z"));
+}
+
+#[test]
+fn test_convert_to_config_formats() {
+    let content = r#"{"port": 8080, "db": {"host": "localhost"}}"#;
+    let convert = |target: &str| {
+        convert_format(content.to_string(), "json".to_string(), target.to_string()).unwrap()
+    };
+    assert_eq!(convert("ini"), "port = 8080\n\n[db]\nhost = localhost\n");
+    assert_eq!(convert("env"), "port=8080\ndb__host=localhost\n");
+    assert_eq!(convert("properties"), "port=8080\ndb.host=localhost\n");
+
+    let back = convert_format(
+        "[db]\nhost = localhost\n".to_string(),
+        "ini".to_string(),
+        "json".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&back).unwrap(),
+        serde_json::json!({"db": {"host": "localhost"}})
+    );
+}
+
+#[test]
+fn test_convert_hcl_and_terraform_json() {
+    let content = "resource \"aws_s3_bucket\" \"logs\" {\n  bucket = var.name\n}\n";
+    let json = convert_format(content.to_string(), "hcl".to_string(), "json".to_string()).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&json).unwrap(),
+        serde_json::json!({"resource": {"aws_s3_bucket": {"logs": {"bucket": "${var.name}"}}}})
+    );
+    let back = convert_format(json, "json".to_string(), "hcl".to_string()).unwrap();
+    assert_eq!(back, content);
+}
+
+#[test]
+fn test_convert_protobuf_with_options() {
+    use crate::formats::protobuf::ProtobufOptions;
+    use std::collections::BTreeMap;
+
+    let options = ConvertOptions {
+        protobuf: ProtobufOptions {
+            proto_files: BTreeMap::from([(
+                "ping.proto".to_string(),
+                "syntax = \"proto3\";\nmessage Ping { int64 seq = 1; }\n".to_string(),
+            )]),
+            message_type: Some("Ping".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let converted = convert_document(
+        r#"{"seq": "42"}"#.to_string(),
+        "json".to_string(),
+        "protobuf".to_string(),
+        Some(options.clone()),
+    )
+    .unwrap();
+    assert!(converted.base64);
+    assert_eq!(converted.output, "CCo=");
+
+    let back = convert_document(
+        converted.output,
+        "protobuf".to_string(),
+        "json".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&back.output).unwrap(),
+        serde_json::json!({"seq": "42"})
+    );
+}
+
+fn csv_export(content: &str, csv: CsvExportOptions) -> Result<String, String> {
+    let options = ConvertOptions {
+        csv,
+        ..Default::default()
+    };
+    convert_document(
+        content.to_string(),
+        "json".to_string(),
+        "csv".to_string(),
+        Some(options),
+    )
+    .map(|converted| converted.output)
+}
+
+#[test]
+fn test_csv_export_delimiter_columns_and_headers() {
+    let content = r#"[{"id": 1, "user": {"name": "Ada"}, "note": "x"}, {"id": 2}]"#;
+    let output = csv_export(
+        content,
+        CsvExportOptions {
+            delimiter: ';',
+            columns: vec!["user.name".to_string(), "id".to_string()],
+            headers: [("user.name".to_string(), "Name".to_string())].into(),
+            bom: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(output, "\u{feff}Name;id\nAda;1\n;2\n");
+
+    let err = csv_export(
+        content,
+        CsvExportOptions {
+            delimiter: '§',
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(err.contains("ASCII"), "{}", err);
+}
+
+#[test]
+fn test_csv_export_joins_arrays() {
+    let content =
+        r#"[{"id": 1, "tags": ["a", "b", 3], "items": [{"x": 1}]}, {"id": 2, "tags": []}]"#;
+    let output = csv_export(
+        content,
+        CsvExportOptions {
+            arrays: CsvArrays::Join,
+            array_separator: "|".to_string(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(output, "id,tags,items\n1,a|b|3,\"[{\"\"x\"\":1}]\"\n2,,\n");
+}
+
+#[test]
+fn test_csv_export_explodes_arrays_into_rows() {
+    let content = r#"[
+        {"order": 1, "items": [{"sku": "A", "qty": 2}, {"sku": "B", "qty": 1}], "paid": true},
+        {"order": 2, "items": [], "paid": false},
+        {"order": 3, "items": [{"sku": "C"}], "tags": ["x", "y"], "paid": true}
+    ]"#;
+    let output = csv_export(
+        content,
+        CsvExportOptions {
+            arrays: CsvArrays::Rows,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        output,
+        "order,items.sku,items.qty,paid,tags\n\
+         1,A,2,true,\n\
+         1,B,1,true,\n\
+         2,,,false,\n\
+         3,C,,true,x\n\
+         3,C,,true,y\n"
+    );
+}
+
+#[test]
+fn test_csv_round_trip_with_unflatten() {
+    use crate::commands::parser::{parse_with_options, ParseOptions};
+    use crate::formats::csv::CsvOptions;
+
+    let content = r#"[
+        {"id": 1, "user": {"name": "Ada", "tags": ["a", "b"]}, "items": [{"sku": "A.1", "qty": 2}]},
+        {"id": 2, "user": {"name": "Bob", "tags": ["c"]}, "items": [{"sku": "B", "qty": 1}, {"sku": "C", "qty": 5}]}
+    ]"#;
+    let csv = csv_export(
+        content,
+        CsvExportOptions {
+            path_separator: "/".to_string(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(csv.starts_with("id,user/name,user/tags[0],user/tags[1],items[0]/sku"));
+
+    let options = ParseOptions {
+        csv: CsvOptions {
+            unflatten: true,
+            path_separator: "/".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = parse_with_options(&csv, "csv", &options).unwrap();
+    assert_eq!(
+        parsed.value,
+        serde_json::from_str::<Value>(content).unwrap()
+    );
 }
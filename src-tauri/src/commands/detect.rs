@@ -0,0 +1,232 @@
+use serde::Serialize;
+use tauri::command;
+
+/// Formats `auto` can pick from, in the order they are tried when scores tie.
+pub const DETECTABLE_FORMATS: [&str; 9] = [
+    "json", "jsonc", "json5", "ndjson", "plist", "xml", "toml", "yaml", "csv",
+];
+
+/// Number of non-empty lines inspected by the line-based heuristics.
+const SAMPLE_LINES: usize = 20;
+
+/// Number of leading bytes searched for a property list doctype or root element.
+const PROLOGUE_BYTES: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Detection {
+    pub format: String,
+    pub confidence: f32,
+}
+
+/// Maps a file name or extension to one of the parser formats. A compression suffix such as
+/// `.json.gz` is looked through.
+pub fn format_from_filename(filename: &str) -> Option<&'static str> {
+    let lower = filename.to_lowercase();
+    let name = [".gz", ".zst", ".zstd"]
+        .iter()
+        .find_map(|suffix| lower.strip_suffix(suffix))
+        .unwrap_or(&lower);
+    // `.env`, `.env.local`, `.env.production` and so on.
+    let basename = name.rsplit(['/', '\\']).next()?;
+    if basename == ".env" || basename.starts_with(".env.") {
+        return Some("env");
+    }
+    let ext = name.rsplit('.').next()?;
+    match ext {
+        "json" | "geojson" => Some("json"),
+        "jsonc" => Some("jsonc"),
+        "json5" => Some("json5"),
+        "ndjson" | "jsonl" => Some("ndjson"),
+        "yaml" | "yml" => Some("yaml"),
+        "toml" => Some("toml"),
+        "xml" | "svg" | "xsd" => Some("xml"),
+        "csv" | "tsv" => Some("csv"),
+        "msgpack" | "mpk" => Some("msgpack"),
+        "cbor" => Some("cbor"),
+        "bson" => Some("bson"),
+        "plist" => Some("plist"),
+        "ini" | "cfg" | "gitconfig" | "editorconfig" => Some("ini"),
+        "env" => Some("env"),
+        "properties" => Some("properties"),
+        "tf" | "hcl" | "tfvars" => Some("hcl"),
+        "pb" | "binpb" | "protobuf" => Some("protobuf"),
+        "avro" => Some("avro"),
+        _ => None,
+    }
+}
+
+/// Property lists are XML with a known doctype or root element.
+fn is_plist_prologue(content: &str) -> bool {
+    let end = content
+        .char_indices()
+        .map(|(i, _)| i)
+        .find(|i| *i >= PROLOGUE_BYTES)
+        .unwrap_or(content.len());
+    let head = &content[..end];
+    head.contains("<!DOCTYPE plist") || head.contains("<plist")
+}
+
+fn sample_lines(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .take(SAMPLE_LINES)
+        .collect()
+}
+
+fn is_toml_table_header(line: &str) -> bool {
+    let line = line.trim();
+    let inner = line
+        .strip_prefix("[[")
+        .and_then(|l| l.strip_suffix("]]"))
+        .or_else(|| line.strip_prefix('[').and_then(|l| l.strip_suffix(']')));
+    inner.is_some_and(|name| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '"' | '\'' | ' '))
+    })
+}
+
+fn is_toml_assignment(line: &str) -> bool {
+    match line.split_once('=') {
+        Some((key, _)) => {
+            let key = key.trim();
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '"' | '\''))
+        }
+        None => false,
+    }
+}
+
+fn is_yaml_line(line: &str) -> bool {
+    let line = line.trim_start();
+    line == "-"
+        || line.starts_with("- ")
+        || line.ends_with(':')
+        || line
+            .split_once(": ")
+            .is_some_and(|(key, _)| !key.is_empty() && !key.contains(['{', '[', ',']))
+}
+
+fn ratio(lines: &[&str], predicate: impl Fn(&str) -> bool) -> f32 {
+    if lines.is_empty() {
+        return 0.0;
+    }
+    lines.iter().filter(|line| predicate(line)).count() as f32 / lines.len() as f32
+}
+
+fn is_json_line(line: &str) -> bool {
+    let line = line.trim();
+    (line.starts_with('{') && line.ends_with('}')) || (line.starts_with('[') && line.ends_with(']'))
+}
+
+fn csv_score(lines: &[&str]) -> f32 {
+    if lines.len() < 2 {
+        return 0.0;
+    }
+    let consistent = |delimiter: char| {
+        let columns = lines[0].matches(delimiter).count();
+        columns > 0
+            && lines
+                .iter()
+                .all(|line| line.matches(delimiter).count() == columns)
+    };
+    if [',', '\t', ';', '|'].into_iter().any(consistent) {
+        0.7
+    } else {
+        0.0
+    }
+}
+
+/// Scores every detectable format against the content and returns them best first.
+///
+/// This only looks at the shape of the text; callers still have to parse the content to know
+/// whether a candidate is actually valid.
+pub fn rank_formats(content: &str, filename: Option<&str>) -> Vec<Detection> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    let lines = sample_lines(trimmed);
+    let mut scores = [0.0_f32; DETECTABLE_FORMATS.len()];
+    let mut add = |format: &str, score: f32| {
+        if let Some(i) = DETECTABLE_FORMATS.iter().position(|f| *f == format) {
+            scores[i] += score;
+        }
+    };
+
+    if trimmed.starts_with('<') && is_plist_prologue(trimmed) {
+        // Ties go to plist, which comes before xml above.
+        add("plist", 1.0);
+        add("xml", 1.0);
+    } else if trimmed.starts_with("<?xml") {
+        add("xml", 1.0);
+    } else if trimmed.starts_with('<') {
+        add("xml", 0.8);
+    } else if trimmed.starts_with('{') {
+        add("json", 0.8);
+        add("yaml", 0.1);
+    } else if trimmed.starts_with('[') {
+        if lines.first().is_some_and(|line| is_toml_table_header(line)) {
+            add("toml", 0.7);
+            add("json", 0.3);
+        } else {
+            add("json", 0.8);
+            add("yaml", 0.1);
+        }
+    } else if trimmed.starts_with('"')
+        || trimmed.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+            && !trimmed.starts_with("---")
+        || ["true", "false", "null"].contains(&trimmed.trim_end())
+    {
+        add("json", 0.4);
+    }
+
+    if !lines.is_empty() && lines.iter().all(|line| is_json_line(line)) {
+        // A single line is also plain JSON; only the file name can tell them apart.
+        add("ndjson", if lines.len() > 1 { 0.9 } else { 0.4 });
+    }
+    if trimmed.starts_with("---") || trimmed.starts_with("%YAML") {
+        add("yaml", 0.8);
+    }
+    add(
+        "toml",
+        0.6 * ratio(&lines, |l| is_toml_assignment(l) || is_toml_table_header(l)),
+    );
+    add("yaml", 0.6 * ratio(&lines, is_yaml_line));
+    add("csv", csv_score(&lines));
+
+    if let Some(hint) = filename.and_then(format_from_filename) {
+        add(hint, 0.5);
+    }
+
+    let mut ranked: Vec<Detection> = DETECTABLE_FORMATS
+        .iter()
+        .zip(scores)
+        .filter(|(_, score)| *score > 0.0)
+        .map(|(format, score)| Detection {
+            format: format.to_string(),
+            confidence: score.min(1.0),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    // Plain text is still valid YAML (a single string), so keep it as the last resort.
+    if !ranked.iter().any(|d| d.format == "yaml") {
+        ranked.push(Detection {
+            format: "yaml".to_string(),
+            confidence: 0.1,
+        });
+    }
+    ranked
+}
+
+#[command]
+pub fn detect_format(content: String, filename: Option<String>) -> Detection {
+    rank_formats(&content, filename.as_deref()).remove(0)
+}
+
+#[cfg(test)]
+#[path = "detect_test.rs"]
+mod detect_test;
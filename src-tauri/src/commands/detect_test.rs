@@ -0,0 +1,73 @@
+use super::*;
+
+fn top(content: &str, filename: Option<&str>) -> String {
+    rank_formats(content, filename).remove(0).format
+}
+
+#[test]
+fn test_detect_json() {
+    assert_eq!(top(r#"{"name": "test"}"#, None), "json");
+    assert_eq!(top("  [1, 2, 3]", None), "json");
+}
+
+#[test]
+fn test_detect_xml() {
+    let detection = detect_format("<?xml version=\"1.0\"?><root/>".to_string(), None);
+    assert_eq!(detection.format, "xml");
+    assert_eq!(detection.confidence, 1.0);
+}
+
+#[test]
+fn test_detect_yaml() {
+    assert_eq!(top("name: test\nitems:\n  - a\n  - b", None), "yaml");
+    assert_eq!(top("---\nname: test", None), "yaml");
+}
+
+#[test]
+fn test_detect_toml() {
+    assert_eq!(top("name = 'test'\nvalue = 123", None), "toml");
+    assert_eq!(top("[package]\nname = \"demo\"", None), "toml");
+}
+
+#[test]
+fn test_detect_csv() {
+    assert_eq!(top("name,value\ntest,123\nother,456", None), "csv");
+    assert_eq!(top("name\tvalue\ntest\t123\nother\t456", None), "csv");
+    assert_eq!(top("name;value\ntest;123\nother;456", None), "csv");
+    assert_eq!(format_from_filename("export.tsv"), Some("csv"));
+}
+
+#[test]
+fn test_filename_hint_breaks_ties() {
+    assert_eq!(top("value", Some("data.toml")), "toml");
+    assert_eq!(format_from_filename("config.YML"), Some("yaml"));
+    assert_eq!(format_from_filename("README"), None);
+    assert_eq!(format_from_filename("dump.JSON.gz"), Some("json"));
+    assert_eq!(format_from_filename("events.ndjson.zst"), Some("ndjson"));
+}
+
+#[test]
+fn test_plain_text_falls_back_to_yaml() {
+    let ranked = rank_formats("just some words", None);
+    assert_eq!(ranked.last().unwrap().format, "yaml");
+}
+
+#[test]
+fn test_detect_ndjson() {
+    assert_eq!(top("{\"id\": 1}\n{\"id\": 2}\n", None), "ndjson");
+    assert_eq!(top("{\"id\": 1}", Some("events.jsonl")), "ndjson");
+    assert_eq!(top("[\n  {\"id\": 1},\n  {\"id\": 2}\n]", None), "json");
+}
+
+#[test]
+fn test_config_formats_by_filename() {
+    assert_eq!(format_from_filename("setup.cfg"), Some("ini"));
+    assert_eq!(format_from_filename("/home/me/.gitconfig"), Some("ini"));
+    assert_eq!(format_from_filename(".env"), Some("env"));
+    assert_eq!(format_from_filename("deploy/.env.production"), Some("env"));
+    assert_eq!(format_from_filename("prod.env"), Some("env"));
+    assert_eq!(
+        format_from_filename("application.properties"),
+        Some("properties")
+    );
+}
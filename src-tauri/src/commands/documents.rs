@@ -0,0 +1,473 @@
+use crate::commands::converter::{serialize_value, ConvertOptions, ConvertedDocument};
+use crate::commands::parser::{
+    canonical_format, parse_avro_head, parse_reader_with_options, ParseError, ParseErrorKind,
+    ParseOptions, ParsedDocument,
+};
+use crate::commands::schema::infer_schema;
+use crate::commands::tools::{query_jq, QueryResult};
+use crate::formats::avro::{self, AvroReader};
+use crate::formats::compression::{self, ArchiveEntry, Compression};
+use crate::formats::csv::CsvDialect;
+use crate::formats::encoding::{self, TextEncoding};
+use crate::formats::numbers;
+use crate::formats::positions::DuplicateKey;
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::repair::Repair;
+use crate::formats::yaml::DocumentInfo;
+use base64::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, State};
+
+pub type DocumentHandle = u64;
+
+/// Documents parsed in the backend, so large files cross the IPC bridge only as summaries
+/// and query results.
+#[derive(Default)]
+pub struct DocumentStore {
+    next_handle: AtomicU64,
+    documents: Mutex<HashMap<DocumentHandle, OpenDocument>>,
+}
+
+struct OpenDocument {
+    value: Arc<Value>,
+    format: String,
+    encoding: TextEncoding,
+    compression: Option<Compression>,
+    /// Descriptor and message type the document was decoded with, reused when saving.
+    protobuf: ProtobufOptions,
+    /// `value` keeps numbers as parsed; values sent to the viewer have the ones a double
+    /// cannot hold turned into strings.
+    lossless_numbers: bool,
+    /// Only the first page of an Avro file is loaded.
+    has_more: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSummary {
+    pub handle: DocumentHandle,
+    pub path: String,
+    pub format: String,
+    pub confidence: f32,
+    /// Size of the file on disk.
+    pub bytes: u64,
+    pub root_type: String,
+    /// Number of keys or items directly under the root.
+    pub entries: usize,
+    /// Number of values in the whole tree, the root included.
+    pub nodes: usize,
+    pub depth: usize,
+    pub errors: Vec<ParseError>,
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<Vec<DocumentInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<CsvDialect>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<Repair>,
+    pub encoding: TextEncoding,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_keys: Vec<DuplicateKey>,
+    /// Set for Avro files with more records than the first page, which is all the document
+    /// holds; `read_avro_records` reads the others.
+    pub has_more: bool,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Counts the values in a tree and its depth (a scalar root has depth 0).
+fn measure(value: &Value) -> (usize, usize) {
+    let children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Array(arr) => Box::new(arr.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        _ => return (1, 0),
+    };
+    children.fold((1, 0), |(nodes, depth), child| {
+        let (child_nodes, child_depth) = measure(child);
+        (nodes + child_nodes, depth.max(child_depth + 1))
+    })
+}
+
+impl DocumentStore {
+    pub fn open(
+        &self,
+        path: &str,
+        format: &str,
+        options: &ParseOptions,
+    ) -> Result<DocumentSummary, ParseError> {
+        let io_error = |e: std::io::Error| {
+            ParseError::new(format, ParseErrorKind::Io, format!("{}: {}", path, e))
+        };
+        let bytes = std::fs::metadata(path).map_err(io_error)?.len();
+        let mut reader = BufReader::new(std::fs::File::open(path).map_err(io_error)?);
+        // Avro files are paged rather than decoded whole; `read_avro_records` reads the rest.
+        let is_avro = match canonical_format(format) {
+            Some("avro") => true,
+            Some("auto") => reader
+                .fill_buf()
+                .map_err(io_error)?
+                .starts_with(avro::MAGIC),
+            _ => false,
+        };
+
+        let lossless_numbers = options.lossless_numbers;
+        let options = ParseOptions {
+            filename: options.filename.clone().or_else(|| {
+                Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            }),
+            keep_numbers: true,
+            ..options.clone()
+        };
+        let (parsed, has_more) = if is_avro {
+            parse_avro_head(reader, AVRO_PAGE_SIZE, &options)?
+        } else {
+            (parse_reader_with_options(reader, format, &options)?, false)
+        };
+        let ParsedDocument {
+            value,
+            format,
+            confidence,
+            errors,
+            warnings,
+            documents,
+            dialect,
+            repairs,
+            encoding,
+            compression,
+            entry,
+            schema,
+            positions: _,
+            duplicate_keys,
+        } = parsed;
+        let encoding = encoding.unwrap_or_default();
+
+        let (nodes, depth) = measure(&value);
+        let entries = match &value {
+            Value::Array(arr) => arr.len(),
+            Value::Object(map) => map.len(),
+            _ => 0,
+        };
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed) + 1;
+        let summary = DocumentSummary {
+            handle,
+            path: path.to_string(),
+            format: format.clone(),
+            confidence,
+            bytes,
+            root_type: type_name(&value).to_string(),
+            entries,
+            nodes,
+            depth,
+            errors,
+            warnings,
+            documents,
+            dialect,
+            repairs,
+            encoding: encoding.clone(),
+            compression,
+            entry,
+            schema,
+            duplicate_keys,
+            has_more,
+        };
+
+        self.documents.lock().unwrap().insert(
+            handle,
+            OpenDocument {
+                value: Arc::new(value),
+                format,
+                encoding,
+                compression,
+                protobuf: options.protobuf,
+                lossless_numbers,
+                has_more,
+            },
+        );
+        Ok(summary)
+    }
+
+    /// Returns the parsed value without holding the lock while callers work on it.
+    pub fn value(&self, handle: DocumentHandle) -> Result<Arc<Value>, String> {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(&handle)
+            .map(|doc| doc.value.clone())
+            .ok_or_else(|| format!("Unknown document handle: {}", handle))
+    }
+
+    fn with_document<T>(
+        &self,
+        handle: DocumentHandle,
+        f: impl FnOnce(&OpenDocument) -> T,
+    ) -> Result<T, String> {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(&handle)
+            .map(f)
+            .ok_or_else(|| format!("Unknown document handle: {}", handle))
+    }
+
+    /// The value at `pointer` as sent to the viewer, with lossless numbers as strings.
+    pub fn viewer_value(
+        &self,
+        handle: DocumentHandle,
+        pointer: Option<&str>,
+    ) -> Result<Value, String> {
+        let (value, lossless_numbers) =
+            self.with_document(handle, |doc| (doc.value.clone(), doc.lossless_numbers))?;
+        let mut part = match pointer {
+            None | Some("") => (*value).clone(),
+            Some(pointer) => value
+                .pointer(pointer)
+                .cloned()
+                .ok_or_else(|| format!("No value at {}", pointer))?,
+        };
+        if lossless_numbers {
+            numbers::stringify_unsafe_numbers(&mut part);
+        }
+        Ok(part)
+    }
+
+    /// Runs a jq filter over the document, returning the result as sent to the viewer.
+    pub fn query(
+        &self,
+        handle: DocumentHandle,
+        filter: &str,
+        sort_keys: bool,
+    ) -> Result<QueryResult, String> {
+        let (value, lossless_numbers) =
+            self.with_document(handle, |doc| (doc.value.clone(), doc.lossless_numbers))?;
+        let mut result = query_jq(filter, &value, sort_keys)?;
+        if lossless_numbers {
+            numbers::stringify_unsafe_numbers(&mut result.value);
+        }
+        Ok(result)
+    }
+
+    pub fn export(
+        &self,
+        handle: DocumentHandle,
+        target_format: &str,
+        options: &ConvertOptions,
+    ) -> Result<ConvertedDocument, String> {
+        let (value, has_more) =
+            self.with_document(handle, |doc| (doc.value.clone(), doc.has_more))?;
+        let mut converted = serialize_value(&value, target_format, options)?;
+        if has_more {
+            converted.warnings.push(format!(
+                "Only the first {} records of this Avro file are loaded and exported",
+                AVRO_PAGE_SIZE
+            ));
+        }
+        Ok(converted)
+    }
+
+    /// Writes the document to `path` in its original format, encoding and gzip or zstd
+    /// compression, unless the options ask for a different format or compression.
+    pub fn save(
+        &self,
+        handle: DocumentHandle,
+        path: &str,
+        target_format: Option<&str>,
+        options: &ConvertOptions,
+    ) -> Result<ConvertedDocument, String> {
+        let (value, format, text_encoding, original_compression, protobuf, has_more) = self
+            .with_document(handle, |doc| {
+                (
+                    doc.value.clone(),
+                    doc.format.clone(),
+                    doc.encoding.clone(),
+                    doc.compression,
+                    doc.protobuf.clone(),
+                    doc.has_more,
+                )
+            })?;
+        if has_more {
+            return Err(format!(
+                "Only the first {} records of this Avro file are loaded; saving would drop the rest",
+                AVRO_PAGE_SIZE
+            ));
+        }
+        let text_options = ConvertOptions {
+            compression: None,
+            protobuf: if options.protobuf.is_empty() {
+                protobuf
+            } else {
+                options.protobuf.clone()
+            },
+            ..options.clone()
+        };
+        let converted = serialize_value(&value, target_format.unwrap_or(&format), &text_options)?;
+        let mut bytes = if converted.base64 {
+            BASE64_STANDARD
+                .decode(&converted.output)
+                .map_err(|e| e.to_string())?
+        } else {
+            encoding::encode(&converted.output, &text_encoding)?
+        };
+        // A file picked out of a zip archive is saved on its own.
+        let method = options
+            .compression
+            .or(original_compression.filter(|c| *c != Compression::Zip));
+        if let Some(method) = method {
+            bytes = compression::compress(&bytes, method)?;
+        }
+        std::fs::write(path, bytes).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(converted)
+    }
+
+    pub fn close(&self, handle: DocumentHandle) -> bool {
+        self.documents.lock().unwrap().remove(&handle).is_some()
+    }
+}
+
+/// Reads and parses a file in the backend and keeps the result under a handle.
+#[command]
+pub fn open_document(
+    store: State<'_, DocumentStore>,
+    path: String,
+    format: Option<String>,
+    options: Option<ParseOptions>,
+) -> Result<DocumentSummary, ParseError> {
+    store.open(
+        &path,
+        format.as_deref().unwrap_or("auto"),
+        &options.unwrap_or_default(),
+    )
+}
+
+#[command]
+pub fn close_document(store: State<'_, DocumentStore>, handle: DocumentHandle) -> bool {
+    store.close(handle)
+}
+
+/// Returns the value of an open document, or the part of it at a JSON Pointer such as
+/// `/items/0`, so the UI can load large trees branch by branch.
+#[command]
+pub fn get_document_value(
+    store: State<'_, DocumentStore>,
+    handle: DocumentHandle,
+    pointer: Option<String>,
+) -> Result<Value, String> {
+    store.viewer_value(handle, pointer.as_deref())
+}
+
+#[command]
+pub fn query_document(
+    store: State<'_, DocumentStore>,
+    handle: DocumentHandle,
+    filter: String,
+    sort_keys: Option<bool>,
+) -> Result<QueryResult, String> {
+    store.query(handle, &filter, sort_keys.unwrap_or(false))
+}
+
+#[command]
+pub fn export_document(
+    store: State<'_, DocumentStore>,
+    handle: DocumentHandle,
+    target_format: String,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertedDocument, String> {
+    store.export(handle, &target_format, &options.unwrap_or_default())
+}
+
+/// Saves an open document, keeping the encoding it was read with.
+#[command]
+pub fn save_document(
+    store: State<'_, DocumentStore>,
+    handle: DocumentHandle,
+    path: String,
+    target_format: Option<String>,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertedDocument, String> {
+    store.save(
+        handle,
+        &path,
+        target_format.as_deref(),
+        &options.unwrap_or_default(),
+    )
+}
+
+/// A page of records from an Avro file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvroRecords {
+    /// The writer schema embedded in the file.
+    pub schema: Value,
+    pub records: Vec<Value>,
+    /// Index of the first record in `records`.
+    pub offset: u64,
+    pub has_more: bool,
+}
+
+const AVRO_PAGE_SIZE: usize = 1000;
+
+/// Reads `limit` records from an Avro file starting at record `offset`, streaming from disk so
+/// large files can be paged through without loading them whole.
+pub fn read_avro_page(path: &str, offset: u64, limit: usize) -> Result<AvroRecords, ParseError> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| ParseError::new("avro", ParseErrorKind::Io, format!("{}: {}", path, e)))?;
+    let mut reader = AvroReader::new(BufReader::new(file))?;
+    reader.skip_records(offset)?;
+    let records = reader.by_ref().take(limit).collect::<Result<Vec<_>, _>>()?;
+    Ok(AvroRecords {
+        schema: reader.schema().clone(),
+        records,
+        offset,
+        has_more: reader.has_next()?,
+    })
+}
+
+#[command]
+pub fn read_avro_records(
+    path: String,
+    offset: Option<u64>,
+    limit: Option<usize>,
+) -> Result<AvroRecords, ParseError> {
+    read_avro_page(&path, offset.unwrap_or(0), limit.unwrap_or(AVRO_PAGE_SIZE))
+}
+
+/// Lists the files in a zip archive on disk so the user can choose the `entry` to open.
+#[command]
+pub fn list_file_archive_entries(path: String) -> Result<Vec<ArchiveEntry>, String> {
+    let archive = std::fs::read(&path).map_err(|e| format!("{}: {}", path, e))?;
+    compression::list_zip_entries(&archive)
+}
+
+#[command]
+pub fn document_schema(
+    store: State<'_, DocumentStore>,
+    handle: DocumentHandle,
+) -> Result<Value, String> {
+    Ok(infer_schema(&*store.value(handle)?))
+}
+
+#[cfg(test)]
+#[path = "documents_test.rs"]
+mod documents_test;
@@ -0,0 +1,334 @@
+use super::*;
+use std::io::Write;
+
+fn write_temp_file(name: &str, content: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("jsonmap-documents-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let path = dir.join(name);
+    let mut file = std::fs::File::create(&path).expect("create temp file");
+    file.write_all(content.as_bytes()).expect("write temp file");
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_open_document_summary() {
+    let path = write_temp_file(
+        "summary.json",
+        r#"{"users": [{"name": "Alex"}, {"name": "Bob"}], "count": 2}"#,
+    );
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+
+    assert_eq!(summary.format, "json");
+    assert_eq!(summary.root_type, "object");
+    assert_eq!(summary.entries, 2);
+    assert_eq!(summary.nodes, 7);
+    assert_eq!(summary.depth, 3);
+    assert!(summary.bytes > 0);
+}
+
+#[test]
+fn test_open_document_uses_file_name_for_detection() {
+    let path = write_temp_file("config.toml", "name = 'test'");
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "toml");
+}
+
+#[test]
+fn test_open_missing_file() {
+    let store = DocumentStore::default();
+    let err = store
+        .open(
+            "/definitely/not/here.json",
+            "json",
+            &ParseOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Io);
+}
+
+#[test]
+fn test_handle_commands() {
+    let path = write_temp_file("handles.yaml", "items:\n  - id: 1\n  - id: 2\n");
+    let store = DocumentStore::default();
+    let handle = store
+        .open(&path, "yaml", &ParseOptions::default())
+        .unwrap()
+        .handle;
+
+    let value = store.value(handle).unwrap();
+    let value = &*value;
+    assert_eq!(value.pointer("/items/1/id").unwrap(), 2);
+
+    let result = query_jq("[.items[].id]", value, false).unwrap();
+    assert_eq!(result.value, serde_json::json!([1, 2]));
+
+    let converted = serialize_value(value, "json", &ConvertOptions::default()).unwrap();
+    assert!(converted.output.contains("\"items\""));
+
+    assert_eq!(infer_schema(value)["type"], "object");
+
+    assert!(store.close(handle));
+    assert!(store.value(handle).is_err());
+    assert!(!store.close(handle));
+}
+
+#[test]
+fn test_handles_are_unique() {
+    let path = write_temp_file("unique.json", "[]");
+    let store = DocumentStore::default();
+    let first = store.open(&path, "json", &ParseOptions::default()).unwrap();
+    let second = store.open(&path, "json", &ParseOptions::default()).unwrap();
+    assert_ne!(first.handle, second.handle);
+}
+
+#[test]
+fn test_save_keeps_original_encoding() {
+    let path = write_temp_file("latin1.csv", "");
+    std::fs::write(&path, b"name,city\nJos\xE9,M\xE1laga\n").unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "csv");
+    assert_eq!(summary.encoding.name, "windows-1252");
+    assert_eq!(
+        store.value(summary.handle).unwrap()[0]["name"],
+        serde_json::json!("José")
+    );
+
+    store
+        .save(summary.handle, &path, None, &ConvertOptions::default())
+        .unwrap();
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"name,city\nJos\xE9,M\xE1laga\n"
+    );
+}
+
+#[test]
+fn test_open_and_save_compressed() {
+    use crate::formats::compression::{compress, decompress, Compression};
+
+    let path = write_temp_file("dump.json.gz", "");
+    std::fs::write(
+        &path,
+        compress(b"{\"items\": [1, 2]}", Compression::Gzip).unwrap(),
+    )
+    .unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "json");
+    assert_eq!(summary.compression, Some(Compression::Gzip));
+
+    store
+        .save(summary.handle, &path, None, &ConvertOptions::default())
+        .unwrap();
+    let saved = decompress(std::fs::File::open(&path).unwrap(), None).unwrap();
+    assert_eq!(saved.compression, Some(Compression::Gzip));
+    assert_eq!(
+        serde_json::from_slice::<Value>(&saved.bytes).unwrap(),
+        serde_json::json!({"items": [1, 2]})
+    );
+}
+
+#[test]
+fn test_open_and_save_binary_format() {
+    let path = write_temp_file("state.cbor", "");
+    std::fs::write(&path, [0xa1, 0x61, b'n', 0x18, 0x64]).unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "cbor");
+    assert_eq!(
+        *store.value(summary.handle).unwrap(),
+        serde_json::json!({"n": 100})
+    );
+
+    let converted = store
+        .save(summary.handle, &path, None, &ConvertOptions::default())
+        .unwrap();
+    assert!(converted.base64);
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        [0xa1, 0x61, b'n', 0x18, 0x64]
+    );
+}
+
+#[test]
+fn test_save_keeps_plist_flavor() {
+    use crate::formats::plist::{to_binary_plist, BINARY_MAGIC};
+
+    let path = write_temp_file("Settings.plist", "");
+    std::fs::write(
+        &path,
+        to_binary_plist(&serde_json::json!({"n": 1})).unwrap(),
+    )
+    .unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "bplist");
+
+    store
+        .save(summary.handle, &path, None, &ConvertOptions::default())
+        .unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(BINARY_MAGIC));
+
+    store
+        .save(
+            summary.handle,
+            &path,
+            Some("plist"),
+            &ConvertOptions::default(),
+        )
+        .unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("<key>n</key>"));
+    let reopened = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(reopened.format, "plist");
+}
+
+/// A null-codec Avro file with one block per entry of `blocks`, holding records
+/// `{"id": n}` for each `n` in the block.
+fn avro_file(blocks: &[std::ops::Range<i64>]) -> Vec<u8> {
+    fn long(out: &mut Vec<u8>, n: i64) {
+        let mut n = ((n << 1) ^ (n >> 63)) as u64;
+        while n >= 0x80 {
+            out.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        out.push(n as u8);
+    }
+    let schema = r#"{"type":"record","name":"Row","fields":[{"name":"id","type":"long"}]}"#;
+    let mut out = b"Obj\x01".to_vec();
+    long(&mut out, 1);
+    long(&mut out, 11);
+    out.extend_from_slice(b"avro.schema");
+    long(&mut out, schema.len() as i64);
+    out.extend_from_slice(schema.as_bytes());
+    long(&mut out, 0);
+    out.extend_from_slice(&[7; 16]);
+    for ids in blocks {
+        let mut data = Vec::new();
+        ids.clone().for_each(|id| long(&mut data, id));
+        long(&mut out, ids.end - ids.start);
+        long(&mut out, data.len() as i64);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&[7; 16]);
+    }
+    out
+}
+
+#[test]
+fn test_open_avro_document() {
+    let path = write_temp_file("rows.avro", "");
+    std::fs::write(&path, avro_file(&[0..2, 2..3])).unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.format, "avro");
+    assert_eq!(summary.entries, 3);
+    assert_eq!(summary.schema.unwrap()["name"], "Row");
+    assert_eq!(
+        *store.value(summary.handle).unwrap(),
+        serde_json::json!([{"id": 0}, {"id": 1}, {"id": 2}])
+    );
+
+    // The header identifies Avro files whatever their name.
+    let unnamed = write_temp_file("rows.bin", "");
+    std::fs::write(&unnamed, avro_file(&[0..1, 1..2])).unwrap();
+    let summary = store
+        .open(&unnamed, "auto", &ParseOptions::default())
+        .unwrap();
+    assert_eq!(summary.format, "avro");
+}
+
+#[test]
+fn test_open_large_avro_document_loads_first_page() {
+    let path = write_temp_file("many.avro", "");
+    std::fs::write(&path, avro_file(&[0..600, 600..1200, 1200..1500])).unwrap();
+    let store = DocumentStore::default();
+    let summary = store.open(&path, "auto", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.entries, AVRO_PAGE_SIZE);
+    assert!(summary.has_more);
+    assert_eq!(
+        serde_json::to_value(&summary).unwrap()["hasMore"],
+        serde_json::json!(true)
+    );
+    assert_eq!(
+        store.value(summary.handle).unwrap()[999],
+        serde_json::json!({"id": 999})
+    );
+
+    let exported = store
+        .export(summary.handle, "json", &ConvertOptions::default())
+        .unwrap();
+    assert!(exported.warnings[0].contains("first 1000 records"));
+    let err = store
+        .save(summary.handle, &path, None, &ConvertOptions::default())
+        .unwrap_err();
+    assert!(err.contains("saving would drop the rest"), "{}", err);
+
+    let small = write_temp_file("few.avro", "");
+    std::fs::write(&small, avro_file(&[0..2, 2..3])).unwrap();
+    assert!(
+        !store
+            .open(&small, "avro", &ParseOptions::default())
+            .unwrap()
+            .has_more
+    );
+}
+
+#[test]
+fn test_read_avro_records_pages() {
+    let path = write_temp_file("pages.avro", "");
+    std::fs::write(&path, avro_file(&[0..4, 4..8, 8..10])).unwrap();
+
+    let page = read_avro_page(&path, 3, 4).unwrap();
+    assert_eq!(page.offset, 3);
+    assert_eq!(
+        page.records,
+        (3..7)
+            .map(|id| serde_json::json!({"id": id}))
+            .collect::<Vec<_>>()
+    );
+    assert!(page.has_more);
+    assert_eq!(page.schema["fields"][0]["name"], "id");
+
+    let last = read_avro_page(&path, 8, 4).unwrap();
+    assert_eq!(last.records.len(), 2);
+    assert!(!last.has_more);
+
+    let err = read_avro_page(&write_temp_file("not.avro", "{}"), 0, 10).unwrap_err();
+    assert_eq!(err.format, "avro");
+}
+
+#[test]
+fn test_lossless_numbers_are_kept_when_saving() {
+    let content = r#"{"id": 1311768467463790320, "n": 1}"#;
+    let path = write_temp_file("lossless.json", content);
+    let store = DocumentStore::default();
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..Default::default()
+    };
+    let summary = store.open(&path, "json", &options).unwrap();
+    assert!(summary.warnings.is_empty(), "{:?}", summary.warnings);
+
+    let viewed = store.viewer_value(summary.handle, None).unwrap();
+    assert_eq!(viewed["id"], "1311768467463790320");
+    assert_eq!(
+        store.viewer_value(summary.handle, Some("/id")).unwrap(),
+        "1311768467463790320"
+    );
+    let result = store.query(summary.handle, ".id", false).unwrap();
+    assert_eq!(result.value, "1311768467463790320");
+
+    let saved = write_temp_file("lossless-saved.json", "");
+    store
+        .save(summary.handle, &saved, None, &ConvertOptions::default())
+        .unwrap();
+    let text = std::fs::read_to_string(&saved).unwrap();
+    assert!(text.contains("\"id\": 1311768467463790320"), "{}", text);
+
+    // Without lossless numbers the viewer rounds the id, so the warning stays.
+    let summary = store.open(&path, "json", &ParseOptions::default()).unwrap();
+    assert_eq!(summary.warnings.len(), 1, "{:?}", summary.warnings);
+}
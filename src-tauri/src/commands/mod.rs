@@ -1,4 +1,6 @@
 pub mod converter;
+pub mod detect;
+pub mod documents;
 pub mod network;
 pub mod parser;
 pub mod schema;
@@ -1,27 +1,716 @@
+use crate::commands::detect::{format_from_filename, rank_formats, DETECTABLE_FORMATS};
+use crate::formats::compression::{self, ArchiveEntry, Compression};
+use crate::formats::csv::{parse_csv, CsvDialect, CsvOptions};
+use crate::formats::encoding::{self, TextEncoding};
+use crate::formats::positions::{self, DuplicateKey, NodePosition};
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::repair::{repair_json, Repair};
+use crate::formats::xml::{parse_xml, XmlOptions};
+use crate::formats::yaml::DocumentInfo;
+use crate::formats::{
+    avro, bson, cbor, dotenv, ejson, hcl, ini, jsonc, keys, logs, msgpack, ndjson, numbers, plist,
+    properties, protobuf, yaml,
+};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::Read;
 use tauri::command;
+use thiserror::Error;
 
-#[command]
-pub fn parse_content(content: String, format: String) -> Result<Value, String> {
+/// Maximum number of characters shown on each side of the error position in a snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    /// The input is not well-formed for the requested format.
+    Syntax,
+    /// The input ended before the document was complete.
+    Eof,
+    /// The input is well-formed but cannot be mapped to a JSON value.
+    Data,
+    /// The requested format is not supported.
+    Unsupported,
+    /// The file could not be read.
+    Io,
+}
+
+/// A position in the source text. `line` and `column` are 1-based, `column` counts characters
+/// and `offset` is a byte offset into the original content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl SourceLocation {
+    pub fn from_offset(content: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(content, offset.min(content.len()));
+        let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+        SourceLocation {
+            line: content[..line_start].matches('\n').count() + 1,
+            column: content[line_start..offset].chars().count() + 1,
+            offset,
+        }
+    }
+}
+
+/// A parse failure with enough location information for the editor to jump to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{}", self.describe())]
+pub struct ParseError {
+    pub format: String,
+    pub kind: ParseErrorKind,
+    pub message: String,
+    #[serde(flatten)]
+    pub location: Option<SourceLocation>,
+    pub snippet: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(format: &str, kind: ParseErrorKind, message: impl Into<String>) -> Self {
+        ParseError {
+            format: format.to_string(),
+            kind,
+            message: message.into(),
+            location: None,
+            snippet: None,
+        }
+    }
+
+    pub fn unsupported(format: &str) -> Self {
+        ParseError::new(
+            format,
+            ParseErrorKind::Unsupported,
+            format!("Unsupported format: {}", format),
+        )
+    }
+
+    /// Attaches the line, column and snippet for a byte offset into `content`.
+    pub fn at_offset(mut self, content: &str, offset: usize) -> Self {
+        let location = SourceLocation::from_offset(content, offset);
+        self.snippet = Some(snippet(content, location));
+        self.location = Some(location);
+        self
+    }
+
+    /// Attaches the position for a 1-based line and a 1-based byte column. Column 0 points at
+    /// the end of the previous line, which is how serde_json reports errors found after a newline.
+    pub fn at_line_column(self, content: &str, line: usize, column: usize) -> Self {
+        let line_start = if line <= 1 {
+            0
+        } else {
+            content
+                .match_indices('\n')
+                .nth(line - 2)
+                .map_or(content.len(), |(i, _)| i + 1)
+        };
+        let offset = if column == 0 {
+            line_start.saturating_sub(1)
+        } else {
+            line_start + column - 1
+        };
+        self.at_offset(content, offset)
+    }
+
+    fn describe(&self) -> String {
+        match self.location {
+            Some(location) => format!(
+                "{} error at line {}, column {}: {}",
+                self.format.to_uppercase(),
+                location.line,
+                location.column,
+                self.message
+            ),
+            None => self.message.clone(),
+        }
+    }
+}
+
+fn floor_char_boundary(content: &str, mut offset: usize) -> usize {
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Renders the offending line (clipped around the error) with a caret under the error position.
+fn snippet(content: &str, location: SourceLocation) -> String {
+    let offset = location.offset;
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |i| offset + i);
+    let before: Vec<char> = content[line_start..offset].chars().collect();
+    let after: String = content[offset..line_end]
+        .trim_end_matches('\r')
+        .chars()
+        .take(SNIPPET_CONTEXT)
+        .collect();
+    let skipped = before.len().saturating_sub(SNIPPET_CONTEXT);
+    let before: String = before[skipped..].iter().collect();
+
+    format!(
+        "{}{}\n{}^",
+        before,
+        after,
+        " ".repeat(before.chars().count())
+    )
+}
+
+pub(crate) fn json_error(content: &str, e: serde_json::Error) -> ParseError {
+    use serde_json::error::Category;
+
+    let kind = match e.classify() {
+        Category::Eof => ParseErrorKind::Eof,
+        Category::Data => ParseErrorKind::Data,
+        Category::Syntax | Category::Io => ParseErrorKind::Syntax,
+    };
+    let message = e.to_string();
+    // serde_json appends " at line X column Y", which is reported separately.
+    let message = match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message,
+    };
+    ParseError::new("json", kind, message).at_line_column(content, e.line(), e.column())
+}
+
+pub(crate) fn yaml_error(content: &str, e: serde_yaml::Error) -> ParseError {
+    match e.location() {
+        Some(location) => ParseError::new("yaml", ParseErrorKind::Syntax, e.to_string())
+            .at_offset(content, location.index()),
+        None => ParseError::new("yaml", ParseErrorKind::Data, e.to_string()),
+    }
+}
+
+fn toml_error(content: &str, e: toml::de::Error) -> ParseError {
+    let error = ParseError::new("toml", ParseErrorKind::Syntax, e.message().trim());
+    match e.span() {
+        Some(span) => error.at_offset(content, span.start),
+        None => error,
+    }
+}
+
+pub(crate) fn csv_error(content: &str, e: csv::Error) -> ParseError {
+    let kind = match e.kind() {
+        csv::ErrorKind::UnequalLengths { .. } => ParseErrorKind::Syntax,
+        _ => ParseErrorKind::Data,
+    };
+    let error = ParseError::new("csv", kind, e.to_string());
+    match e.position() {
+        Some(position) => error.at_offset(content, position.byte() as usize),
+        None => error,
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ParseOptions {
+    /// File name of the content, used as a hint when the format is `auto`.
+    pub filename: Option<String>,
+    /// Return numbers a double cannot hold as strings of their original text.
+    pub lossless_numbers: bool,
+    /// Sort object keys alphabetically instead of keeping document order.
+    pub sort_keys: bool,
+    /// Dialect and typing for CSV input.
+    pub csv: CsvOptions,
+    /// Mapping options for XML input.
+    pub xml: XmlOptions,
+    /// When JSON fails to parse, repair it as far as possible and report each fix.
+    pub repair: bool,
+    /// Encoding of byte input, detected from the bytes when unset.
+    pub encoding: Option<String>,
+    /// File to read from zip input; optional when the archive holds a single file.
+    pub entry: Option<String>,
+    /// Descriptor and message type for protobuf input.
+    pub protobuf: ProtobufOptions,
+    /// Report where each value is in the source, for JSON, JSONC, YAML and TOML input.
+    pub positions: bool,
+    /// Fail on duplicate object keys in JSON and YAML input instead of warning about them.
+    pub reject_duplicate_keys: bool,
+    /// Keep numbers as parsed even with `lossless_numbers`, which then only leaves out the
+    /// rounding warnings. Set for stored documents, whose numbers become strings on the way
+    /// to the viewer.
+    #[serde(skip)]
+    pub(crate) keep_numbers: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedDocument {
+    pub value: Value,
+    pub format: String,
+    pub confidence: f32,
+    /// Non-fatal errors, e.g. bad NDJSON lines. `value` holds everything that could be read.
+    pub errors: Vec<ParseError>,
+    pub warnings: Vec<String>,
+    /// Set when a YAML stream held several documents and `value` is the array of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<Vec<DocumentInfo>>,
+    /// Set for CSV input: the dialect it was read with and the type inferred for each column.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<CsvDialect>,
+    /// Fixes applied to broken JSON when parsed with `repair`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<Repair>,
+    /// Set for byte input: the encoding the text was decoded from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TextEncoding>,
+    /// Set for compressed byte input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    /// Set for zip input: the archive entry that was read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    /// Set for Avro input: the writer schema embedded in the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    /// Set when asked for with `positions`: the source span of every value, in document order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub positions: Option<Vec<NodePosition>>,
+    /// Keys repeated within an object of JSON or YAML input, of which only the last value is kept.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_keys: Vec<DuplicateKey>,
+}
+
+impl ParsedDocument {
+    fn new(format: &str) -> Self {
+        ParsedDocument {
+            value: Value::Null,
+            format: format.to_string(),
+            confidence: 1.0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            documents: None,
+            dialect: None,
+            repairs: Vec::new(),
+            encoding: None,
+            compression: None,
+            entry: None,
+            schema: None,
+            positions: None,
+            duplicate_keys: Vec::new(),
+        }
+    }
+}
+
+/// Returns the canonical name of a format, or `None` when the parser does not support it.
+pub fn canonical_format(format: &str) -> Option<&'static str> {
     match format.to_lowercase().as_str() {
-        "json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
-        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
-        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        "auto" => Some("auto"),
+        "json" => Some("json"),
+        "jsonc" => Some("jsonc"),
+        "json5" => Some("json5"),
+        "ndjson" | "jsonl" => Some("ndjson"),
+        "logs" | "log" => Some("logs"),
+        "yaml" | "yml" => Some("yaml"),
+        "toml" => Some("toml"),
+        "csv" => Some("csv"),
+        "xml" => Some("xml"),
+        "msgpack" | "messagepack" => Some("msgpack"),
+        "cbor" => Some("cbor"),
+        "bson" => Some("bson"),
+        "ejson" | "extjson" => Some("ejson"),
+        "plist" => Some("plist"),
+        "bplist" => Some("bplist"),
+        "ini" | "cfg" => Some("ini"),
+        "env" | "dotenv" => Some("env"),
+        "properties" => Some("properties"),
+        "hcl" | "tf" | "terraform" | "tfvars" => Some("hcl"),
+        "protobuf" | "pb" => Some("protobuf"),
+        "avro" => Some("avro"),
+        _ => None,
+    }
+}
+
+/// Formats stored as bytes rather than text. Passed as a string, their content is base64.
+pub fn is_binary_format(format: &str) -> bool {
+    matches!(
+        canonical_format(format),
+        Some("msgpack" | "cbor" | "bson" | "bplist" | "protobuf" | "avro")
+    )
+}
+
+fn parse_binary(
+    bytes: &[u8],
+    format: &str,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseError> {
+    let mut doc = ParsedDocument::new(format);
+    doc.value = match format {
+        "msgpack" => msgpack::parse_msgpack(bytes)?,
+        "cbor" => cbor::parse_cbor(bytes)?,
+        "bson" => bson::parse_bson(bytes)?,
+        "bplist" => plist::parse_binary_plist(bytes)?,
+        "protobuf" => protobuf::parse_protobuf(bytes, &options.protobuf)?,
+        "avro" => {
+            let (records, schema) = avro::parse_avro(bytes)?;
+            doc.schema = Some(schema);
+            records
+        }
+        _ => return Err(ParseError::unsupported(format)),
+    };
+    Ok(doc)
+}
+
+fn parse_as(
+    content: &str,
+    format: &str,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseError> {
+    let mut doc = ParsedDocument::new(format);
+    doc.value = match format {
+        "json" => serde_json::from_str(content).map_err(|e| json_error(content, e))?,
+        "jsonc" => jsonc::parse_jsonc(content)?,
+        "json5" => jsonc::parse_json5(content)?,
+        "ndjson" => ndjson::parse_ndjson(content, &mut doc.errors)?,
+        "logs" => logs::parse_logs(content),
+        "yaml" => {
+            let (value, documents) = yaml::parse_yaml(content)?;
+            doc.documents = documents;
+            value
+        }
+        "toml" => toml::from_str(content).map_err(|e| toml_error(content, e))?,
         "csv" => {
-            let mut reader = csv::ReaderBuilder::new()
-                .has_headers(true)
-                .from_reader(content.as_bytes());
-
-            let mut data = Vec::new();
-            for result in reader.deserialize() {
-                let record: serde_json::Map<String, Value> = result.map_err(|e| e.to_string())?;
-                data.push(Value::Object(record));
+            let (value, dialect) = parse_csv(content, &options.csv)?;
+            doc.dialect = Some(dialect);
+            value
+        }
+        "xml" => parse_xml(content, &options.xml)?,
+        "ejson" => {
+            ejson::from_extended_json(serde_json::from_str(content).map_err(|e| ParseError {
+                format: "ejson".to_string(),
+                ..json_error(content, e)
+            })?)
+        }
+        "plist" => plist::parse_xml_plist(content)?,
+        "ini" => ini::parse_ini(content)?,
+        "env" => dotenv::parse_env(content)?,
+        "properties" => properties::parse_properties(content)?,
+        "hcl" => hcl::parse_hcl(content)?,
+        format if is_binary_format(format) => {
+            let bytes = BASE64_STANDARD
+                .decode(content.split_whitespace().collect::<String>())
+                .map_err(|e| {
+                    ParseError::new(
+                        format,
+                        ParseErrorKind::Syntax,
+                        format!("{} content must be base64-encoded: {}", format, e),
+                    )
+                })?;
+            return parse_binary(&bytes, format, options);
+        }
+        _ => return Err(ParseError::unsupported(format)),
+    };
+    Ok(doc)
+}
+
+/// Confidence above which a failed best guess is reported instead of trying weaker candidates,
+/// so a broken JSON object surfaces its syntax error rather than parsing as YAML.
+const STRONG_DETECTION: f32 = 0.8;
+
+/// Tries the formats that look plausible for the content, best guess first. When none of them
+/// parse, the error of the best guess is returned since it is the most likely intent.
+fn parse_auto(content: &str, options: &ParseOptions) -> Result<ParsedDocument, ParseError> {
+    // Formats without content heuristics are only picked by file name.
+    let hint = options.filename.as_deref().and_then(format_from_filename);
+    if let Some(format) = hint.filter(|f| !DETECTABLE_FORMATS.contains(f) && !is_binary_format(f)) {
+        return parse_as(content, format, options);
+    }
+
+    let mut first_error = None;
+    for candidate in rank_formats(content, options.filename.as_deref()) {
+        let mut result = parse_as(content, &candidate.format, options);
+        // Config files named `.json` often carry comments or trailing commas.
+        if candidate.format == "json" && result.is_err() {
+            if let Some(doc) = ["jsonc", "json5"]
+                .iter()
+                .find_map(|format| parse_as(content, format, options).ok())
+            {
+                result = Ok(doc);
+            }
+        }
+
+        match result {
+            Ok(doc) => {
+                return Ok(ParsedDocument {
+                    confidence: candidate.confidence,
+                    ..doc
+                })
+            }
+            Err(e) if candidate.confidence >= STRONG_DETECTION => {
+                return Err(first_error.unwrap_or(e));
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| ParseError::unsupported("auto")))
+}
+
+pub fn parse_with_options(
+    content: &str,
+    format: &str,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseError> {
+    let parsed = match canonical_format(format) {
+        Some("auto") => parse_auto(content, options),
+        Some(format) => parse_as(content, format, options),
+        None => return Err(ParseError::unsupported(format)),
+    };
+    let doc = match parsed {
+        Ok(doc) => doc,
+        Err(error) if options.repair && error.format == "json" => {
+            let (value, repairs) = repair_json(content).ok_or(error)?;
+            ParsedDocument {
+                value,
+                repairs,
+                ..ParsedDocument::new("json")
             }
-            Ok(Value::Array(data))
         }
-        "xml" => quick_xml::de::from_str(&content).map_err(|e| e.to_string()),
-        _ => Err(format!("Unsupported format: {}", format)),
+        Err(error) => return Err(error),
+    };
+    let mut doc = finish(doc, options);
+    // Repaired JSON no longer matches the source.
+    if doc.repairs.is_empty() {
+        scan_source(&mut doc, content, options)?;
     }
+    Ok(doc)
+}
+
+/// Formats items as `2, 5 and 9`.
+fn and_list(items: Vec<String>) -> String {
+    match items.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => items.join(""),
+    }
+}
+
+/// Where a duplicate key occurs: `on lines 2 and 5`, `on line 1, columns 2 and 9` when all
+/// occurrences share a line, or `at 1:2, 1:9 and 3:1` when only some do.
+fn occurrences(duplicate: &DuplicateKey) -> String {
+    let locations = &duplicate.locations;
+    let lines: HashSet<usize> = locations.iter().map(|location| location.line).collect();
+    if lines.len() == locations.len() {
+        let lines = locations.iter().map(|l| l.line.to_string()).collect();
+        format!("on lines {}", and_list(lines))
+    } else if lines.len() == 1 {
+        let columns = locations.iter().map(|l| l.column.to_string()).collect();
+        format!(
+            "on line {}, columns {}",
+            locations[0].line,
+            and_list(columns)
+        )
+    } else {
+        let places = locations
+            .iter()
+            .map(|l| format!("{}:{}", l.line, l.column))
+            .collect();
+        format!("at {}", and_list(places))
+    }
+}
+
+/// Looks for duplicate keys in JSON and YAML input, and records where each value is when asked.
+fn scan_source(
+    doc: &mut ParsedDocument,
+    content: &str,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
+    // Blanking out comments and trailing commas keeps every offset in place.
+    let json = match doc.format.as_str() {
+        "json" | "ejson" => Some(Cow::Borrowed(content)),
+        "jsonc" => Some(Cow::Owned(jsonc::strip_jsonc(content))),
+        _ => None,
+    };
+    doc.duplicate_keys = match (&json, doc.format.as_str()) {
+        (Some(json), _) => positions::json_duplicate_keys(json),
+        (None, "yaml") => positions::yaml_duplicate_keys(content),
+        _ => Vec::new(),
+    };
+    if let (Some(duplicate), true) = (doc.duplicate_keys.first(), options.reject_duplicate_keys) {
+        return Err(ParseError::new(
+            &doc.format,
+            ParseErrorKind::Data,
+            format!(
+                "Duplicate key {} {}",
+                positions::json_path(&duplicate.path),
+                occurrences(duplicate)
+            ),
+        )
+        .at_offset(content, duplicate.locations[1].offset));
+    }
+    for duplicate in &doc.duplicate_keys {
+        doc.warnings.push(format!(
+            "Duplicate key {} {}; only the last value is kept",
+            positions::json_path(&duplicate.path),
+            occurrences(duplicate)
+        ));
+    }
+
+    if options.positions {
+        doc.positions = match (&json, doc.format.as_str()) {
+            (Some(json), "json" | "jsonc") => Some(positions::json_positions(json)),
+            (None, "yaml") => Some(positions::yaml_positions(content)),
+            (None, "toml") => Some(positions::toml_positions(content)),
+            _ => None,
+        };
+    }
+    Ok(())
+}
+
+fn finish(mut doc: ParsedDocument, options: &ParseOptions) -> ParsedDocument {
+    if options.sort_keys {
+        keys::sort_keys(&mut doc.value);
+    }
+    if options.lossless_numbers {
+        if !options.keep_numbers {
+            numbers::stringify_unsafe_numbers(&mut doc.value);
+        }
+    } else {
+        doc.warnings.extend(numbers::viewer_warnings(&doc.value));
+    }
+    doc
+}
+
+/// Reads the first `limit` records of an Avro file without decoding the rest. Returns the
+/// document and whether more records follow.
+pub fn parse_avro_head(
+    reader: impl Read,
+    limit: usize,
+    options: &ParseOptions,
+) -> Result<(ParsedDocument, bool), ParseError> {
+    let mut avro = avro::AvroReader::new(reader)?;
+    let records = avro.by_ref().take(limit).collect::<Result<Vec<_>, _>>()?;
+    let has_more = avro.has_next()?;
+    let doc = ParsedDocument {
+        value: Value::Array(records),
+        schema: Some(avro.schema().clone()),
+        ..ParsedDocument::new("avro")
+    };
+    Ok((finish(doc, options), has_more))
+}
+
+/// Reads raw file bytes, decompressing and decoding them as needed (see
+/// `formats::compression` and `formats::encoding`), and parses the text. Binary formats are
+/// parsed from the bytes directly; with `auto` they are recognized by the file name.
+pub fn parse_reader_with_options(
+    reader: impl Read,
+    format: &str,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseError> {
+    let decompressed = compression::decompress(reader, options.entry.as_deref())
+        .map_err(|e| ParseError::new(format, ParseErrorKind::Data, e))?;
+
+    // Inside an archive, the entry name says more about the format than the archive name.
+    let entry_options;
+    let options = match &decompressed.entry {
+        Some(entry) => {
+            entry_options = ParseOptions {
+                filename: Some(entry.clone()),
+                ..options.clone()
+            };
+            &entry_options
+        }
+        None => options,
+    };
+
+    let binary_format = match canonical_format(format) {
+        Some("auto") => options.filename.as_deref().and_then(format_from_filename),
+        other => other,
+    };
+    // `.plist` files come in an XML and a binary flavor, and Avro files carry a magic header.
+    let binary_format = match binary_format {
+        Some("plist") if decompressed.bytes.starts_with(plist::BINARY_MAGIC) => Some("bplist"),
+        None if canonical_format(format) == Some("auto")
+            && decompressed.bytes.starts_with(avro::MAGIC) =>
+        {
+            Some("avro")
+        }
+        other => other,
+    }
+    .filter(|format| is_binary_format(format));
+    if let Some(binary_format) = binary_format {
+        let doc = parse_binary(&decompressed.bytes, binary_format, options)?;
+        return Ok(ParsedDocument {
+            compression: decompressed.compression,
+            entry: decompressed.entry,
+            ..finish(doc, options)
+        });
+    }
+
+    let (content, text_encoding, had_errors) =
+        encoding::decode(&decompressed.bytes, options.encoding.as_deref())
+            .map_err(|e| ParseError::new(format, ParseErrorKind::Unsupported, e))?;
+    let mut doc = parse_with_options(&content, format, options)?;
+    if had_errors {
+        doc.warnings.push(format!(
+            "Some bytes are not valid {} and were replaced with U+FFFD",
+            text_encoding.name
+        ));
+    }
+    doc.encoding = Some(text_encoding);
+    doc.compression = decompressed.compression;
+    doc.entry = decompressed.entry;
+    Ok(doc)
+}
+
+pub fn parse_bytes_with_options(
+    bytes: &[u8],
+    format: &str,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseError> {
+    parse_reader_with_options(bytes, format, options)
+}
+
+/// Parses content into its value alone. Content with bad NDJSON lines fails on the first one;
+/// `parse_document` reads the good lines and reports every bad one.
+#[command]
+pub fn parse_content(content: String, format: String) -> Result<Value, ParseError> {
+    let mut doc = parse_with_options(&content, &format, &ParseOptions::default())?;
+    if !doc.errors.is_empty() {
+        return Err(doc.errors.swap_remove(0));
+    }
+    Ok(doc.value)
+}
+
+/// Parses content and reports which format was used, which is what `auto` callers need.
+#[command]
+pub fn parse_document(
+    content: String,
+    format: String,
+    options: Option<ParseOptions>,
+) -> Result<ParsedDocument, ParseError> {
+    parse_with_options(&content, &format, &options.unwrap_or_default())
+}
+
+/// Like `parse_document` for raw file bytes in any supported encoding.
+#[command]
+pub fn parse_bytes(
+    bytes: Vec<u8>,
+    format: String,
+    options: Option<ParseOptions>,
+) -> Result<ParsedDocument, ParseError> {
+    parse_bytes_with_options(&bytes, &format, &options.unwrap_or_default())
+}
+
+/// Lists the files in zip bytes so the user can choose the `entry` to parse.
+#[command]
+pub fn list_archive_entries(bytes: Vec<u8>) -> Result<Vec<ArchiveEntry>, String> {
+    compression::list_zip_entries(&bytes)
+}
+
+/// Lists the message types in a descriptor set or `.proto` sources so the user can choose the
+/// `messageType` to decode with.
+#[command]
+pub fn protobuf_message_types(options: ProtobufOptions) -> Result<Vec<String>, String> {
+    protobuf::message_types(&options)
 }
 
 #[cfg(test)]
@@ -18,12 +18,9 @@ fn test_parse_yaml() {
 
 #[test]
 fn test_parse_xml() {
-    // Quick-XML usually produces a structure reflecting the XML.
-    // For <root><name>test</name></root>, it might differ based on config.
-    // Adjusted expectation based on simple parsing.
     let content = "<root><name>test</name></root>";
-    let result = parse_content(content.to_string(), "xml".to_string());
-    assert!(result.is_ok());
+    let result = parse_content(content.to_string(), "xml".to_string()).unwrap();
+    assert_eq!(result["root"]["name"], "test");
 }
 
 #[test]
@@ -32,13 +29,7 @@ fn test_parse_csv() {
     let result = parse_content(content.to_string(), "csv".to_string()).unwrap();
     assert!(result.is_array());
     assert_eq!(result[0]["name"], "test");
-    // CSV might parse as number if it looks like one, depending on configuration
-    // Verify it is equal to 123 (number) or "123" (string) depending on output
-    if result[0]["value"].is_number() {
-        assert_eq!(result[0]["value"], 123);
-    } else {
-        assert_eq!(result[0]["value"], "123");
-    }
+    assert_eq!(result[0]["value"], 123);
 }
 
 #[test]
@@ -54,3 +45,531 @@ fn test_invalid_format() {
     let result = parse_content("{}".to_string(), "unknown".to_string());
     assert!(result.is_err());
 }
+
+#[test]
+fn test_json_error_position() {
+    let content = "{\n  \"name\": \"test\",\n  \"value\": tru\n}";
+    let err = parse_content(content.to_string(), "json".to_string()).unwrap_err();
+    assert_eq!(err.format, "json");
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+    assert_eq!(err.location.unwrap().line, 3);
+    assert_eq!(err.message, "expected ident");
+    assert!(err.snippet.unwrap().ends_with('^'));
+}
+
+#[test]
+fn test_json_error_eof() {
+    let content = "{\"name\": \"test\"";
+    let err = parse_content(content.to_string(), "json".to_string()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Eof);
+    assert_eq!(err.location.unwrap().line, 1);
+    assert_eq!(err.location.unwrap().offset, content.len() - 1);
+}
+
+#[test]
+fn test_yaml_error_position() {
+    let content = "name: test\nitems: [1,\n  value: : x";
+    let err = parse_content(content.to_string(), "yaml".to_string()).unwrap_err();
+    assert_eq!(err.format, "yaml");
+    assert_eq!(err.location.unwrap().line, 3);
+    assert!(err.snippet.is_some());
+}
+
+#[test]
+fn test_toml_error_position() {
+    let content = "name = 'test'\nvalue = = 123";
+    let err = parse_content(content.to_string(), "toml".to_string()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+    assert_eq!(err.location.unwrap().line, 2);
+    assert_eq!(err.location.unwrap().column, 9);
+    assert_eq!(err.location.unwrap().offset, 22);
+    assert_eq!(err.snippet.unwrap(), "value = = 123\n        ^");
+}
+
+#[test]
+fn test_csv_error_position() {
+    let content = "name,value\ntest,123\nbroken\n";
+    let err = parse_content(content.to_string(), "csv".to_string()).unwrap_err();
+    assert_eq!(err.format, "csv");
+    assert_eq!(err.location.unwrap().line, 3);
+    assert_eq!(err.location.unwrap().column, 1);
+}
+
+#[test]
+fn test_xml_error_position() {
+    let content = "<root>\n  <name>test</nam>\n</root>";
+    let err = parse_content(content.to_string(), "xml".to_string()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+    assert_eq!(err.location.unwrap().line, 2);
+}
+
+#[test]
+fn test_error_snippet_is_clipped_on_long_lines() {
+    let content = format!("[{}tru]", "1, ".repeat(1000));
+    let err = parse_content(content, "json".to_string()).unwrap_err();
+    let snippet = err.snippet.unwrap();
+    let first_line = snippet.lines().next().unwrap();
+    assert!(first_line.chars().count() <= 2 * SNIPPET_CONTEXT);
+    assert!(first_line.contains("tru"));
+}
+
+#[test]
+fn test_unsupported_format_error() {
+    let err = parse_content("{}".to_string(), "unknown".to_string()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Unsupported);
+    assert_eq!(err.to_string(), "Unsupported format: unknown");
+}
+
+#[test]
+fn test_parse_auto_detects_format() {
+    let doc = parse_document(
+        "name: test\nvalue: 123".to_string(),
+        "auto".to_string(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(doc.format, "yaml");
+    assert!(doc.confidence > 0.0);
+    assert_eq!(doc.value["value"], 123);
+
+    let doc = parse_document("name,value\ntest,123".to_string(), "auto".to_string(), None).unwrap();
+    assert_eq!(doc.format, "csv");
+    assert_eq!(doc.value[0]["name"], "test");
+}
+
+#[test]
+fn test_parse_auto_uses_filename_hint() {
+    let options = ParseOptions {
+        filename: Some("Cargo.toml".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_document(
+        "name = 'test'".to_string(),
+        "auto".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(doc.format, "toml");
+    assert_eq!(doc.value["name"], "test");
+}
+
+#[test]
+fn test_parse_auto_falls_back_when_best_guess_fails() {
+    // Starts like a JSON number but is only valid as a YAML plain scalar.
+    let doc = parse_document("2024 release".to_string(), "auto".to_string(), None).unwrap();
+    assert_eq!(doc.format, "yaml");
+    assert_eq!(doc.value, "2024 release");
+}
+
+#[test]
+fn test_parse_auto_reports_best_guess_error() {
+    let err = parse_content("{\"name\": }".to_string(), "auto".to_string()).unwrap_err();
+    assert_eq!(err.format, "json");
+    assert!(err.location.is_some());
+}
+
+#[test]
+fn test_parse_explicit_format_reports_canonical_name() {
+    let doc = parse_document("a: 1".to_string(), "YML".to_string(), None).unwrap();
+    assert_eq!(doc.format, "yaml");
+    assert_eq!(doc.confidence, 1.0);
+}
+
+#[test]
+fn test_parse_jsonc_and_json5() {
+    let value = parse_content("{\"a\": 1, // note\n}".to_string(), "jsonc".to_string()).unwrap();
+    assert_eq!(value["a"], 1);
+    let value = parse_content("{a: 'b'}".to_string(), "json5".to_string()).unwrap();
+    assert_eq!(value["a"], "b");
+}
+
+#[test]
+fn test_parse_auto_accepts_commented_json() {
+    let options = ParseOptions {
+        filename: Some("tsconfig.json".to_string()),
+        ..Default::default()
+    };
+    let content = "{\n  // strict mode\n  \"strict\": true,\n}";
+    let doc = parse_document(content.to_string(), "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "jsonc");
+    assert_eq!(doc.value["strict"], true);
+}
+
+#[test]
+fn test_parse_ndjson_collects_line_errors() {
+    let content = "{\"id\": 1}\n{broken}\n{\"id\": 3}";
+    let doc = parse_document(content.to_string(), "jsonl".to_string(), None).unwrap();
+    assert_eq!(doc.format, "ndjson");
+    assert_eq!(doc.value.as_array().unwrap().len(), 2);
+    assert_eq!(doc.errors.len(), 1);
+    assert_eq!(doc.errors[0].location.unwrap().line, 2);
+}
+
+#[test]
+fn test_parse_content_fails_on_bad_ndjson_lines() {
+    let content = "{\"id\": 1}\n{\"id\": 2}\n{broken}\n";
+    let err = parse_content(content.to_string(), "ndjson".to_string()).unwrap_err();
+    assert_eq!(err.format, "ndjson");
+    assert_eq!(err.location.unwrap().line, 3);
+
+    let result = parse_content("{\"id\": 1}\n".to_string(), "ndjson".to_string()).unwrap();
+    assert_eq!(result, serde_json::json!([{"id": 1}]));
+}
+
+#[test]
+fn test_parse_multi_document_yaml() {
+    let content = "name: first\n---\nname: second\n";
+    let doc = parse_document(content.to_string(), "yaml".to_string(), None).unwrap();
+    assert_eq!(doc.value[1]["name"], "second");
+    let documents = doc.documents.unwrap();
+    assert_eq!(documents[1].line, 2);
+}
+
+#[test]
+fn test_parse_keeps_big_numbers_exact() {
+    let content = r#"{"id": 1311768467463790320, "amount": 0.1000000000000000055, "n": 1}"#;
+    let doc = parse_document(content.to_string(), "json".to_string(), None).unwrap();
+    assert_eq!(doc.value["id"].to_string(), "1311768467463790320");
+    assert_eq!(doc.warnings.len(), 2);
+    assert!(doc.warnings[0].contains("$.id"));
+
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..Default::default()
+    };
+    let doc = parse_document(content.to_string(), "json".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.value["id"], "1311768467463790320");
+    assert_eq!(doc.value["amount"], "0.1000000000000000055");
+    assert_eq!(doc.value["n"], 1);
+    assert!(doc.warnings.is_empty());
+}
+
+#[test]
+fn test_parse_preserves_key_order() {
+    let content = "zeta: 1\nalpha: 2\nmid: 3";
+    let value = parse_content(content.to_string(), "yaml".to_string()).unwrap();
+    let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["zeta", "alpha", "mid"]);
+
+    let options = ParseOptions {
+        sort_keys: true,
+        ..Default::default()
+    };
+    let doc = parse_document(content.to_string(), "yaml".to_string(), Some(options)).unwrap();
+    let keys: Vec<&String> = doc.value.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["alpha", "mid", "zeta"]);
+}
+
+#[test]
+fn test_parse_document_with_csv_options() {
+    let options = ParseOptions {
+        csv: CsvOptions {
+            delimiter: Some('\t'),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let doc = parse_document(
+        "name\tvalue\ntest\t1.5".to_string(),
+        "csv".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(doc.value[0]["value"], 1.5);
+    let dialect = doc.dialect.unwrap();
+    assert_eq!(dialect.delimiter, '\t');
+    assert!(dialect.has_headers);
+}
+
+#[test]
+fn test_parse_document_repairs_json() {
+    let options = ParseOptions {
+        repair: true,
+        ..Default::default()
+    };
+    let doc = parse_document(
+        "{'name': 'test', 'active': True".to_string(),
+        "auto".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(doc.format, "json");
+    assert_eq!(doc.value["active"], true);
+    assert_eq!(doc.repairs.len(), 5);
+
+    let err = parse_document("{'name': 'test'".to_string(), "json".to_string(), None).unwrap_err();
+    assert_eq!(err.format, "json");
+}
+
+#[test]
+fn test_parse_bytes_reports_encoding() {
+    let bytes: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain("name = 'Zoë'".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let options = ParseOptions {
+        filename: Some("config.toml".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_bytes(bytes, "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "toml");
+    assert_eq!(doc.value["name"], "Zoë");
+    let encoding = doc.encoding.unwrap();
+    assert_eq!(encoding.name, "UTF-16LE");
+    assert!(encoding.bom);
+}
+
+#[test]
+fn test_parse_bytes_decompresses() {
+    use crate::formats::compression::{compress, Compression};
+
+    let bytes = compress(b"{\"a\": 1}\n{\"a\": 2}\n", Compression::Gzip).unwrap();
+    let options = ParseOptions {
+        filename: Some("events.ndjson.gz".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_bytes(bytes, "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "ndjson");
+    assert_eq!(doc.value[1]["a"], 2);
+    assert_eq!(doc.compression, Some(Compression::Gzip));
+}
+
+#[test]
+fn test_parse_binary_formats_from_base64() {
+    // {"a": 1} in MessagePack and CBOR.
+    let result = parse_content("gaFhAQ==".to_string(), "msgpack".to_string()).unwrap();
+    assert_eq!(result, serde_json::json!({"a": 1}));
+    let result = parse_content("oWFhAQ==".to_string(), "cbor".to_string()).unwrap();
+    assert_eq!(result, serde_json::json!({"a": 1}));
+
+    let err = parse_content("{\"a\": 1}".to_string(), "msgpack".to_string()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+}
+
+#[test]
+fn test_parse_bytes_reads_binary_formats_by_filename() {
+    let options = ParseOptions {
+        filename: Some("state.msgpack".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_bytes(
+        vec![0x81, 0xa1, b'a', 0xc4, 0x01, 0xff],
+        "auto".to_string(),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(doc.format, "msgpack");
+    assert_eq!(doc.value, serde_json::json!({"a": {"$bytes": "/w=="}}));
+    assert_eq!(doc.encoding, None);
+}
+
+#[test]
+fn test_parse_extended_json_and_bson() {
+    let content = r#"{"_id": {"$oid": "6553f1000000000000000001"}, "n": {"$numberLong": "9"}}"#;
+    let doc = parse_document(content.to_string(), "ejson".to_string(), None).unwrap();
+    assert_eq!(doc.format, "ejson");
+    assert_eq!(
+        doc.value,
+        serde_json::json!({"_id": {"$oid": "6553f1000000000000000001"}, "n": 9})
+    );
+    let err = parse_content("{".to_string(), "ejson".to_string()).unwrap_err();
+    assert_eq!(err.format, "ejson");
+
+    let options = ParseOptions {
+        filename: Some("users.bson".to_string()),
+        ..Default::default()
+    };
+    let bytes = vec![12, 0, 0, 0, 0x10, b'n', 0, 9, 0, 0, 0, 0];
+    let doc = parse_bytes(bytes, "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "bson");
+    assert_eq!(doc.value, serde_json::json!({"n": 9}));
+}
+
+#[test]
+fn test_parse_plist_flavors() {
+    let content = r#"<?xml version="1.0"?><!DOCTYPE plist><plist version="1.0"><dict><key>a</key><integer>1</integer></dict></plist>"#;
+    let doc = parse_document(content.to_string(), "auto".to_string(), None).unwrap();
+    assert_eq!(doc.format, "plist");
+    assert_eq!(doc.value, serde_json::json!({"a": 1}));
+
+    let binary = crate::formats::plist::to_binary_plist(&doc.value).unwrap();
+    let options = ParseOptions {
+        filename: Some("Info.plist".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_bytes(binary, "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "bplist");
+    assert_eq!(doc.value, serde_json::json!({"a": 1}));
+}
+
+#[test]
+fn test_parse_config_formats_by_filename() {
+    let parse = |content: &str, filename: &str| {
+        let options = ParseOptions {
+            filename: Some(filename.to_string()),
+            ..Default::default()
+        };
+        parse_document(content.to_string(), "auto".to_string(), Some(options)).unwrap()
+    };
+    let doc = parse("[core]\nbare = false\n", "config.ini");
+    assert_eq!(doc.format, "ini");
+    assert_eq!(doc.value, serde_json::json!({"core": {"bare": "false"}}));
+
+    let doc = parse("PORT=8080\n", "app/.env.local");
+    assert_eq!(doc.format, "env");
+    assert_eq!(doc.value, serde_json::json!({"PORT": "8080"}));
+
+    let doc = parse("server.port=8080\n", "application.properties");
+    assert_eq!(doc.format, "properties");
+    assert_eq!(doc.value, serde_json::json!({"server.port": "8080"}));
+
+    let err = parse_content("[open".to_string(), "ini".to_string()).unwrap_err();
+    assert_eq!(err.format, "ini");
+}
+
+#[test]
+fn test_parse_protobuf_by_filename_and_descriptor() {
+    use crate::formats::protobuf::ProtobufOptions;
+    use std::collections::BTreeMap;
+
+    let bytes = vec![0x08, 0x2a, 0x12, 0x02, b'h', b'i'];
+    let options = ParseOptions {
+        filename: Some("capture.pb".to_string()),
+        ..Default::default()
+    };
+    let doc = parse_bytes(bytes.clone(), "auto".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.format, "protobuf");
+    assert_eq!(doc.value[0]["value"], 42);
+    assert_eq!(doc.value[1]["value"], "hi");
+
+    let options = ParseOptions {
+        protobuf: ProtobufOptions {
+            proto_files: BTreeMap::from([(
+                "ping.proto".to_string(),
+                "syntax = \"proto3\";\nmessage Ping { int64 seq = 1; string note = 2; }\n"
+                    .to_string(),
+            )]),
+            message_type: Some("Ping".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let doc = parse_bytes(bytes, "protobuf".to_string(), Some(options)).unwrap();
+    assert_eq!(doc.value, serde_json::json!({"seq": "42", "note": "hi"}));
+}
+
+#[test]
+fn test_parse_document_positions() {
+    let options = ParseOptions {
+        positions: true,
+        ..Default::default()
+    };
+    let content = "// settings\n{\"a\": [1, 2,],}";
+    let doc = parse_document(
+        content.to_string(),
+        "jsonc".to_string(),
+        Some(options.clone()),
+    )
+    .unwrap();
+    assert_eq!(doc.format, "jsonc");
+    let positions = doc.positions.unwrap();
+    assert_eq!(positions.len(), 4);
+    assert_eq!(positions[3].start.line, 2);
+    assert_eq!(positions[3].start.column, 11);
+
+    let doc = parse_document(
+        "a = 1".to_string(),
+        "toml".to_string(),
+        Some(options.clone()),
+    )
+    .unwrap();
+    assert_eq!(doc.positions.unwrap().len(), 2);
+
+    let doc = parse_document("a,b\n1,2".to_string(), "csv".to_string(), Some(options)).unwrap();
+    assert!(doc.positions.is_none());
+
+    let doc = parse_document("{\"a\": 1}".to_string(), "json".to_string(), None).unwrap();
+    assert!(doc.positions.is_none());
+}
+
+#[test]
+fn test_parse_reports_duplicate_keys() {
+    let doc = parse_document(
+        "{\"port\": 80,\n\"port\": 8080}".to_string(),
+        "json".to_string(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(doc.value, serde_json::json!({"port": 8080}));
+    assert_eq!(doc.duplicate_keys.len(), 1);
+    assert_eq!(
+        doc.warnings,
+        ["Duplicate key $.port on lines 1 and 2; only the last value is kept"]
+    );
+
+    let doc = parse_document("a: 1\nb: 2\na: 3\n".to_string(), "yaml".to_string(), None).unwrap();
+    assert_eq!(doc.value, serde_json::json!({"a": 3, "b": 2}));
+    assert_eq!(doc.duplicate_keys[0].locations[1].line, 3);
+
+    let strict = ParseOptions {
+        reject_duplicate_keys: true,
+        ..Default::default()
+    };
+    let err = parse_document(
+        "a: 1\nb: 2\na: 3\n".to_string(),
+        "auto".to_string(),
+        Some(strict.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Data);
+    assert_eq!(err.message, "Duplicate key $.a on lines 1 and 3");
+    assert_eq!(err.location.unwrap().line, 3);
+
+    assert!(parse_document("{\"a\": 1}".to_string(), "json".to_string(), Some(strict)).is_ok());
+}
+
+#[test]
+fn test_duplicate_key_messages_on_one_line() {
+    let doc = parse_document(r#"{"a": 1, "a": 2}"#.to_string(), "json".to_string(), None).unwrap();
+    assert_eq!(
+        doc.warnings,
+        ["Duplicate key $.a on line 1, columns 2 and 10; only the last value is kept"]
+    );
+
+    let doc = parse_document(
+        "{\"a\": 1, \"a\": 2,\n\"a\": 3}".to_string(),
+        "json".to_string(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        doc.warnings,
+        ["Duplicate key $.a at 1:2, 1:10 and 2:1; only the last value is kept"]
+    );
+}
+
+#[test]
+fn test_parsed_document_serializes_camel_case() {
+    let doc = parse_document(
+        "{\"a\": 1,\n\"a\": 2}".to_string(),
+        "json".to_string(),
+        None,
+    )
+    .unwrap();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["duplicateKeys"][0]["path"], serde_json::json!(["a"]));
+    assert!(json.get("duplicate_keys").is_none());
+}
+
+#[test]
+fn test_parse_logs_format() {
+    let doc = parse_document(
+        "12:00 GET /users -> {\"count\": 2}\n".to_string(),
+        "log".to_string(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(doc.format, "logs");
+    assert_eq!(doc.value[0]["value"], serde_json::json!({"count": 2}));
+    assert_eq!(doc.value[0]["prefix"], "12:00 GET /users ->");
+}
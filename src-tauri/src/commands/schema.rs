@@ -6,7 +6,7 @@ pub fn generate_schema(json: Value) -> Result<Value, String> {
     Ok(infer_schema(&json))
 }
 
-fn infer_schema(v: &Value) -> Value {
+pub(crate) fn infer_schema(v: &Value) -> Value {
     match v {
         Value::Null => json!({ "type": "null" }),
         Value::Bool(_) => json!({ "type": "boolean" }),
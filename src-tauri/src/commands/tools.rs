@@ -1,14 +1,62 @@
+use crate::formats::numbers::{find_numbers, survives_f64};
+use crate::formats::{csv, keys};
 use base64::prelude::*;
+use serde::Serialize;
 use serde_json::Value;
+use std::collections::HashSet;
 use tauri::command;
 
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub value: Value,
+    pub warnings: Vec<String>,
+}
+
 #[command]
-pub fn run_jq(filter: String, json: Value) -> Result<Value, String> {
+pub fn run_jq(filter: String, json: Value, sort_keys: Option<bool>) -> Result<Value, String> {
+    run_jq_with_warnings(filter, json, sort_keys).map(|result| result.value)
+}
+
+/// Numbers jq passes through keep their original text, but arithmetic happens on doubles.
+/// Warn when the input holds numbers a double cannot represent and the output has numbers
+/// that were not copied from the input.
+fn jq_precision_warnings(input: &Value, output: &Value) -> Vec<String> {
+    let unsafe_numbers = find_numbers(input, &|n| survives_f64(&n.to_string()));
+    let Some((path, text)) = unsafe_numbers.first() else {
+        return Vec::new();
+    };
+
+    let input_numbers: HashSet<String> = find_numbers(input, &|_| false)
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect();
+    if find_numbers(output, &|n| input_numbers.contains(&n.to_string())).is_empty() {
+        return Vec::new();
+    }
+
+    vec![format!(
+        "jq computes with double precision and the input has {} number(s) beyond it (e.g. {} at {}); computed results may be rounded",
+        unsafe_numbers.len(),
+        text,
+        path
+    )]
+}
+
+#[command]
+pub fn run_jq_with_warnings(
+    filter: String,
+    json: Value,
+    sort_keys: Option<bool>,
+) -> Result<QueryResult, String> {
+    query_jq(&filter, &json, sort_keys.unwrap_or(false))
+}
+
+pub fn query_jq(filter: &str, json: &Value, sort_keys: bool) -> Result<QueryResult, String> {
     use jaq_core::load::{Arena, File, Loader};
     use jaq_core::{Compiler, Ctx, RcIter};
 
     let program = File {
-        code: filter.as_str(),
+        code: filter,
         path: (),
     };
 
@@ -31,7 +79,7 @@ pub fn run_jq(filter: String, json: Value) -> Result<Value, String> {
     let inputs = RcIter::new(core::iter::empty());
 
     // Convert input to jaq's internal Val using jaq-json
-    let input_val = jaq_json::Val::from(json);
+    let input_val = jaq_json::Val::from(json.clone());
 
     let mut results = Vec::new();
     // Iterator over output values
@@ -42,11 +90,16 @@ pub fn run_jq(filter: String, json: Value) -> Result<Value, String> {
         }
     }
 
-    if results.len() == 1 {
-        Ok(results[0].clone())
+    let mut value = if results.len() == 1 {
+        results[0].clone()
     } else {
-        Ok(Value::Array(results))
+        Value::Array(results)
+    };
+    if sort_keys {
+        keys::sort_keys(&mut value);
     }
+    let warnings = jq_precision_warnings(json, &value);
+    Ok(QueryResult { value, warnings })
 }
 
 #[command]
@@ -63,7 +116,7 @@ pub fn run_jsonpath(path: String, json: Value) -> Result<Value, String> {
 }
 
 #[command]
-pub fn anonymize_data(json: Value) -> Result<Value, String> {
+pub fn anonymize_data(json: Value, sort_keys: Option<bool>) -> Result<Value, String> {
     fn anonymize(v: &mut Value) {
         match v {
             Value::Object(map) => {
@@ -97,9 +150,23 @@ pub fn anonymize_data(json: Value) -> Result<Value, String> {
 
     let mut cloned = json.clone();
     anonymize(&mut cloned);
+    if sort_keys.unwrap_or(false) {
+        keys::sort_keys(&mut cloned);
+    }
     Ok(cloned)
 }
 
+/// Rebuilds nested JSON from flattened keys like `user.name` and `tags[0]`, as CSV export writes
+/// them. `separator` defaults to `.`.
+#[command]
+pub fn unflatten_json(json: Value, separator: Option<String>) -> Result<Value, String> {
+    let separator = separator.unwrap_or_else(|| ".".to_string());
+    if separator.is_empty() {
+        return Err("Separator must not be empty".to_string());
+    }
+    Ok(csv::unflatten(&json, &separator))
+}
+
 #[command]
 pub fn decode_jwt(token: String) -> Result<Value, String> {
     let header = jsonwebtoken::decode_header(&token).map_err(|e| e.to_string())?;
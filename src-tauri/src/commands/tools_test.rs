@@ -4,7 +4,7 @@ use serde_json::json;
 #[test]
 fn test_run_jq_identity() {
     let json = json!({"name": "test"});
-    let result = run_jq(".".to_string(), json).unwrap();
+    let result = run_jq(".".to_string(), json, None).unwrap();
     assert_eq!(result["name"], "test");
 }
 
@@ -16,7 +16,7 @@ fn test_run_jq_filter() {
             {"id": 2}
         ]
     });
-    let result = run_jq(".items[].id".to_string(), json).unwrap();
+    let result = run_jq(".items[].id".to_string(), json, None).unwrap();
     assert!(result.is_array());
     assert_eq!(result[0], 1);
     assert_eq!(result[1], 2);
@@ -41,8 +41,66 @@ fn test_anonymize_data() {
         "email": "private@example.com",
         "other": "public"
     });
-    let result = anonymize_data(json).unwrap();
+    let result = anonymize_data(json, None).unwrap();
     assert_eq!(result["name"], "REDACTED");
     assert_eq!(result["email"], "XXXX@example.com");
     assert_eq!(result["other"], "public");
 }
+
+#[test]
+fn test_run_jq_keeps_big_numbers() {
+    let json: Value = serde_json::from_str(r#"{"id": 1311768467463790320, "n": 1}"#).unwrap();
+    let result = run_jq_with_warnings(".id".to_string(), json, None).unwrap();
+    assert_eq!(result.value.to_string(), "1311768467463790320");
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn test_run_jq_warns_on_rounded_arithmetic() {
+    let json: Value = serde_json::from_str(r#"{"id": 1311768467463790320}"#).unwrap();
+    let result = run_jq_with_warnings(".id + 1".to_string(), json, None).unwrap();
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("$.id"));
+}
+
+#[test]
+fn test_key_order_is_preserved_unless_sorted() {
+    let json: Value =
+        serde_json::from_str(r#"{"zeta": 1, "email": "a@b.c", "alpha": {"y": 1, "b": 2}}"#)
+            .unwrap();
+
+    let result = run_jq(".".to_string(), json.clone(), None).unwrap();
+    assert_eq!(
+        serde_json::to_string(&result).unwrap(),
+        r#"{"zeta":1,"email":"a@b.c","alpha":{"y":1,"b":2}}"#
+    );
+
+    let result = anonymize_data(json.clone(), None).unwrap();
+    let keys: Vec<&String> = result.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["zeta", "email", "alpha"]);
+
+    let result = run_jq(".alpha".to_string(), json.clone(), Some(true)).unwrap();
+    assert_eq!(serde_json::to_string(&result).unwrap(), r#"{"b":2,"y":1}"#);
+
+    let result = anonymize_data(json, Some(true)).unwrap();
+    let keys: Vec<&String> = result.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["alpha", "email", "zeta"]);
+}
+
+#[test]
+fn test_unflatten_json() {
+    let flat = json!({"user.name": "Ada", "tags[1]": "b", "tags[0]": "a"});
+    assert_eq!(
+        unflatten_json(flat, None).unwrap(),
+        json!({"user": {"name": "Ada"}, "tags": ["a", "b"]})
+    );
+    assert_eq!(
+        unflatten_json(json!({"a__b": 1}), Some("__".to_string())).unwrap(),
+        json!({"a": {"b": 1}})
+    );
+    assert_eq!(
+        unflatten_json(json!({"a[4294967295]": 1}), None).unwrap(),
+        json!({"a[4294967295]": 1})
+    );
+    assert!(unflatten_json(json!({}), Some(String::new())).is_err());
+}
@@ -0,0 +1,850 @@
+//! Avro object container files, decoded with the writer schema embedded in the header.
+//! Records are read one block at a time, so a file can be paged through without decoding
+//! (or even reading) all of it.
+//!
+//! Values map to plain JSON rather than Avro's JSON encoding: unions show the branch value
+//! without a type wrapper, `bytes` and `fixed` are `{"$bytes": ...}`, timestamps are
+//! `{"$date": ...}`, dates and times are ISO-8601 strings and decimals are exact numbers.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::{compression, tagged};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use serde_json::{json, Map, Number, Value};
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+pub const MAGIC: &[u8; 4] = b"Obj\x01";
+
+const SYNC_SIZE: usize = 16;
+
+/// Nesting limit for recursive schemas, so a crafted file cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Most records a block, or items an array or map, may hold. Counts come from the file, and
+/// values such as `null` take no bytes, so without a cap a tiny file could claim billions.
+const MAX_COUNT: u64 = 1 << 22;
+
+fn error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("avro", kind, message)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Logical {
+    Date,
+    TimeMillis,
+    TimeMicros,
+    TimestampMillis,
+    TimestampMicros,
+    TimestampNanos,
+    LocalTimestampMillis,
+    LocalTimestampMicros,
+    LocalTimestampNanos,
+    Decimal(u32),
+    Uuid,
+    Duration,
+}
+
+#[derive(Debug, Clone)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    /// A record, enum or fixed, by its index in `Schemas::named`.
+    Named(usize),
+    Logical(Logical, Box<Schema>),
+}
+
+#[derive(Debug, Clone)]
+enum Named {
+    Record(Vec<(String, Schema)>),
+    Enum(Vec<String>),
+    Fixed(usize),
+}
+
+/// A parsed schema and the named types it defines.
+#[derive(Debug, Default)]
+struct Schemas {
+    named: Vec<Named>,
+    names: HashMap<String, usize>,
+}
+
+impl Schemas {
+    /// Whether a value of `schema` can be encoded in zero bytes, like `null` or an empty record.
+    fn zero_width(&self, schema: &Schema, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        match schema {
+            Schema::Null => true,
+            Schema::Logical(_, base) => self.zero_width(base, depth + 1),
+            Schema::Named(index) => match &self.named[*index] {
+                Named::Record(fields) => fields
+                    .iter()
+                    .all(|(_, field)| self.zero_width(field, depth + 1)),
+                Named::Fixed(size) => *size == 0,
+                Named::Enum(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn full_name(name: &str, namespace: Option<&str>) -> String {
+        match namespace {
+            Some(ns) if !name.contains('.') && !ns.is_empty() => format!("{}.{}", ns, name),
+            _ => name.to_string(),
+        }
+    }
+
+    fn lookup(&self, name: &str, namespace: Option<&str>) -> Option<usize> {
+        self.names
+            .get(&Self::full_name(name, namespace))
+            .or_else(|| self.names.get(name))
+            .copied()
+    }
+
+    fn define(
+        &mut self,
+        json: &Map<String, Value>,
+        namespace: Option<&str>,
+        named: Named,
+    ) -> usize {
+        let name = json.get("name").and_then(Value::as_str).unwrap_or_default();
+        let namespace = json.get("namespace").and_then(Value::as_str).or(namespace);
+        let index = self.named.len();
+        self.named.push(named);
+        self.names.insert(Self::full_name(name, namespace), index);
+        index
+    }
+
+    fn parse(&mut self, json: &Value, namespace: Option<&str>) -> Result<Schema, String> {
+        let object = match json {
+            Value::String(name) => return self.primitive_or_reference(name, namespace),
+            Value::Array(branches) => {
+                return branches
+                    .iter()
+                    .map(|branch| self.parse(branch, namespace))
+                    .collect::<Result<_, _>>()
+                    .map(Schema::Union)
+            }
+            Value::Object(object) => object,
+            other => return Err(format!("Invalid schema: {}", other)),
+        };
+        let kind = object.get("type").ok_or("Schema object without a type")?;
+        let inner_namespace = object
+            .get("namespace")
+            .and_then(Value::as_str)
+            .or(namespace);
+        let schema = match kind.as_str() {
+            Some("record" | "error") => {
+                // Defined before the fields so they can refer back to it.
+                let index = self.define(object, namespace, Named::Record(Vec::new()));
+                let mut fields = Vec::new();
+                for field in object
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .ok_or("Record schema without fields")?
+                {
+                    let name = field
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .ok_or("Record field without a name")?;
+                    let schema = self.parse(
+                        field.get("type").ok_or("Record field without a type")?,
+                        inner_namespace,
+                    )?;
+                    fields.push((name.to_string(), schema));
+                }
+                self.named[index] = Named::Record(fields);
+                Schema::Named(index)
+            }
+            Some("enum") => {
+                let symbols = object
+                    .get("symbols")
+                    .and_then(Value::as_array)
+                    .ok_or("Enum schema without symbols")?
+                    .iter()
+                    .map(|s| s.as_str().unwrap_or_default().to_string())
+                    .collect();
+                Schema::Named(self.define(object, namespace, Named::Enum(symbols)))
+            }
+            Some("fixed") => {
+                let size = object
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    .ok_or("Fixed schema without a size")?;
+                Schema::Named(self.define(object, namespace, Named::Fixed(size as usize)))
+            }
+            Some("array") => Schema::Array(Box::new(self.parse(
+                object.get("items").ok_or("Array schema without items")?,
+                namespace,
+            )?)),
+            Some("map") => Schema::Map(Box::new(self.parse(
+                object.get("values").ok_or("Map schema without values")?,
+                namespace,
+            )?)),
+            _ => self.parse(kind, namespace)?,
+        };
+        let logical = match object.get("logicalType").and_then(Value::as_str) {
+            Some("date") => Logical::Date,
+            Some("time-millis") => Logical::TimeMillis,
+            Some("time-micros") => Logical::TimeMicros,
+            Some("timestamp-millis") => Logical::TimestampMillis,
+            Some("timestamp-micros") => Logical::TimestampMicros,
+            Some("timestamp-nanos") => Logical::TimestampNanos,
+            Some("local-timestamp-millis") => Logical::LocalTimestampMillis,
+            Some("local-timestamp-micros") => Logical::LocalTimestampMicros,
+            Some("local-timestamp-nanos") => Logical::LocalTimestampNanos,
+            Some("decimal") => {
+                Logical::Decimal(object.get("scale").and_then(Value::as_u64).unwrap_or(0) as u32)
+            }
+            Some("uuid") => Logical::Uuid,
+            Some("duration") => Logical::Duration,
+            // Readers ignore logical types they do not know.
+            _ => return Ok(schema),
+        };
+        Ok(Schema::Logical(logical, Box::new(schema)))
+    }
+
+    fn primitive_or_reference(
+        &self,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<Schema, String> {
+        Ok(match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            name => Schema::Named(
+                self.lookup(name, namespace)
+                    .ok_or_else(|| format!("Unknown type in schema: {}", name))?,
+            ),
+        })
+    }
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Unexpected end of block")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn long(&mut self) -> Result<i64, String> {
+        let mut value = 0u64;
+        for shift in (0..70).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        Err("Varint longer than 64 bits".to_string())
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        usize::try_from(self.long()?).map_err(|_| "Negative length".to_string())
+    }
+
+    /// Reads the item count of the next array or map block; negative counts are followed by
+    /// the block size in bytes. `read` is the number of items before this block; items that
+    /// take no bytes cannot outnumber the bytes left.
+    fn block_count(&mut self, read: usize, zero_width: bool) -> Result<usize, String> {
+        let count = self.long()?;
+        if count < 0 {
+            self.long()?;
+        }
+        let count = count.unsigned_abs();
+        if count.saturating_add(read as u64) > MAX_COUNT {
+            return Err(format!("More than {} items in an array or map", MAX_COUNT));
+        }
+        let left = self.bytes.len() - self.pos;
+        if zero_width && count > left as u64 {
+            return Err(format!(
+                "Array or map claims {} items but only {} bytes are left",
+                count, left
+            ));
+        }
+        Ok(count as usize)
+    }
+
+    fn value(&mut self, schema: &Schema, schemas: &Schemas, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("Nesting deeper than {} levels", MAX_DEPTH));
+        }
+        Ok(match schema {
+            Schema::Null => Value::Null,
+            Schema::Boolean => Value::Bool(self.take(1)?[0] != 0),
+            Schema::Int | Schema::Long => Value::from(self.long()?),
+            Schema::Float => {
+                tagged::float(f32::from_le_bytes(self.take(4)?.try_into().unwrap()) as f64)
+            }
+            Schema::Double => tagged::float(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            Schema::Bytes => {
+                let len = self.len()?;
+                tagged::bytes(self.take(len)?)
+            }
+            Schema::String => {
+                let len = self.len()?;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|_| "Invalid UTF-8 in string".to_string())?;
+                Value::String(text.to_string())
+            }
+            Schema::Array(items) => {
+                let zero_width = schemas.zero_width(items, 0);
+                let mut values = Vec::new();
+                loop {
+                    let count = self.block_count(values.len(), zero_width)?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        values.push(self.value(items, schemas, depth + 1)?);
+                    }
+                }
+                Value::Array(values)
+            }
+            Schema::Map(values) => {
+                let mut map = Map::new();
+                loop {
+                    // Keys take at least a byte for their length.
+                    let count = self.block_count(map.len(), false)?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        let len = self.len()?;
+                        let key = String::from_utf8_lossy(self.take(len)?).into_owned();
+                        map.insert(key, self.value(values, schemas, depth + 1)?);
+                    }
+                }
+                Value::Object(map)
+            }
+            Schema::Union(branches) => {
+                let index = self.long()?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| format!("Union branch {} out of range", index))?;
+                self.value(branch, schemas, depth + 1)?
+            }
+            Schema::Named(index) => match &schemas.named[*index] {
+                Named::Record(fields) => {
+                    let mut map = Map::new();
+                    for (name, field) in fields {
+                        map.insert(name.clone(), self.value(field, schemas, depth + 1)?);
+                    }
+                    Value::Object(map)
+                }
+                Named::Enum(symbols) => {
+                    let index = self.long()?;
+                    let symbol = usize::try_from(index)
+                        .ok()
+                        .and_then(|i| symbols.get(i))
+                        .ok_or_else(|| format!("Enum index {} out of range", index))?;
+                    Value::String(symbol.clone())
+                }
+                Named::Fixed(size) => tagged::bytes(self.take(*size)?),
+            },
+            Schema::Logical(logical, base) => {
+                let value = self.value(base, schemas, depth + 1)?;
+                convert_logical(*logical, value)
+            }
+        })
+    }
+}
+
+fn decimal(unscaled: &[u8], scale: u32) -> Option<Value> {
+    if unscaled.is_empty() || unscaled.len() > 16 {
+        return None;
+    }
+    // Big-endian two's complement, sign-extended to 128 bits.
+    let fill = if unscaled[0] & 0x80 != 0 { 0xff } else { 0 };
+    let mut bytes = [fill; 16];
+    bytes[16 - unscaled.len()..].copy_from_slice(unscaled);
+    let n = i128::from_be_bytes(bytes);
+    let digits = n.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let digits = if scale == 0 {
+        digits
+    } else {
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - scale);
+        format!("{}.{}", whole, fraction)
+    };
+    let sign = if n < 0 { "-" } else { "" };
+    format!("{}{}", sign, digits)
+        .parse::<Number>()
+        .ok()
+        .map(Value::Number)
+}
+
+fn convert_logical(logical: Logical, value: Value) -> Value {
+    let converted = match (&value, logical) {
+        (Value::Number(n), _) => n.as_i64().and_then(|n| match logical {
+            Logical::Date => NaiveDate::from_ymd_opt(1970, 1, 1)?
+                .checked_add_signed(chrono::Duration::days(n))
+                .map(|date| Value::String(date.to_string())),
+            Logical::TimeMillis | Logical::TimeMicros => {
+                let micros = if logical == Logical::TimeMillis {
+                    n * 1000
+                } else {
+                    n
+                };
+                NaiveTime::from_num_seconds_from_midnight_opt(
+                    u32::try_from(micros / 1_000_000).ok()?,
+                    u32::try_from(micros % 1_000_000 * 1000).ok()?,
+                )
+                .map(|time| Value::String(time.to_string()))
+            }
+            Logical::TimestampMillis => DateTime::from_timestamp_millis(n).map(tagged::date),
+            Logical::TimestampMicros => DateTime::from_timestamp_micros(n).map(tagged::date),
+            Logical::TimestampNanos => Some(tagged::date(DateTime::from_timestamp_nanos(n))),
+            Logical::LocalTimestampMillis => DateTime::from_timestamp_millis(n)
+                .map(|dt| Value::String(dt.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+            Logical::LocalTimestampMicros => DateTime::from_timestamp_micros(n)
+                .map(|dt| Value::String(dt.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+            Logical::LocalTimestampNanos => Some(Value::String(
+                DateTime::from_timestamp_nanos(n)
+                    .naive_utc()
+                    .format("%Y-%m-%dT%H:%M:%S%.f")
+                    .to_string(),
+            )),
+            _ => None,
+        }),
+        (bytes, Logical::Decimal(scale)) => {
+            tagged::as_bytes(bytes).and_then(|data| decimal(&data, scale))
+        }
+        (bytes, Logical::Uuid) => {
+            tagged::as_bytes(bytes)
+                .filter(|data| data.len() == 16)
+                .map(|data| {
+                    let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+                    Value::String(format!(
+                        "{}-{}-{}-{}-{}",
+                        &hex[..8],
+                        &hex[8..12],
+                        &hex[12..16],
+                        &hex[16..20],
+                        &hex[20..]
+                    ))
+                })
+        }
+        (bytes, Logical::Duration) => {
+            tagged::as_bytes(bytes)
+                .filter(|data| data.len() == 12)
+                .map(|data| {
+                    let part = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+                    json!({ "months": part(0), "days": part(4), "milliseconds": part(8) })
+                })
+        }
+        _ => None,
+    };
+    converted.unwrap_or(value)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Codec {
+    Null,
+    Deflate,
+    Snappy,
+    Zstandard,
+}
+
+/// Reads records from an object container file, decoding one block at a time.
+pub struct AvroReader<R> {
+    reader: R,
+    /// Offset in the file, for error messages.
+    pos: u64,
+    schema_json: Value,
+    schema: Schema,
+    schemas: Schemas,
+    codec: Codec,
+    sync: [u8; SYNC_SIZE],
+    block: Vec<u8>,
+    block_pos: usize,
+    /// Records left in the current block.
+    remaining: u64,
+    /// Index of the next record in the file.
+    record: u64,
+}
+
+fn read_exact<R: Read>(reader: &mut R, pos: &mut u64, buf: &mut [u8]) -> Result<(), ParseError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => error(
+            ParseErrorKind::Eof,
+            format!("Unexpected end of data after byte {}", pos),
+        ),
+        _ => error(ParseErrorKind::Io, e.to_string()),
+    })?;
+    *pos += buf.len() as u64;
+    Ok(())
+}
+
+/// Reads a zigzag varint, or `None` at a clean end of the stream.
+fn read_long<R: Read>(reader: &mut R, pos: &mut u64) -> Result<Option<i64>, ParseError> {
+    let start = *pos;
+    let mut value = 0u64;
+    for (i, shift) in (0..70).step_by(7).enumerate() {
+        let mut byte = [0u8];
+        match reader.read(&mut byte) {
+            Ok(0) if i == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(error(
+                    ParseErrorKind::Eof,
+                    format!("Unexpected end of data after byte {}", pos),
+                ))
+            }
+            Ok(_) => *pos += 1,
+            Err(e) => return Err(error(ParseErrorKind::Io, e.to_string())),
+        }
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some((value >> 1) as i64 ^ -((value & 1) as i64)));
+        }
+    }
+    Err(error(
+        ParseErrorKind::Syntax,
+        format!("Varint longer than 64 bits at byte {}", start),
+    ))
+}
+
+fn expect_long<R: Read>(reader: &mut R, pos: &mut u64) -> Result<i64, ParseError> {
+    read_long(reader, pos)?.ok_or_else(|| {
+        error(
+            ParseErrorKind::Eof,
+            format!("Unexpected end of data after byte {}", pos),
+        )
+    })
+}
+
+fn expect_len<R: Read>(reader: &mut R, pos: &mut u64) -> Result<usize, ParseError> {
+    let start = *pos;
+    usize::try_from(expect_long(reader, pos)?).map_err(|_| {
+        error(
+            ParseErrorKind::Syntax,
+            format!("Negative length at byte {}", start),
+        )
+    })
+}
+
+fn read_bytes<R: Read>(reader: &mut R, pos: &mut u64, len: usize) -> Result<Vec<u8>, ParseError> {
+    let mut buf = Vec::new();
+    let read = reader
+        .take(len as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| error(ParseErrorKind::Io, e.to_string()))?;
+    *pos += read as u64;
+    if read < len {
+        return Err(error(
+            ParseErrorKind::Eof,
+            format!("Unexpected end of data after byte {}", pos),
+        ));
+    }
+    Ok(buf)
+}
+
+impl<R: Read> AvroReader<R> {
+    /// Reads the header: magic, metadata with the schema and codec, and the sync marker.
+    pub fn new(mut reader: R) -> Result<Self, ParseError> {
+        let mut pos = 0;
+        let mut magic = [0u8; 4];
+        read_exact(&mut reader, &mut pos, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(error(
+                ParseErrorKind::Syntax,
+                "Not an Avro object container file (missing Obj\\x01 header)".to_string(),
+            ));
+        }
+        let mut metadata = HashMap::new();
+        loop {
+            let count = expect_long(&mut reader, &mut pos)?;
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                expect_long(&mut reader, &mut pos)?;
+            }
+            for _ in 0..count.unsigned_abs() {
+                let len = expect_len(&mut reader, &mut pos)?;
+                let key = read_bytes(&mut reader, &mut pos, len)?;
+                let len = expect_len(&mut reader, &mut pos)?;
+                let value = read_bytes(&mut reader, &mut pos, len)?;
+                metadata.insert(String::from_utf8_lossy(&key).into_owned(), value);
+            }
+        }
+        let mut sync = [0u8; SYNC_SIZE];
+        read_exact(&mut reader, &mut pos, &mut sync)?;
+
+        let schema_json: Value = metadata
+            .get("avro.schema")
+            .and_then(|bytes| serde_json::from_slice(bytes).ok())
+            .ok_or_else(|| {
+                error(
+                    ParseErrorKind::Syntax,
+                    "Missing or invalid avro.schema in the header".to_string(),
+                )
+            })?;
+        let mut schemas = Schemas::default();
+        let schema = schemas
+            .parse(&schema_json, None)
+            .map_err(|e| error(ParseErrorKind::Data, e))?;
+        let codec = match metadata.get("avro.codec").map(|c| c.as_slice()) {
+            None | Some(b"null") => Codec::Null,
+            Some(b"deflate") => Codec::Deflate,
+            Some(b"snappy") => Codec::Snappy,
+            Some(b"zstandard") => Codec::Zstandard,
+            Some(other) => {
+                return Err(error(
+                    ParseErrorKind::Unsupported,
+                    format!("Unsupported Avro codec: {}", String::from_utf8_lossy(other)),
+                ))
+            }
+        };
+        Ok(AvroReader {
+            reader,
+            pos,
+            schema_json,
+            schema,
+            schemas,
+            codec,
+            sync,
+            block: Vec::new(),
+            block_pos: 0,
+            remaining: 0,
+            record: 0,
+        })
+    }
+
+    /// The writer schema from the header, as written.
+    pub fn schema(&self) -> &Value {
+        &self.schema_json
+    }
+
+    /// Reads the next block's header. Returns the record count and the data size, or `None` at
+    /// the end of the file.
+    fn block_header(&mut self) -> Result<Option<(u64, usize)>, ParseError> {
+        let start = self.pos;
+        let Some(count) = read_long(&mut self.reader, &mut self.pos)? else {
+            return Ok(None);
+        };
+        let size = expect_len(&mut self.reader, &mut self.pos)?;
+        let count = u64::try_from(count).map_err(|_| {
+            error(
+                ParseErrorKind::Syntax,
+                format!("Negative record count at byte {}", start),
+            )
+        })?;
+        if count > MAX_COUNT {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!(
+                    "Block at byte {} holds {} records, more than the limit of {}",
+                    start, count, MAX_COUNT
+                ),
+            ));
+        }
+        Ok(Some((count, size)))
+    }
+
+    fn check_sync(&mut self) -> Result<(), ParseError> {
+        let at = self.pos;
+        let mut sync = [0u8; SYNC_SIZE];
+        read_exact(&mut self.reader, &mut self.pos, &mut sync)?;
+        if sync != self.sync {
+            return Err(error(
+                ParseErrorKind::Syntax,
+                format!("Sync marker mismatch at byte {}", at),
+            ));
+        }
+        Ok(())
+    }
+
+    fn decompress(&self, data: Vec<u8>, at: u64) -> Result<Vec<u8>, ParseError> {
+        let failed = |e: String| {
+            error(
+                ParseErrorKind::Data,
+                format!("Cannot decompress block at byte {}: {}", at, e),
+            )
+        };
+        match self.codec {
+            Codec::Null => Ok(data),
+            Codec::Deflate => {
+                compression::read_limited(flate2::read::DeflateDecoder::new(data.as_slice()))
+                    .map_err(failed)
+            }
+            Codec::Snappy => {
+                // Snappy blocks end with the CRC-32 of the uncompressed data.
+                let split = data
+                    .len()
+                    .checked_sub(4)
+                    .ok_or_else(|| failed("missing checksum".to_string()))?;
+                let len =
+                    snap::raw::decompress_len(&data[..split]).map_err(|e| failed(e.to_string()))?;
+                if len as u64 > compression::MAX_DECOMPRESSED_BYTES {
+                    return Err(failed(format!(
+                        "Decompressed content exceeds {} MiB",
+                        compression::MAX_DECOMPRESSED_BYTES >> 20
+                    )));
+                }
+                let out = snap::raw::Decoder::new()
+                    .decompress_vec(&data[..split])
+                    .map_err(|e| failed(e.to_string()))?;
+                if crc32fast::hash(&out).to_be_bytes() != data[split..] {
+                    return Err(failed("checksum mismatch".to_string()));
+                }
+                Ok(out)
+            }
+            Codec::Zstandard => {
+                let decoder = zstd::stream::read::Decoder::new(data.as_slice())
+                    .map_err(|e| failed(e.to_string()))?;
+                compression::read_limited(decoder).map_err(failed)
+            }
+        }
+    }
+
+    /// Reads and decompresses a block whose header was just read.
+    fn load_block(&mut self, count: u64, size: usize) -> Result<(), ParseError> {
+        let at = self.pos;
+        let data = read_bytes(&mut self.reader, &mut self.pos, size)?;
+        self.check_sync()?;
+        let block = self.decompress(data, at)?;
+        // Records that take no bytes cannot outnumber the bytes in the block.
+        if count > block.len() as u64 && self.schemas.zero_width(&self.schema, 0) {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!(
+                    "Block at byte {} claims {} records in {} bytes",
+                    at,
+                    count,
+                    block.len()
+                ),
+            ));
+        }
+        self.block = block;
+        self.block_pos = 0;
+        self.remaining = count;
+        Ok(())
+    }
+
+    /// Loads the next non-empty block. Returns `false` at the end of the file.
+    fn next_block(&mut self) -> Result<bool, ParseError> {
+        while self.remaining == 0 {
+            let Some((count, size)) = self.block_header()? else {
+                return Ok(false);
+            };
+            self.load_block(count, size)?;
+        }
+        Ok(true)
+    }
+
+    /// Whether there are records left.
+    pub fn has_next(&mut self) -> Result<bool, ParseError> {
+        self.next_block()
+    }
+
+    fn decode_next(&mut self) -> Result<Value, ParseError> {
+        let mut cursor = Cursor {
+            bytes: &self.block,
+            pos: self.block_pos,
+        };
+        let value = cursor.value(&self.schema, &self.schemas, 0).map_err(|e| {
+            error(
+                ParseErrorKind::Data,
+                format!("{} in record {}", e, self.record),
+            )
+        })?;
+        self.block_pos = cursor.pos;
+        self.remaining -= 1;
+        self.record += 1;
+        Ok(value)
+    }
+
+    /// Skips `n` records. Blocks that are skipped whole are neither decompressed nor decoded.
+    pub fn skip_records(&mut self, mut n: u64) -> Result<(), ParseError> {
+        while n > 0 {
+            if self.remaining == 0 {
+                let Some((count, size)) = self.block_header()? else {
+                    return Ok(());
+                };
+                if count <= n {
+                    let skipped =
+                        io::copy(&mut (&mut self.reader).take(size as u64), &mut io::sink())
+                            .map_err(|e| error(ParseErrorKind::Io, e.to_string()))?;
+                    self.pos += skipped;
+                    if skipped < size as u64 {
+                        return Err(error(
+                            ParseErrorKind::Eof,
+                            format!("Unexpected end of data after byte {}", self.pos),
+                        ));
+                    }
+                    self.check_sync()?;
+                    self.record += count;
+                    n -= count;
+                    continue;
+                }
+                self.load_block(count, size)?;
+            }
+            self.decode_next()?;
+            n -= 1;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for AvroReader<R> {
+    type Item = Result<Value, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_block() {
+            Ok(true) => Some(self.decode_next()),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Decodes every record in a container file. Returns the records and the writer schema.
+pub fn parse_avro(bytes: &[u8]) -> Result<(Value, Value), ParseError> {
+    let mut reader = AvroReader::new(bytes)?;
+    let mut records = Vec::new();
+    for record in reader.by_ref() {
+        if records.len() as u64 == MAX_COUNT {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!(
+                    "More than {} records; page through the file with read_avro_records",
+                    MAX_COUNT
+                ),
+            ));
+        }
+        records.push(record?);
+    }
+    Ok((Value::Array(records), reader.schema_json))
+}
+
+#[cfg(test)]
+#[path = "avro_test.rs"]
+mod avro_test;
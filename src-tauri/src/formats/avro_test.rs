@@ -0,0 +1,406 @@
+use super::*;
+use std::io::Write;
+
+const SYNC: [u8; SYNC_SIZE] = *b"0123456789abcdef";
+
+fn long(out: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn string(out: &mut Vec<u8>, s: &[u8]) {
+    long(out, s.len() as i64);
+    out.extend_from_slice(s);
+}
+
+fn compress(codec: &str, data: &[u8]) -> Vec<u8> {
+    match codec {
+        "null" => data.to_vec(),
+        "deflate" => {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        "snappy" => {
+            let mut out = snap::raw::Encoder::new().compress_vec(data).unwrap();
+            out.extend_from_slice(&crc32fast::hash(data).to_be_bytes());
+            out
+        }
+        "zstandard" => zstd::stream::encode_all(data, 0).unwrap(),
+        other => panic!("no test encoder for {}", other),
+    }
+}
+
+/// Builds a container file with one block per entry of `blocks`, each holding a record count
+/// and the encoded records.
+fn container(schema: &Value, codec: &str, blocks: &[(i64, Vec<u8>)]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    long(&mut out, 2);
+    string(&mut out, b"avro.schema");
+    string(&mut out, schema.to_string().as_bytes());
+    string(&mut out, b"avro.codec");
+    string(&mut out, codec.as_bytes());
+    long(&mut out, 0);
+    out.extend_from_slice(&SYNC);
+    for (count, data) in blocks {
+        let data = compress(codec, data);
+        long(&mut out, *count);
+        long(&mut out, data.len() as i64);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&SYNC);
+    }
+    out
+}
+
+fn user_schema() -> Value {
+    json!({
+        "type": "record",
+        "name": "User",
+        "namespace": "com.example",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "name", "type": "string"},
+            {"name": "email", "type": ["null", "string"]},
+        ]
+    })
+}
+
+fn user(id: i64, name: &str, email: Option<&str>) -> Vec<u8> {
+    let mut out = Vec::new();
+    long(&mut out, id);
+    string(&mut out, name.as_bytes());
+    match email {
+        Some(email) => {
+            long(&mut out, 1);
+            string(&mut out, email.as_bytes());
+        }
+        None => long(&mut out, 0),
+    }
+    out
+}
+
+fn users(ids: std::ops::Range<i64>) -> Vec<u8> {
+    ids.flat_map(|id| user(id, &format!("user{}", id), None))
+        .collect()
+}
+
+#[test]
+fn test_avro_reads_records_and_schema() {
+    let mut block = user(1, "Ada", Some("ada@example.com"));
+    block.extend(user(2, "Bob", None));
+    let file = container(&user_schema(), "null", &[(2, block)]);
+
+    let (records, schema) = parse_avro(&file).unwrap();
+    assert_eq!(
+        records,
+        json!([
+            {"id": 1, "name": "Ada", "email": "ada@example.com"},
+            {"id": 2, "name": "Bob", "email": null},
+        ])
+    );
+    assert_eq!(schema, user_schema());
+}
+
+#[test]
+fn test_avro_codecs() {
+    for codec in ["null", "deflate", "snappy", "zstandard"] {
+        let file = container(&user_schema(), codec, &[(3, users(0..3)), (2, users(3..5))]);
+        let (records, _) = parse_avro(&file).unwrap();
+        assert_eq!(records.as_array().unwrap().len(), 5, "{}", codec);
+        assert_eq!(records[4]["name"], "user4", "{}", codec);
+    }
+}
+
+#[test]
+fn test_avro_unsupported_codec() {
+    let file = container(&user_schema(), "xz", &[]);
+    let err = parse_avro(&file).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Unsupported);
+    assert!(err.message.contains("xz"), "{}", err.message);
+}
+
+#[test]
+fn test_avro_snappy_checksum_mismatch() {
+    let mut file = container(&user_schema(), "snappy", &[(1, users(0..1))]);
+    let checksum = file.len() - SYNC_SIZE - 1;
+    file[checksum] ^= 0xff;
+    let err = parse_avro(&file).unwrap_err();
+    assert!(err.message.contains("checksum mismatch"), "{}", err.message);
+}
+
+#[test]
+fn test_avro_sync_marker_mismatch() {
+    let mut file = container(&user_schema(), "null", &[(1, users(0..1))]);
+    let last = file.len() - 1;
+    file[last] = b'!';
+    let err = parse_avro(&file).unwrap_err();
+    assert!(
+        err.message.contains("Sync marker mismatch"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn test_avro_rejects_other_input() {
+    let err = parse_avro(b"{\"a\": 1}").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+    let err = parse_avro(b"Obj").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Eof);
+}
+
+#[test]
+fn test_avro_truncated_block() {
+    let mut file = container(&user_schema(), "null", &[(2, users(0..2))]);
+    file.truncate(file.len() - SYNC_SIZE - 3);
+    let err = parse_avro(&file).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Eof);
+}
+
+#[test]
+fn test_avro_complex_types() {
+    let schema = json!({
+        "type": "record",
+        "name": "Event",
+        "fields": [
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "counts", "type": {"type": "map", "values": "int"}},
+            {"name": "level", "type": {"type": "enum", "name": "Level", "symbols": ["LOW", "HIGH"]}},
+            {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 2}},
+            {"name": "payload", "type": "bytes"},
+            {"name": "ok", "type": "boolean"},
+            {"name": "ratio", "type": "double"},
+            {"name": "next", "type": ["null", "Event"]},
+        ]
+    });
+    let mut data = Vec::new();
+    // tags: one block of two items, then the end marker.
+    long(&mut data, 2);
+    string(&mut data, b"a");
+    string(&mut data, b"b");
+    long(&mut data, 0);
+    // counts: a block with a negative count, followed by its size in bytes.
+    long(&mut data, -1);
+    long(&mut data, 3);
+    string(&mut data, b"x");
+    long(&mut data, 7);
+    long(&mut data, 0);
+    long(&mut data, 1);
+    data.extend_from_slice(&[0xca, 0xfe]);
+    string(&mut data, b"hi");
+    data.push(1);
+    data.extend_from_slice(&1.5f64.to_le_bytes());
+    // next: a nested Event with empty collections and no next.
+    long(&mut data, 1);
+    long(&mut data, 0);
+    long(&mut data, 0);
+    long(&mut data, 0);
+    data.extend_from_slice(&[0, 0]);
+    string(&mut data, b"");
+    data.push(0);
+    data.extend_from_slice(&0f64.to_le_bytes());
+    long(&mut data, 0);
+
+    let (records, _) = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap();
+    let event = &records[0];
+    assert_eq!(event["tags"], json!(["a", "b"]));
+    assert_eq!(event["counts"], json!({"x": 7}));
+    assert_eq!(event["level"], "HIGH");
+    assert_eq!(event["hash"], tagged::bytes(&[0xca, 0xfe]));
+    assert_eq!(event["payload"], tagged::bytes(b"hi"));
+    assert_eq!(event["ok"], true);
+    assert_eq!(event["ratio"], json!(1.5));
+    assert_eq!(event["next"]["level"], "LOW");
+    assert_eq!(event["next"]["next"], Value::Null);
+}
+
+#[test]
+fn test_avro_logical_types() {
+    let schema = json!({
+        "type": "record",
+        "name": "Row",
+        "fields": [
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "local", "type": {"type": "long", "logicalType": "local-timestamp-micros"}},
+            {"name": "time", "type": {"type": "int", "logicalType": "time-millis"}},
+            {"name": "price", "type": {"type": "bytes", "logicalType": "decimal", "precision": 9, "scale": 2}},
+            {"name": "id", "type": {"type": "string", "logicalType": "uuid"}},
+            {"name": "wait", "type": {"type": "fixed", "name": "Duration", "size": 12, "logicalType": "duration"}},
+        ]
+    });
+    let mut data = Vec::new();
+    long(&mut data, 19_000);
+    long(&mut data, 1_700_000_000_123);
+    long(&mut data, 1_700_000_000_000_456);
+    long(&mut data, 45_296_789);
+    // -12345 as big-endian two's complement.
+    string(&mut data, &(-12345i32).to_be_bytes()[1..]);
+    string(&mut data, b"9b2f1c3e-2a3b-4c5d-8e9f-0a1b2c3d4e5f");
+    for part in [1u32, 2, 3000] {
+        data.extend_from_slice(&part.to_le_bytes());
+    }
+
+    let (records, _) = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap();
+    let row = &records[0];
+    assert_eq!(row["day"], "2022-01-08");
+    assert_eq!(row["at"], json!({"$date": "2023-11-14T22:13:20.123Z"}));
+    assert_eq!(row["local"], "2023-11-14T22:13:20.000456");
+    assert_eq!(row["time"], "12:34:56.789");
+    assert_eq!(row["price"].to_string(), "-123.45");
+    assert_eq!(row["id"], "9b2f1c3e-2a3b-4c5d-8e9f-0a1b2c3d4e5f");
+    assert_eq!(
+        row["wait"],
+        json!({"months": 1, "days": 2, "milliseconds": 3000})
+    );
+}
+
+#[test]
+fn test_avro_named_type_references() {
+    let schema = json!({
+        "type": "record",
+        "name": "Pair",
+        "namespace": "geo",
+        "fields": [
+            {"name": "from", "type": {"type": "record", "name": "Point", "fields": [
+                {"name": "x", "type": "int"},
+            ]}},
+            {"name": "to", "type": "Point"},
+            {"name": "via", "type": "geo.Point"},
+        ]
+    });
+    let mut data = Vec::new();
+    for x in [1, 2, 3] {
+        long(&mut data, x);
+    }
+    let (records, _) = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap();
+    assert_eq!(
+        records,
+        json!([{"from": {"x": 1}, "to": {"x": 2}, "via": {"x": 3}}])
+    );
+
+    let unknown = json!({"type": "record", "name": "R", "fields": [{"name": "a", "type": "Nope"}]});
+    let err = parse_avro(&container(&unknown, "null", &[])).unwrap_err();
+    assert!(
+        err.message.contains("Unknown type in schema: Nope"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn test_avro_reader_skips_and_pages() {
+    let file = container(
+        &user_schema(),
+        "deflate",
+        &[(3, users(0..3)), (3, users(3..6)), (3, users(6..9))],
+    );
+    let mut reader = AvroReader::new(file.as_slice()).unwrap();
+    reader.skip_records(4).unwrap();
+    let page: Vec<Value> = reader.by_ref().take(3).map(Result::unwrap).collect();
+    assert_eq!(
+        page.iter().map(|r| r["id"].clone()).collect::<Vec<_>>(),
+        [json!(4), json!(5), json!(6)]
+    );
+    assert!(reader.has_next().unwrap());
+    assert_eq!(reader.count(), 2);
+
+    let mut reader = AvroReader::new(file.as_slice()).unwrap();
+    reader.skip_records(20).unwrap();
+    assert!(!reader.has_next().unwrap());
+}
+
+#[test]
+fn test_avro_rejects_deep_nesting() {
+    let schema = json!({
+        "type": "record",
+        "name": "Node",
+        "fields": [{"name": "child", "type": ["null", "Node"]}]
+    });
+    let mut data = Vec::new();
+    for _ in 0..200 {
+        long(&mut data, 1);
+    }
+    long(&mut data, 0);
+    let err = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap_err();
+    assert!(err.message.contains("Nesting deeper"), "{}", err.message);
+}
+
+#[test]
+fn test_avro_rejects_oversized_blocks() {
+    // A snappy block whose header claims 2 GiB of output.
+    let mut block = vec![0x80, 0x80, 0x80, 0x80, 0x08, 0x00];
+    block.extend_from_slice(&[0; 4]);
+    let mut file = container(&user_schema(), "snappy", &[]);
+    long(&mut file, 1);
+    long(&mut file, block.len() as i64);
+    file.extend_from_slice(&block);
+    file.extend_from_slice(&SYNC);
+    let err = parse_avro(&file).unwrap_err();
+    assert!(err.message.contains("exceeds 1024 MiB"), "{}", err.message);
+}
+
+#[test]
+fn test_avro_rejects_huge_counts_of_empty_values() {
+    // An array of 2^40 nulls, which take no bytes each.
+    let schema = json!({"type": "array", "items": "null"});
+    let mut data = Vec::new();
+    long(&mut data, 1 << 40);
+    long(&mut data, 0);
+    let err = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Data);
+    // Below the cap, the count still cannot exceed the bytes left.
+    let mut data = Vec::new();
+    long(&mut data, 1000);
+    long(&mut data, 0);
+    let err = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap_err();
+    assert!(
+        err.message.contains("claims 1000 items but only 1 bytes are left"),
+        "{}",
+        err.message
+    );
+
+    // The paged reader stops at the same record.
+    let mut data = Vec::new();
+    long(&mut data, 1 << 40);
+    let file = container(&schema, "null", &[(1, data)]);
+    let mut reader = AvroReader::new(file.as_slice()).unwrap();
+    assert!(reader.next().unwrap().is_err());
+
+    // A block of 2^40 null records.
+    let err = parse_avro(&container(&json!("null"), "null", &[(1 << 40, vec![])])).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Data);
+    assert!(
+        err.message.contains("more than the limit"),
+        "{}",
+        err.message
+    );
+    let err = parse_avro(&container(&json!("null"), "null", &[(5, vec![0; 2])])).unwrap_err();
+    assert!(
+        err.message.contains("claims 5 records in 2 bytes"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn test_avro_caps_items_per_array() {
+    let schema = json!({"type": "array", "items": "boolean"});
+    let mut data = Vec::new();
+    long(&mut data, (MAX_COUNT + 1) as i64);
+    let err = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Data);
+    assert!(err.message.contains("More than"), "{}", err.message);
+
+    let mut data = Vec::new();
+    long(&mut data, 2);
+    data.extend_from_slice(&[1, 0]);
+    long(&mut data, 0);
+    let (records, _) = parse_avro(&container(&schema, "null", &[(1, data)])).unwrap();
+    assert_eq!(records, json!([[true, false]]));
+}
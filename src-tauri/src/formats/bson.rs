@@ -0,0 +1,114 @@
+//! BSON decoding and encoding, done by the `bson` crate. Documents map to the same tree as
+//! relaxed Extended JSON (see `formats::ejson`): numbers are plain, and typed values such as
+//! `{"$oid": ...}`, `{"$numberDecimal": ...}` and `{"$date": ...}` are single-key objects.
+//! Generic binary data uses the shared `{"$bytes": ...}` form.
+//!
+//! A `.bson` dump holds documents back to back; a single document parses to an object and
+//! anything else to an array of documents.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::ejson;
+use bson::raw::{RawBsonRef, RawDocument};
+use serde_json::Value;
+
+/// Nesting limit, so a crafted input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+fn error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("bson", kind, message)
+}
+
+fn raw_error(e: bson::raw::Error, at: usize) -> ParseError {
+    let kind = match e.kind {
+        bson::raw::ErrorKind::Utf8EncodingError(_) => ParseErrorKind::Data,
+        _ => ParseErrorKind::Syntax,
+    };
+    error(kind, format!("{} in the document at byte {}", e, at))
+}
+
+/// The `bson` crate reads nested documents recursively and without a limit, so their depth is
+/// checked first, with a stack of its own.
+fn check_depth(document: &RawDocument, at: usize) -> Result<(), ParseError> {
+    let mut stack = vec![document.iter()];
+    while let Some(elements) = stack.last_mut() {
+        let Some(element) = elements.next() else {
+            stack.pop();
+            continue;
+        };
+        let nested = match element.map_err(|e| raw_error(e, at))?.1 {
+            RawBsonRef::Document(document) => document,
+            RawBsonRef::Array(array) => {
+                RawDocument::from_bytes(array.as_bytes()).map_err(|e| raw_error(e, at))?
+            }
+            RawBsonRef::JavaScriptCodeWithScope(code) => code.scope,
+            _ => continue,
+        };
+        if stack.len() >= MAX_DEPTH {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!(
+                    "Nesting deeper than {} levels in the document at byte {}",
+                    MAX_DEPTH, at
+                ),
+            ));
+        }
+        stack.push(nested.iter());
+    }
+    Ok(())
+}
+
+pub fn parse_bson(bytes: &[u8]) -> Result<Value, ParseError> {
+    let mut documents = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let at = bytes.len() - rest.len();
+        let Some(prefix) = rest.first_chunk::<4>() else {
+            return Err(error(
+                ParseErrorKind::Eof,
+                format!("Unexpected end of data at byte {}", bytes.len()),
+            ));
+        };
+        let len = usize::try_from(i32::from_le_bytes(*prefix))
+            .ok()
+            .filter(|len| (5..=rest.len()).contains(len))
+            .ok_or_else(|| {
+                error(
+                    ParseErrorKind::Syntax,
+                    format!("Invalid document length at byte {}", at),
+                )
+            })?;
+        let (document, tail) = rest.split_at(len);
+        rest = tail;
+        let document = RawDocument::from_bytes(document).map_err(|e| raw_error(e, at))?;
+        check_depth(document, at)?;
+        let document = bson::Document::try_from(document).map_err(|e| raw_error(e, at))?;
+        documents.push(ejson::from_extended_json(
+            bson::Bson::Document(document).into_relaxed_extjson(),
+        ));
+    }
+    Ok(if documents.len() == 1 {
+        documents.pop().unwrap()
+    } else {
+        Value::Array(documents)
+    })
+}
+
+/// Encodes an object as one BSON document, or an array of objects as a dump of documents.
+pub fn to_bson(value: &Value) -> Result<Vec<u8>, String> {
+    let documents = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let mut out = Vec::new();
+    for document in documents {
+        let bson::Bson::Document(document) = ejson::to_bson(document)? else {
+            return Err("BSON documents must be objects".to_string());
+        };
+        document.to_writer(&mut out).map_err(|e| e.to_string())?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "bson_test.rs"]
+mod bson_test;
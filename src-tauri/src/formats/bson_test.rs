@@ -0,0 +1,125 @@
+use super::*;
+use serde_json::json;
+
+/// Wraps raw elements in a document with the length prefix and terminator.
+fn document(elements: &[u8]) -> Vec<u8> {
+    let mut out = ((elements.len() + 5) as i32).to_le_bytes().to_vec();
+    out.extend(elements);
+    out.push(0);
+    out
+}
+
+#[test]
+fn test_parses_scalar_elements() {
+    let mut elements = vec![0x02, b's', 0, 3, 0, 0, 0, b'h', b'i', 0];
+    elements.extend([0x10, b'i', 0, 42, 0, 0, 0]);
+    elements.extend([0x12, b'l', 0]);
+    elements.extend(5_000_000_000i64.to_le_bytes());
+    elements.extend([0x01, b'd', 0]);
+    elements.extend(1.5f64.to_le_bytes());
+    elements.extend([0x08, b'b', 0, 1, 0x0a, b'n', 0]);
+    assert_eq!(
+        parse_bson(&document(&elements)).unwrap(),
+        json!({"s": "hi", "i": 42, "l": 5_000_000_000i64, "d": 1.5, "b": true, "n": null})
+    );
+}
+
+#[test]
+fn test_typed_values_become_extended_json() {
+    let mut elements = vec![0x07, b'_', b'i', b'd', 0];
+    elements.extend([0x65, 0x53, 0xf1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    elements.extend([0x09, b'a', b't', 0]);
+    elements.extend(1_700_000_000_123i64.to_le_bytes());
+    elements.extend([0x05, b'b', 0, 2, 0, 0, 0, 0, 0xde, 0xad]);
+    elements.extend([0x05, b'u', 0, 1, 0, 0, 0, 4, 0xff]);
+    assert_eq!(
+        parse_bson(&document(&elements)).unwrap(),
+        json!({
+            "_id": {"$oid": "6553f1000000000000000001"},
+            "at": {"$date": "2023-11-14T22:13:20.123Z"},
+            "b": {"$bytes": "3q0="},
+            "u": {"$binary": {"base64": "/w==", "subType": "04"}}
+        })
+    );
+}
+
+#[test]
+fn test_arrays_and_nested_documents() {
+    let inner = document(&[0x10, b'0', 0, 1, 0, 0, 0, 0x10, b'1', 0, 2, 0, 0, 0]);
+    let mut elements = vec![0x04, b'a', 0];
+    elements.extend(&inner);
+    elements.extend([0x03, b'o', 0]);
+    elements.extend(document(&[0x0a, b'x', 0]));
+    assert_eq!(
+        parse_bson(&document(&elements)).unwrap(),
+        json!({"a": [1, 2], "o": {"x": null}})
+    );
+}
+
+#[test]
+fn test_dumps_with_several_documents_become_arrays() {
+    let mut bytes = document(&[0x10, b'n', 0, 1, 0, 0, 0]);
+    bytes.extend(document(&[0x10, b'n', 0, 2, 0, 0, 0]));
+    assert_eq!(parse_bson(&bytes).unwrap(), json!([{"n": 1}, {"n": 2}]));
+    assert_eq!(parse_bson(&[]).unwrap(), json!([]));
+}
+
+#[test]
+fn test_wrong_lengths_are_errors() {
+    let mut bytes = document(&[0x10, b'n', 0, 1, 0, 0, 0]);
+    bytes[0] += 1;
+    assert_eq!(parse_bson(&bytes).unwrap_err().kind, ParseErrorKind::Syntax);
+    let error = parse_bson(&bytes[..6]).unwrap_err();
+    assert_eq!(error.format, "bson");
+}
+
+#[test]
+fn test_decimal128_round_trips() {
+    for decimal in ["1.10", "-0.000001", "1E-7", "1E+3", "-Infinity", "NaN"] {
+        let value = json!({"d": {"$numberDecimal": decimal}});
+        assert_eq!(parse_bson(&to_bson(&value).unwrap()).unwrap(), value);
+    }
+    assert!(to_bson(&json!({"d": {"$numberDecimal": "1.2.3"}})).is_err());
+}
+
+#[test]
+fn test_deep_nesting_is_rejected() {
+    let mut bytes = document(&[0x0a, b'x', 0]);
+    for _ in 0..200 {
+        let mut elements = vec![0x03, b'o', 0];
+        elements.extend(&bytes);
+        bytes = document(&elements);
+    }
+    assert_eq!(parse_bson(&bytes).unwrap_err().kind, ParseErrorKind::Data);
+}
+
+#[test]
+fn test_encoding_round_trips_typed_values() {
+    let value = json!({
+        "_id": {"$oid": "6553f1000000000000000001"},
+        "small": 7,
+        "large": 5_000_000_000i64,
+        "ratio": 0.5,
+        "price": {"$numberDecimal": "19.99"},
+        "at": {"$date": "2023-11-14T22:13:20.123Z"},
+        "ancient": {"$date": {"$numberLong": "-99999999999999999"}},
+        "blob": {"$bytes": "3q0="},
+        "uuid": {"$binary": {"base64": "AAECAwQFBgcICQoLDA0ODw==", "subType": "04"}},
+        "re": {"$regularExpression": {"pattern": "^a", "options": "i"}},
+        "ts": {"$timestamp": {"t": 1700000000, "i": 3}},
+        "code": {"$code": "x => x"},
+        "nan": {"$numberDouble": "NaN"},
+        "min": {"$minKey": 1},
+        "tags": ["a", {"nested": true}]
+    });
+    assert_eq!(parse_bson(&to_bson(&value).unwrap()).unwrap(), value);
+}
+
+#[test]
+fn test_encoding_arrays_writes_a_dump() {
+    let value = json!([{"n": 1}, {"n": 2}]);
+    assert_eq!(parse_bson(&to_bson(&value).unwrap()).unwrap(), value);
+    assert!(to_bson(&json!([1])).is_err());
+    assert!(to_bson(&json!({"$oid": "6553f1000000000000000001"})).is_err());
+    assert!(to_bson(&json!({"_id": {"$oid": "nope"}})).is_err());
+}
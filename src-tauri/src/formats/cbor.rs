@@ -0,0 +1,199 @@
+//! CBOR decoding and encoding. Byte strings become `{"$bytes": ...}` and date tags 0 and 1
+//! become `{"$date": ...}`. Bignums (tags 2 and 3) become plain numbers; other tags are kept as
+//! `{"$tag": {"tag": n, "value": ...}}`.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::tagged::{self, key_string};
+use chrono::{DateTime, SecondsFormat, Utc};
+use ciborium::value::Value as Cbor;
+use serde_json::{json, Map, Number, Value};
+
+pub const TAG: &str = "$tag";
+
+const TAG_DATE_STRING: u64 = 0;
+const TAG_EPOCH: u64 = 1;
+const TAG_POSITIVE_BIGNUM: u64 = 2;
+const TAG_NEGATIVE_BIGNUM: u64 = 3;
+
+/// Nesting limit, so a crafted input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+fn error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("cbor", kind, message)
+}
+
+fn tag(tag: u64, content: Cbor) -> Result<Value, ParseError> {
+    let value = to_json(content)?;
+    let invalid = || {
+        error(
+            ParseErrorKind::Data,
+            format!("Invalid content for tag {}", tag),
+        )
+    };
+    match tag {
+        TAG_DATE_STRING => {
+            let text = value.as_str().ok_or_else(invalid)?;
+            let datetime = DateTime::parse_from_rfc3339(text).map_err(|_| invalid())?;
+            Ok(tagged::date(datetime.with_timezone(&Utc)))
+        }
+        TAG_EPOCH => {
+            let seconds = value.as_f64().ok_or_else(invalid)?;
+            let nanos = (seconds.rem_euclid(1.0) * 1e9).round() as u32;
+            DateTime::from_timestamp(seconds.floor() as i64, nanos.min(999_999_999))
+                .map(tagged::date)
+                .ok_or_else(invalid)
+        }
+        // ciborium reads bignums that fit in 128 bits as integers; longer ones arrive here.
+        TAG_POSITIVE_BIGNUM | TAG_NEGATIVE_BIGNUM => {
+            let data = tagged::as_bytes(&value).ok_or_else(invalid)?;
+            let magnitude = decimal_from_bytes(&data);
+            let text = if tag == TAG_NEGATIVE_BIGNUM {
+                // The encoded value is -1 - n.
+                format!("-{}", decimal_add_one(&magnitude))
+            } else {
+                magnitude
+            };
+            Ok(Value::Number(
+                text.parse::<Number>().map_err(|_| invalid())?,
+            ))
+        }
+        _ => Ok(tagged::tagged(TAG, json!({ "tag": tag, "value": value }))),
+    }
+}
+
+fn to_json(value: Cbor) -> Result<Value, ParseError> {
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(n) => {
+            Value::Number(i128::from(n).to_string().parse().expect("valid integer"))
+        }
+        Cbor::Float(f) => tagged::float(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(data) => tagged::bytes(&data),
+        Cbor::Array(items) => {
+            Value::Array(items.into_iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Cbor::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                map.insert(key_string(to_json(key)?), to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        Cbor::Tag(n, content) => tag(n, *content)?,
+        _ => Value::Null,
+    })
+}
+
+/// Renders a big-endian unsigned integer of any length in decimal.
+fn decimal_from_bytes(bytes: &[u8]) -> String {
+    // Little-endian base-10 digits.
+    let mut digits = vec![0u8];
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            let n = *digit as u32 * 256 + carry;
+            *digit = (n % 10) as u8;
+            carry = n / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    while digits.len() > 1 && digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits.iter().rev().map(|d| (b'0' + d) as char).collect()
+}
+
+fn decimal_add_one(decimal: &str) -> String {
+    let mut digits: Vec<u8> = decimal.bytes().rev().map(|b| b - b'0').collect();
+    for digit in digits.iter_mut() {
+        if *digit == 9 {
+            *digit = 0;
+        } else {
+            *digit += 1;
+            return digits.iter().rev().map(|d| (b'0' + d) as char).collect();
+        }
+    }
+    digits.push(1);
+    digits.iter().rev().map(|d| (b'0' + d) as char).collect()
+}
+
+pub fn parse_cbor(bytes: &[u8]) -> Result<Value, ParseError> {
+    let mut rest = bytes;
+    let value: Cbor = ciborium::de::from_reader_with_recursion_limit(&mut rest, MAX_DEPTH)
+        .map_err(|e| match e {
+            ciborium::de::Error::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => error(
+                ParseErrorKind::Eof,
+                format!("Unexpected end of data at byte {}", bytes.len()),
+            ),
+            ciborium::de::Error::RecursionLimitExceeded => error(
+                ParseErrorKind::Data,
+                format!("Nesting deeper than {} levels", MAX_DEPTH),
+            ),
+            ciborium::de::Error::Syntax(offset) => error(
+                ParseErrorKind::Syntax,
+                format!("Invalid CBOR at byte {}", offset),
+            ),
+            ciborium::de::Error::Semantic(Some(offset), message) => error(
+                ParseErrorKind::Syntax,
+                format!("{} at byte {}", message, offset),
+            ),
+            e => error(ParseErrorKind::Syntax, e.to_string()),
+        })?;
+    if !rest.is_empty() {
+        return Err(error(
+            ParseErrorKind::Syntax,
+            format!(
+                "Unexpected data after the value at byte {}",
+                bytes.len() - rest.len()
+            ),
+        ));
+    }
+    to_json(value)
+}
+
+fn from_json(value: &Value) -> Cbor {
+    match value {
+        Value::Null => Cbor::Null,
+        Value::Bool(b) => Cbor::Bool(*b),
+        // Integers past 64 bits become bignums, as far as 128 bits go.
+        Value::Number(n) => match n.to_string().parse::<i128>() {
+            Ok(n) => Cbor::from(n),
+            // Longer integers and decimals are rounded; see `numbers::serializer_warnings`.
+            Err(_) => Cbor::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => Cbor::Text(s.clone()),
+        Value::Array(items) => Cbor::Array(items.iter().map(from_json).collect()),
+        Value::Object(map) => {
+            if let Some(data) = tagged::as_bytes(value) {
+                Cbor::Bytes(data)
+            } else if let Some(datetime) = tagged::as_date(value) {
+                let text = datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+                Cbor::Tag(TAG_DATE_STRING, Box::new(Cbor::Text(text)))
+            } else if let Some((tag, content)) = tagged::untag(value, TAG)
+                .and_then(|t| Some((t.get("tag")?.as_u64()?, t.get("value")?)))
+            {
+                Cbor::Tag(tag, Box::new(from_json(content)))
+            } else {
+                let entries = map
+                    .iter()
+                    .map(|(key, item)| (Cbor::Text(key.clone()), from_json(item)));
+                Cbor::Map(entries.collect())
+            }
+        }
+    }
+}
+
+pub fn to_cbor(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(&from_json(value), &mut out).expect("writing to a Vec");
+    out
+}
+
+#[cfg(test)]
+#[path = "cbor_test.rs"]
+mod cbor_test;
@@ -0,0 +1,106 @@
+use super::*;
+
+#[test]
+fn test_parses_scalars_and_containers() {
+    // {"a": [1, -1, true, null], "b": "hi"}
+    let bytes = [
+        0xa2, 0x61, b'a', 0x84, 0x01, 0x20, 0xf5, 0xf6, 0x61, b'b', 0x62, b'h', b'i',
+    ];
+    assert_eq!(
+        parse_cbor(&bytes).unwrap(),
+        json!({"a": [1, -1, true, null], "b": "hi"})
+    );
+}
+
+#[test]
+fn test_parses_indefinite_lengths() {
+    // [_ "ab" "c"] as a chunked string inside an indefinite array, then a map.
+    let bytes = [
+        0x9f, 0x7f, 0x62, b'a', b'b', 0x61, b'c', 0xff, 0xbf, 0x61, b'k', 0x00, 0xff, 0xff,
+    ];
+    assert_eq!(parse_cbor(&bytes).unwrap(), json!(["abc", {"k": 0}]));
+}
+
+#[test]
+fn test_parses_floats_of_every_width() {
+    let mut bytes = vec![0x83, 0xf9, 0x3e, 0x00, 0xfa];
+    bytes.extend(0.5f32.to_be_bytes());
+    bytes.push(0xfb);
+    bytes.extend(1e300f64.to_be_bytes());
+    assert_eq!(parse_cbor(&bytes).unwrap(), json!([1.5, 0.5, 1e300]));
+}
+
+#[test]
+fn test_date_tags_become_dates() {
+    let mut bytes = vec![0x82, 0xc0, 0x74];
+    bytes.extend(b"2023-11-14T22:13:20Z");
+    bytes.extend([0xc1, 0x1a, 0x65, 0x53, 0xf1, 0x00]);
+    assert_eq!(
+        parse_cbor(&bytes).unwrap(),
+        json!([{"$date": "2023-11-14T22:13:20Z"}, {"$date": "2023-11-14T22:13:20Z"}])
+    );
+}
+
+#[test]
+fn test_bignums_become_exact_numbers() {
+    // 2^64 and -2^64 - 1
+    let bytes = [
+        0x82, 0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let value = parse_cbor(&bytes).unwrap();
+    assert_eq!(
+        value.to_string(),
+        "[18446744073709551616,-18446744073709551617]"
+    );
+}
+
+#[test]
+fn test_other_tags_are_kept() {
+    let bytes = [0x82, 0xd8, 0x20, 0x61, b'u', 0xf7];
+    assert_eq!(
+        parse_cbor(&bytes).unwrap(),
+        json!([{"$tag": {"tag": 32, "value": "u"}}, null])
+    );
+}
+
+#[test]
+fn test_unassigned_simple_values_are_rejected() {
+    let error = parse_cbor(&[0x81, 0xf0]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+}
+
+#[test]
+fn test_truncated_input_is_an_eof_error() {
+    let error = parse_cbor(&[0x82, 0x01]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Eof);
+    assert_eq!(error.format, "cbor");
+}
+
+#[test]
+fn test_stray_break_is_a_syntax_error() {
+    let error = parse_cbor(&[0xff]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+}
+
+#[test]
+fn test_encoding_round_trips_tagged_values() {
+    let value = json!({
+        "id": 300,
+        "neg": -200,
+        "big": u64::MAX,
+        "ratio": 0.25,
+        "blob": {"$bytes": "3q2+7w=="},
+        "when": {"$date": "2023-11-14T22:13:20.500Z"},
+        "uri": {"$tag": {"tag": 32, "value": "https://example.com"}},
+        "huge": 18446744073709551616u128,
+        "list": (0..30).collect::<Vec<_>>()
+    });
+    assert_eq!(parse_cbor(&to_cbor(&value)).unwrap(), value);
+}
+
+#[test]
+fn test_encoding_uses_the_smallest_head() {
+    assert_eq!(to_cbor(&json!(10)), [0x0a]);
+    assert_eq!(to_cbor(&json!(-500)), [0x39, 0x01, 0xf3]);
+    assert_eq!(to_cbor(&json!("a")), [0x61, b'a']);
+}
@@ -0,0 +1,197 @@
+//! Compressed input and output: gzip and zstd streams and zip archives, recognized by their
+//! magic bytes rather than the file name.
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io::{Cursor, Read, Write};
+use zip::ZipArchive;
+
+/// Upper bound on decompressed size, so a small archive cannot exhaust memory.
+pub(crate) const MAX_DECOMPRESSED_BYTES: u64 = 1 << 30;
+
+/// zstd level used for export; the library default trades speed and size well.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Folder of AppleDouble metadata files that the macOS Finder adds to archives.
+const MACOS_METADATA: &str = "__MACOSX/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Zip,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+pub struct Decompressed {
+    pub bytes: Vec<u8>,
+    pub compression: Option<Compression>,
+    /// Name of the zip entry that was read.
+    pub entry: Option<String>,
+}
+
+pub fn detect_compression(bytes: &[u8]) -> Option<Compression> {
+    match bytes {
+        [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+        [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some(Compression::Zip),
+        _ => None,
+    }
+}
+
+/// Reads a decompressing reader to the end, failing past `MAX_DECOMPRESSED_BYTES`.
+pub(crate) fn read_limited(reader: impl Read) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    reader
+        .take(MAX_DECOMPRESSED_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to decompress: {}", e))?;
+    if bytes.len() as u64 > MAX_DECOMPRESSED_BYTES {
+        return Err(format!(
+            "Decompressed content exceeds {} MiB",
+            MAX_DECOMPRESSED_BYTES >> 20
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Reads `reader` to the end, decompressing gzip and zstd on the fly. Zip archives need
+/// their central directory at the end, so they are buffered and `entry` picks the file to
+/// read; it can be left out when the archive holds a single file.
+pub fn decompress(mut reader: impl Read, entry: Option<&str>) -> Result<Decompressed, String> {
+    let mut magic = Vec::with_capacity(4);
+    (&mut reader)
+        .take(4)
+        .read_to_end(&mut magic)
+        .map_err(|e| e.to_string())?;
+    let compression = detect_compression(&magic);
+    let mut source = magic.as_slice().chain(reader);
+
+    let (bytes, entry) = match compression {
+        Some(Compression::Gzip) => (read_limited(MultiGzDecoder::new(source))?, None),
+        Some(Compression::Zstd) => {
+            let decoder = zstd::stream::read::Decoder::new(source)
+                .map_err(|e| format!("Failed to decompress: {}", e))?;
+            (read_limited(decoder)?, None)
+        }
+        Some(Compression::Zip) => {
+            let mut archive = Vec::new();
+            source
+                .read_to_end(&mut archive)
+                .map_err(|e| e.to_string())?;
+            let (name, bytes) = read_zip_entry(&archive, entry)?;
+            (bytes, Some(name))
+        }
+        None => {
+            let mut bytes = Vec::new();
+            source.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+            (bytes, None)
+        }
+    };
+    Ok(Decompressed {
+        bytes,
+        compression,
+        entry,
+    })
+}
+
+/// Deserializes the compression of an output, which cannot be a zip archive.
+pub(crate) fn output_compression<'de, D>(deserializer: D) -> Result<Option<Compression>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Compression>::deserialize(deserializer)? {
+        Some(Compression::Zip) => Err(serde::de::Error::custom(
+            "Writing zip archives is not supported",
+        )),
+        compression => Ok(compression),
+    }
+}
+
+pub fn compress(bytes: &[u8], compression: Compression) -> Result<Vec<u8>, String> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes).map_err(|e| e.to_string())?;
+            encoder.finish().map_err(|e| e.to_string())
+        }
+        Compression::Zstd => zstd::stream::encode_all(bytes, ZSTD_LEVEL).map_err(|e| e.to_string()),
+        Compression::Zip => Err("Writing zip archives is not supported".to_string()),
+    }
+}
+
+fn invalid(error: impl std::fmt::Display) -> String {
+    format!("Invalid zip archive: {}", error)
+}
+
+fn open_zip(archive: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>, String> {
+    ZipArchive::new(Cursor::new(archive)).map_err(invalid)
+}
+
+/// Reads the central directory of a zip archive. Directories and macOS metadata are skipped.
+fn zip_entries(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<Vec<ArchiveEntry>, String> {
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(invalid)?;
+        if file.is_dir() || file.name().starts_with(MACOS_METADATA) {
+            continue;
+        }
+        entries.push(ArchiveEntry {
+            name: file.name().to_string(),
+            size: file.size(),
+            compressed_size: file.compressed_size(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Lists the files in a zip archive so the user can pick one.
+pub fn list_zip_entries(archive: &[u8]) -> Result<Vec<ArchiveEntry>, String> {
+    zip_entries(&mut open_zip(archive)?)
+}
+
+fn read_zip_entry(archive: &[u8], name: Option<&str>) -> Result<(String, Vec<u8>), String> {
+    let mut archive = open_zip(archive)?;
+    let mut entries = zip_entries(&mut archive)?;
+    let name = match name {
+        Some(name) => {
+            entries
+                .into_iter()
+                .find(|e| e.name == name)
+                .ok_or_else(|| format!("No entry named {} in the archive", name))?
+                .name
+        }
+        None if entries.len() == 1 => entries.remove(0).name,
+        None => {
+            return Err(format!(
+                "The archive holds {} files; choose one to open",
+                entries.len()
+            ))
+        }
+    };
+    let index = archive
+        .index_for_name(&name)
+        .ok_or_else(|| invalid(&name))?;
+    if archive.by_index_raw(index).map_err(invalid)?.encrypted() {
+        return Err(format!("{} is encrypted", name));
+    }
+    let file = archive
+        .by_index(index)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    // The reader checks the CRC once the entry is read to the end.
+    let bytes = read_limited(file).map_err(|e| format!("{}: {}", name, e))?;
+    Ok((name, bytes))
+}
+
+#[cfg(test)]
+#[path = "compression_test.rs"]
+mod compression_test;
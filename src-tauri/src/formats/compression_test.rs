@@ -0,0 +1,119 @@
+use super::*;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Builds a zip archive; entries flagged `true` are deflated, the rest stored. Names ending
+/// in `/` become directories.
+fn zip_archive(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content, deflate) in entries {
+        let method = if *deflate {
+            CompressionMethod::Deflated
+        } else {
+            CompressionMethod::Stored
+        };
+        let options = SimpleFileOptions::default().compression_method(method);
+        if name.ends_with('/') {
+            writer.add_directory(*name, options).unwrap();
+        } else {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_detects_magic_bytes() {
+    assert_eq!(
+        detect_compression(&[0x1f, 0x8b, 8]),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(
+        detect_compression(&[0x28, 0xb5, 0x2f, 0xfd]),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(detect_compression(b"PK\x03\x04"), Some(Compression::Zip));
+    assert_eq!(detect_compression(b"{}"), None);
+    assert_eq!(detect_compression(b""), None);
+}
+
+#[test]
+fn test_round_trips_streams() {
+    let content = b"{\"id\": 1}\n{\"id\": 2}\n".repeat(100);
+    for method in [Compression::Gzip, Compression::Zstd] {
+        let compressed = compress(&content, method).unwrap();
+        assert_eq!(detect_compression(&compressed), Some(method));
+        let decompressed = decompress(compressed.as_slice(), None).unwrap();
+        assert_eq!(decompressed.bytes, content);
+        assert_eq!(decompressed.compression, Some(method));
+    }
+}
+
+#[test]
+fn test_plain_input_passes_through() {
+    let decompressed = decompress(&b"[1]"[..], None).unwrap();
+    assert_eq!(decompressed.bytes, b"[1]");
+    assert_eq!(decompressed.compression, None);
+
+    let decompressed = decompress(&b""[..], None).unwrap();
+    assert!(decompressed.bytes.is_empty());
+}
+
+#[test]
+fn test_reads_zip_entries() {
+    let archive = zip_archive(&[
+        ("export/", b"", false),
+        ("export/users.json", b"[{\"name\": \"Ada\"}]", true),
+        ("export/readme.txt", b"hello", false),
+    ]);
+    let entries = list_zip_entries(&archive).unwrap();
+    assert_eq!(
+        entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+        vec!["export/users.json", "export/readme.txt"]
+    );
+    assert_eq!(entries[0].size, 17);
+
+    let decompressed = decompress(archive.as_slice(), Some("export/users.json")).unwrap();
+    assert_eq!(decompressed.bytes, b"[{\"name\": \"Ada\"}]");
+    assert_eq!(decompressed.entry.as_deref(), Some("export/users.json"));
+
+    let err = decompress(archive.as_slice(), None).err().unwrap();
+    assert_eq!(err, "The archive holds 2 files; choose one to open");
+    assert!(decompress(archive.as_slice(), Some("missing.json")).is_err());
+}
+
+#[test]
+fn test_single_entry_zip_needs_no_name() {
+    let archive = zip_archive(&[("data.csv", b"a,b\n1,2\n", false)]);
+    let decompressed = decompress(archive.as_slice(), None).unwrap();
+    assert_eq!(decompressed.bytes, b"a,b\n1,2\n");
+}
+
+#[test]
+fn test_rejects_corrupt_zip() {
+    let mut archive = zip_archive(&[("data.json", b"[1, 2, 3]", false)]);
+    let data = archive.windows(9).position(|w| w == b"[1, 2, 3]").unwrap();
+    archive[data] = b'{';
+    let err = decompress(archive.as_slice(), None).err().unwrap();
+    assert!(err.contains("checksum"));
+    assert!(list_zip_entries(b"PK\x03\x04garbage").is_err());
+}
+
+#[test]
+fn test_skips_macos_metadata() {
+    let archive = zip_archive(&[
+        ("data.json", b"[1]", true),
+        ("__MACOSX/", b"", false),
+        ("__MACOSX/._data.json", b"\0\x05\x16\x07", false),
+    ]);
+    let entries = list_zip_entries(&archive).unwrap();
+    assert_eq!(entries.len(), 1);
+    let decompressed = decompress(archive.as_slice(), None).unwrap();
+    assert_eq!(decompressed.entry.as_deref(), Some("data.json"));
+}
+
+#[test]
+fn test_zip_output_is_not_supported() {
+    assert!(compress(b"{}", Compression::Zip).is_err());
+}
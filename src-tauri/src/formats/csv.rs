@@ -0,0 +1,457 @@
+//! CSV import with a configurable dialect. Anything the caller leaves unset (delimiter, quote
+//! character, header row) is sniffed from the first records.
+
+use crate::commands::parser::{csv_error, ParseError, ParseErrorKind};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use csv::{ReaderBuilder, StringRecord, Trim};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+
+/// Delimiters tried when sniffing, in order of preference when they tie.
+const DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// Number of records inspected when sniffing the delimiter.
+const SNIFF_RECORDS: usize = 20;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvOptions {
+    /// Field delimiter, sniffed from the content when unset.
+    pub delimiter: Option<char>,
+    /// Quote character, sniffed from the content when unset.
+    pub quote: Option<char>,
+    /// Whether the first record holds column names, sniffed from the content when unset.
+    pub has_headers: Option<bool>,
+    /// Trim whitespace around headers and fields.
+    pub trim: bool,
+    /// Turn numbers, booleans and empty cells into JSON values; off keeps every cell a string.
+    pub infer_types: bool,
+    /// Rebuild nested objects and arrays from column names like `user.name` and `tags[0]`.
+    pub unflatten: bool,
+    /// Separator between keys in column names when unflattening.
+    pub path_separator: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            quote: None,
+            has_headers: None,
+            trim: false,
+            infer_types: true,
+            unflatten: false,
+            path_separator: ".".to_string(),
+        }
+    }
+}
+
+/// How CSV export writes arrays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvArrays {
+    /// One column per item: `tags[0]`, `tags[1]`, `items[0].id`.
+    #[default]
+    Columns,
+    /// All items in one cell, separated by `arraySeparator`.
+    Join,
+    /// One row per item, repeating the fields around the array. Sibling arrays share rows.
+    Rows,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvExportOptions {
+    pub delimiter: char,
+    /// Flattened paths to write, in this order; every path found when empty.
+    pub columns: Vec<String>,
+    /// Header text for flattened paths, for the columns that should not be named by their path.
+    pub headers: BTreeMap<String, String>,
+    pub arrays: CsvArrays,
+    /// Separator between items joined into one cell.
+    pub array_separator: String,
+    /// Separator between keys in flattened paths, `.` in `user.name`.
+    pub path_separator: String,
+    /// Start with a UTF-8 byte order mark, which Excel needs to read non-ASCII text correctly.
+    pub bom: bool,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            columns: Vec::new(),
+            headers: BTreeMap::new(),
+            arrays: CsvArrays::Columns,
+            array_separator: "; ".to_string(),
+            path_separator: ".".to_string(),
+            bom: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    /// `YYYY-MM-DD`. Dates stay strings in the value; the type only reports what was found.
+    Date,
+    /// RFC 3339 or `YYYY-MM-DD HH:MM:SS`, also kept as a string.
+    Datetime,
+    String,
+}
+
+impl CellType {
+    /// The narrowest type that covers both, e.g. integers and decimals make a number column.
+    fn merge(self, other: CellType) -> CellType {
+        use CellType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Null, t) | (t, Null) => t,
+            (Integer, Number) | (Number, Integer) => Number,
+            (Date, Datetime) | (Datetime, Date) => Datetime,
+            _ => String,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CsvColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: CellType,
+}
+
+/// The dialect a CSV document was read with, including whatever was sniffed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+    pub has_headers: bool,
+    pub columns: Vec<CsvColumn>,
+}
+
+fn ascii_byte(c: char, what: &str) -> Result<u8, ParseError> {
+    if c.is_ascii() {
+        Ok(c as u8)
+    } else {
+        Err(ParseError::new(
+            "csv",
+            ParseErrorKind::Data,
+            format!("CSV {} must be an ASCII character, got '{}'", what, c),
+        ))
+    }
+}
+
+fn is_null_cell(text: &str) -> bool {
+    text.is_empty() || text == "null" || text == "NULL"
+}
+
+fn cell_type(text: &str) -> CellType {
+    if is_null_cell(text) {
+        return CellType::Null;
+    }
+    if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") {
+        return CellType::Boolean;
+    }
+    // JSON number syntax rejects leading zeros, so codes like `007` stay strings. serde_json
+    // skips surrounding whitespace, which untrimmed cells must keep.
+    if text.trim() == text && serde_json::from_str::<Number>(text).is_ok() {
+        return if text.contains(['.', 'e', 'E']) {
+            CellType::Number
+        } else {
+            CellType::Integer
+        };
+    }
+    if NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok() {
+        return CellType::Date;
+    }
+    if DateTime::parse_from_rfc3339(text).is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").is_ok()
+    {
+        return CellType::Datetime;
+    }
+    CellType::String
+}
+
+/// Converts a cell to its column's type. Text columns keep every cell as written, so a `null`
+/// nickname stays a string; other columns have no text for empty and `null` cells but null.
+fn typed_value(text: &str, kind: CellType) -> Value {
+    match kind {
+        CellType::String => Value::String(text.to_string()),
+        _ if is_null_cell(text) => Value::Null,
+        CellType::Boolean => Value::Bool(text.eq_ignore_ascii_case("true")),
+        CellType::Integer | CellType::Number => serde_json::from_str(text)
+            .map(Value::Number)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Double quotes win unless only single quotes ever open a field.
+fn sniff_quote(content: &str) -> u8 {
+    let opens_field = |quote: char| {
+        content.lines().any(|line| {
+            line.starts_with(quote)
+                || DELIMITERS
+                    .iter()
+                    .any(|&d| line.contains(&format!("{}{}", d as char, quote)))
+        })
+    };
+    if !opens_field('"') && opens_field('\'') {
+        b'\''
+    } else {
+        b'"'
+    }
+}
+
+/// Number of fields per record when every sampled record agrees on it.
+fn consistent_width(content: &str, delimiter: u8, quote: u8) -> Option<usize> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .quote(quote)
+        .from_reader(content.as_bytes());
+    let mut width = None;
+    for record in reader.records().take(SNIFF_RECORDS) {
+        let len = record.ok()?.len();
+        match width {
+            None => width = Some(len),
+            Some(w) if w != len => return None,
+            Some(_) => {}
+        }
+    }
+    width
+}
+
+/// Picks the delimiter that splits the sample into the most columns, consistently.
+fn sniff_delimiter(content: &str, quote: u8) -> u8 {
+    let mut best = (b',', 1);
+    for delimiter in DELIMITERS {
+        if let Some(width) = consistent_width(content, delimiter, quote) {
+            if width > best.1 {
+                best = (delimiter, width);
+            }
+        }
+    }
+    best.0
+}
+
+/// A first record of distinct text cells reads as column names. Blank cells are allowed, like
+/// the index column pandas writes, as long as some cell has a name. Numbers, booleans or dates
+/// in it mean it is data.
+fn looks_like_header(record: &StringRecord) -> bool {
+    record.iter().any(|cell| !cell.is_empty())
+        && record
+            .iter()
+            .all(|cell| cell.is_empty() || cell_type(cell) == CellType::String)
+        && record
+            .iter()
+            .enumerate()
+            .all(|(i, cell)| cell.is_empty() || !record.iter().take(i).any(|other| other == cell))
+}
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Splits a flattened column name like `items[0].tags[1]` into keys and indexes, or `None` when
+/// the name does not read as a path. Indexes must be below `columns`: a row of `n` columns
+/// fills at most `n` array items, so a name like `a[100000]` stays a plain key.
+fn path_segments<'a>(name: &'a str, separator: &str, columns: usize) -> Option<Vec<Segment<'a>>> {
+    let mut segments = Vec::new();
+    for part in name.split(separator) {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() {
+            return None;
+        }
+        segments.push(Segment::Key(key));
+        while !rest.is_empty() {
+            let (index, tail) = rest.strip_prefix('[')?.split_once(']')?;
+            let index: usize = index.parse().ok().filter(|i| *i < columns)?;
+            segments.push(Segment::Index(index));
+            rest = tail;
+        }
+    }
+    Some(segments)
+}
+
+/// Sets `value` at `path` under `target`, creating objects and arrays on the way. Returns false
+/// when the path runs into a value of another shape, such as both `a` and `a.b` having values.
+fn insert_path(target: &mut Value, path: &[Segment], value: Value) -> bool {
+    let Some((first, rest)) = path.split_first() else {
+        if !target.is_null() {
+            return false;
+        }
+        *target = value;
+        return true;
+    };
+    let slot = match first {
+        Segment::Key(key) => {
+            if target.is_null() {
+                *target = Value::Object(Map::new());
+            }
+            let Value::Object(map) = target else {
+                return false;
+            };
+            map.entry(key.to_string()).or_insert(Value::Null)
+        }
+        Segment::Index(index) => {
+            if target.is_null() {
+                *target = Value::Array(Vec::new());
+            }
+            let Value::Array(items) = target else {
+                return false;
+            };
+            if items.len() <= *index {
+                items.resize(*index + 1, Value::Null);
+            }
+            &mut items[*index]
+        }
+    };
+    insert_path(slot, rest, value)
+}
+
+fn unflatten_row(row: &Map<String, Value>, separator: &str) -> Value {
+    let mut nested = Value::Object(Map::new());
+    for (name, value) in row {
+        let path = match path_segments(name, separator, row.len()) {
+            Some(path) => path,
+            None => vec![Segment::Key(name)],
+        };
+        // An empty nested cell usually means the row has no such field, or a shorter array.
+        if path.len() > 1 && (value.is_null() || value.as_str() == Some("")) {
+            continue;
+        }
+        if !insert_path(&mut nested, &path, value.clone()) && !value.is_null() {
+            return Value::Object(row.clone());
+        }
+    }
+    nested
+}
+
+/// Rebuilds nested objects and arrays from flattened keys such as `user.name` and `tags[0]`,
+/// the reverse of CSV export. Arrays are unflattened item by item. Empty nested cells are left
+/// out, and a row whose columns clash, like `a` and `a.b` both holding values, stays flat.
+pub fn unflatten(value: &Value, separator: &str) -> Value {
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| unflatten(v, separator)).collect())
+        }
+        Value::Object(row) => unflatten_row(row, separator),
+        _ => value.clone(),
+    }
+}
+
+/// Reads CSV into an array of objects, one per record, keyed by column name. Without a header
+/// row the columns are named `column1`, `column2`, and so on, as are blank header cells.
+pub fn parse_csv(content: &str, options: &CsvOptions) -> Result<(Value, CsvDialect), ParseError> {
+    if options.unflatten && options.path_separator.is_empty() {
+        return Err(ParseError::new(
+            "csv",
+            ParseErrorKind::Data,
+            "CSV path separator must not be empty",
+        ));
+    }
+    let quote = match options.quote {
+        Some(c) => ascii_byte(c, "quote")?,
+        None => sniff_quote(content),
+    };
+    let delimiter = match options.delimiter {
+        Some(c) => ascii_byte(c, "delimiter")?,
+        None => sniff_delimiter(content, quote),
+    };
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .quote(quote)
+        .trim(if options.trim { Trim::All } else { Trim::None })
+        .from_reader(content.as_bytes());
+    let mut records = reader.records();
+
+    let first = match records.next() {
+        Some(record) => record.map_err(|e| csv_error(content, e))?,
+        None => StringRecord::new(),
+    };
+    let has_headers = options
+        .has_headers
+        .unwrap_or_else(|| first.is_empty() || looks_like_header(&first));
+    let names: Vec<String> = if has_headers {
+        (first.iter().enumerate())
+            .map(|(i, name)| match name {
+                "" => format!("column{}", i + 1),
+                _ => name.to_string(),
+            })
+            .collect()
+    } else {
+        (1..=first.len()).map(|i| format!("column{}", i)).collect()
+    };
+
+    let initial = if options.infer_types {
+        CellType::Null
+    } else {
+        CellType::String
+    };
+    let mut types = vec![initial; names.len()];
+    let data_records = (!has_headers)
+        .then_some(Ok(first))
+        .into_iter()
+        .chain(records);
+    let records = data_records
+        .collect::<Result<Vec<StringRecord>, _>>()
+        .map_err(|e| csv_error(content, e))?;
+    if options.infer_types {
+        for record in &records {
+            for (text, column_type) in record.iter().zip(types.iter_mut()) {
+                *column_type = column_type.merge(cell_type(text));
+            }
+        }
+    }
+    // Cells take their column's type, so a zip column holding `02134` keeps `10001` a string.
+    let rows = records
+        .iter()
+        .map(|record| {
+            let row = names
+                .iter()
+                .zip(record.iter())
+                .zip(&types)
+                .map(|((name, text), &kind)| {
+                    let value = if options.infer_types {
+                        typed_value(text, kind)
+                    } else {
+                        Value::String(text.to_string())
+                    };
+                    (name.clone(), value)
+                });
+            Value::Object(row.collect())
+        })
+        .collect();
+
+    let dialect = CsvDialect {
+        delimiter: delimiter as char,
+        quote: quote as char,
+        has_headers,
+        columns: names
+            .into_iter()
+            .zip(types)
+            .map(|(name, kind)| CsvColumn { name, kind })
+            .collect(),
+    };
+    let mut value = Value::Array(rows);
+    if options.unflatten {
+        value = unflatten(&value, &options.path_separator);
+    }
+    Ok((value, dialect))
+}
+
+#[cfg(test)]
+#[path = "csv_test.rs"]
+mod csv_test;
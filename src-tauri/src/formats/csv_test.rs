@@ -0,0 +1,241 @@
+use super::*;
+use serde_json::json;
+
+fn parse(content: &str) -> (Value, CsvDialect) {
+    parse_csv(content, &CsvOptions::default()).unwrap()
+}
+
+#[test]
+fn test_infers_cell_types() {
+    let (value, dialect) =
+        parse("id,price,active,zip,note,born\n1,9.5,true,007,,2024-01-15\n2,10,FALSE,01234,null,2024-01-16T08:30:00Z\n");
+    assert_eq!(
+        value,
+        json!([
+            {"id": 1, "price": 9.5, "active": true, "zip": "007", "note": null, "born": "2024-01-15"},
+            {"id": 2, "price": 10, "active": false, "zip": "01234", "note": null, "born": "2024-01-16T08:30:00Z"}
+        ])
+    );
+    let types: Vec<CellType> = dialect.columns.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        types,
+        vec![
+            CellType::Integer,
+            CellType::Number,
+            CellType::Boolean,
+            CellType::String,
+            CellType::Null,
+            CellType::Datetime
+        ]
+    );
+}
+
+#[test]
+fn test_cells_take_their_column_type() {
+    let (value, dialect) = parse("zip,flag,n\n02134,true,1\n10001,yes,2.5\n,null,\n");
+    assert_eq!(
+        value,
+        json!([
+            {"zip": "02134", "flag": "true", "n": 1},
+            {"zip": "10001", "flag": "yes", "n": 2.5},
+            {"zip": "", "flag": "null", "n": null}
+        ])
+    );
+    let types: Vec<CellType> = dialect.columns.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        types,
+        vec![CellType::String, CellType::String, CellType::Number]
+    );
+}
+
+#[test]
+fn test_null_text_stays_a_string_in_text_columns() {
+    let (value, dialect) = parse("name,nick,age\nAnn,null,30\nBob,Bobby,\nCy,,null\n");
+    assert_eq!(
+        value,
+        json!([
+            {"name": "Ann", "nick": "null", "age": 30},
+            {"name": "Bob", "nick": "Bobby", "age": null},
+            {"name": "Cy", "nick": "", "age": null}
+        ])
+    );
+    assert_eq!(dialect.columns[1].kind, CellType::String);
+
+    // A column with nothing but empty and `null` cells has no type to keep.
+    let (value, _) = parse("name,nick\nAnn,null\nBob,\n");
+    assert_eq!(
+        value,
+        json!([{"name": "Ann", "nick": null}, {"name": "Bob", "nick": null}])
+    );
+}
+
+#[test]
+fn test_blank_header_cells_are_named_by_position() {
+    let (value, dialect) = parse(",name,city\n0,Ann,Paris\n1,Bob,\n");
+    assert!(dialect.has_headers);
+    assert_eq!(
+        value,
+        json!([
+            {"column1": 0, "name": "Ann", "city": "Paris"},
+            {"column1": 1, "name": "Bob", "city": ""}
+        ])
+    );
+    let (_, dialect) = parse(",\n1,2\n");
+    assert!(!dialect.has_headers);
+}
+
+#[test]
+fn test_type_inference_can_be_disabled() {
+    let options = CsvOptions {
+        infer_types: false,
+        ..Default::default()
+    };
+    let (value, dialect) = parse_csv("id,active,note\n1,true,\n", &options).unwrap();
+    assert_eq!(value, json!([{"id": "1", "active": "true", "note": ""}]));
+    assert!(dialect.columns.iter().all(|c| c.kind == CellType::String));
+}
+
+#[test]
+fn test_big_numbers_stay_exact() {
+    let (value, _) = parse("id\n12345678901234567890123\n");
+    assert_eq!(value[0]["id"].to_string(), "12345678901234567890123");
+}
+
+#[test]
+fn test_sniffs_delimiter() {
+    for (content, delimiter) in [
+        ("a\tb\n1\t2\n", '\t'),
+        ("a;b;c\n1,5;2;3\n", ';'),
+        ("a|b\n1|2\n", '|'),
+        ("a,b\n\"x;y\",2\n", ','),
+    ] {
+        let (value, dialect) = parse(content);
+        assert_eq!(dialect.delimiter, delimiter, "{:?}", content);
+        assert_eq!(value.as_array().unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn test_explicit_dialect() {
+    let options = CsvOptions {
+        delimiter: Some(';'),
+        quote: Some('\''),
+        has_headers: Some(false),
+        trim: true,
+        ..Default::default()
+    };
+    let (value, dialect) = parse_csv("'a;b' ; 2\n c ; 3\n", &options).unwrap();
+    assert_eq!(
+        value,
+        json!([{"column1": "a;b", "column2": 2}, {"column1": "c", "column2": 3}])
+    );
+    assert!(!dialect.has_headers);
+    assert_eq!(dialect.quote, '\'');
+}
+
+#[test]
+fn test_sniffs_single_quotes() {
+    let (value, dialect) = parse("name,city\n'Doe, Jane','Paris'\n");
+    assert_eq!(dialect.quote, '\'');
+    assert_eq!(value[0]["name"], "Doe, Jane");
+}
+
+#[test]
+fn test_sniffs_missing_header() {
+    let (value, dialect) = parse("alice,30\nbob,25\n");
+    assert!(!dialect.has_headers);
+    assert_eq!(
+        value,
+        json!([{"column1": "alice", "column2": 30}, {"column1": "bob", "column2": 25}])
+    );
+
+    let (_, dialect) = parse("name,age\nalice,30\n");
+    assert!(dialect.has_headers);
+}
+
+#[test]
+fn test_rejects_non_ascii_delimiter() {
+    let options = CsvOptions {
+        delimiter: Some('→'),
+        ..Default::default()
+    };
+    let err = parse_csv("a→b\n", &options).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Data);
+}
+
+#[test]
+fn test_empty_input() {
+    let (value, dialect) = parse("");
+    assert_eq!(value, json!([]));
+    assert!(dialect.columns.is_empty());
+}
+
+#[test]
+fn test_unflatten_rebuilds_objects_and_arrays() {
+    let rows = json!([
+        {"id": 1, "user.name": "Ada", "user.roles[0]": "admin", "user.roles[1]": "dev", "items[0].sku": "A", "items[1].sku": "B"},
+        {"id": 2, "user.name": "Bob", "user.roles[0]": "dev", "user.roles[1]": null, "items[0].sku": null, "items[1].sku": null},
+    ]);
+    assert_eq!(
+        unflatten(&rows, "."),
+        json!([
+            {"id": 1, "user": {"name": "Ada", "roles": ["admin", "dev"]}, "items": [{"sku": "A"}, {"sku": "B"}]},
+            {"id": 2, "user": {"name": "Bob", "roles": ["dev"]}},
+        ])
+    );
+}
+
+#[test]
+fn test_unflatten_keeps_names_that_are_not_paths() {
+    let row = json!({"price [USD]": 3, "a..b": 1, "[0]": 2, "m[1][0]": "x", "note": null});
+    assert_eq!(
+        unflatten(&row, "."),
+        json!({"price [USD]": 3, "a..b": 1, "[0]": 2, "m": [null, ["x"]], "note": null})
+    );
+    assert_eq!(
+        unflatten(&json!({"a/b": 1, "a.b": 2}), "/"),
+        json!({"a": {"b": 1}, "a.b": 2})
+    );
+}
+
+#[test]
+fn test_unflatten_keeps_indexes_past_the_row_as_keys() {
+    let row = json!({"a[100000]": 1, "b[1]": 2, "b[0]": 3});
+    assert_eq!(unflatten(&row, "."), json!({"a[100000]": 1, "b": [3, 2]}));
+    assert_eq!(
+        unflatten(&json!({"x[3]": true, "y": 1}), "."),
+        json!({"x[3]": true, "y": 1})
+    );
+}
+
+#[test]
+fn test_unflatten_leaves_clashing_rows_flat() {
+    let rows = json!([{"a": 1, "a.b": null}, {"a": null, "a.b": 2}, {"a": 1, "a.b": 2}]);
+    assert_eq!(
+        unflatten(&rows, "."),
+        json!([{"a": 1}, {"a": {"b": 2}}, {"a": 1, "a.b": 2}])
+    );
+}
+
+#[test]
+fn test_parse_unflattens_columns() {
+    let options = CsvOptions {
+        unflatten: true,
+        path_separator: "/".to_string(),
+        ..Default::default()
+    };
+    let (value, dialect) = parse_csv("id,user/name,tags[0]\n1,Ada,x\n", &options).unwrap();
+    assert_eq!(
+        value,
+        json!([{"id": 1, "user": {"name": "Ada"}, "tags": ["x"]}])
+    );
+    assert_eq!(dialect.columns[1].name, "user/name");
+
+    let options = CsvOptions {
+        unflatten: true,
+        path_separator: String::new(),
+        ..Default::default()
+    };
+    let err = parse_csv("a\n1\n", &options).unwrap_err();
+    assert!(err.message.contains("separator"), "{}", err.message);
+}
@@ -0,0 +1,195 @@
+//! `.env` files, read the way dotenv libraries do: `KEY=value` lines with an optional
+//! `export` prefix and `#` comments. Single quotes are literal; double quotes understand
+//! `\n`, `\t`, `\"` and `\\` and may span lines. Variables are not expanded.
+//!
+//! The result is a flat object of strings. When writing, nested values are flattened with `__`
+//! (`{"db": {"host": "x"}}` becomes `db__host=x`), the usual convention for config loaders.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use serde_json::{Map, Value};
+
+pub const SEPARATOR: &str = "__";
+
+fn env_error(content: &str, offset: usize, kind: ParseErrorKind, message: &str) -> ParseError {
+    ParseError::new("env", kind, message).at_offset(content, offset)
+}
+
+fn line_end(content: &str, from: usize) -> usize {
+    content[from..]
+        .find('\n')
+        .map_or(content.len(), |i| from + i + 1)
+}
+
+/// Reads a double-quoted value starting after the opening quote. Returns the value and the
+/// offset just past the closing quote.
+fn double_quoted(content: &str, start: usize) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = content[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, start + i + 1)),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                other @ ('"' | '\\' | '$') => value.push(other),
+                other => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+pub fn parse_env(content: &str) -> Result<Value, ParseError> {
+    let mut map = Map::new();
+    let mut pos = 0;
+    while pos < content.len() {
+        let end = line_end(content, pos);
+        let line = &content[pos..end];
+        let start = pos + line.len() - line.trim_start().len();
+        let body = line.trim();
+        if body.is_empty() || body.starts_with('#') {
+            pos = end;
+            continue;
+        }
+
+        let body = body
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(body, str::trim_start);
+        let Some((key, _)) = body.split_once('=') else {
+            return Err(env_error(
+                content,
+                start,
+                ParseErrorKind::Syntax,
+                "Expected KEY=value",
+            ));
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(env_error(
+                content,
+                start,
+                ParseErrorKind::Syntax,
+                "Invalid variable name",
+            ));
+        }
+
+        let equals = pos + line.find('=').expect("line has an =");
+        let value_start = equals + 1 + content[equals + 1..end].len()
+            - content[equals + 1..end]
+                .trim_start_matches([' ', '\t'])
+                .len();
+        let (value, value_end) = match content[value_start..end].chars().next() {
+            Some('\'') => {
+                let close = content[value_start + 1..].find('\'').ok_or_else(|| {
+                    env_error(
+                        content,
+                        value_start,
+                        ParseErrorKind::Eof,
+                        "Unterminated single-quoted value",
+                    )
+                })?;
+                let close = value_start + 1 + close;
+                (content[value_start + 1..close].to_string(), close + 1)
+            }
+            Some('"') => double_quoted(content, value_start + 1).ok_or_else(|| {
+                env_error(
+                    content,
+                    value_start,
+                    ParseErrorKind::Eof,
+                    "Unterminated double-quoted value",
+                )
+            })?,
+            _ => {
+                let raw = &content[value_start..end];
+                let raw = match raw.find(" #").or_else(|| raw.find("\t#")) {
+                    Some(i) => &raw[..i],
+                    None => raw,
+                };
+                (raw.trim().to_string(), end)
+            }
+        };
+        map.insert(key.to_string(), Value::String(value));
+        // Anything after a closing quote, such as a comment, is skipped.
+        pos = if value_end == end {
+            end
+        } else {
+            line_end(content, value_end)
+        };
+    }
+    Ok(Value::Object(map))
+}
+
+fn flatten(value: &Value, prefix: String, out: &mut Vec<(String, String)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}{}{}", prefix, SEPARATOR, key)
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map {
+                flatten(item, join(key), out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(item, join(&i.to_string()), out);
+            }
+        }
+        Value::Null => out.push((prefix, String::new())),
+        Value::String(s) => out.push((prefix, s.clone())),
+        other => out.push((prefix, other.to_string())),
+    }
+}
+
+fn quote(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+%".contains(c));
+    if plain {
+        return value.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '"' | '\\' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn to_env(value: &Value) -> Result<String, String> {
+    if !value.is_object() {
+        return Err(".env files need an object at the top level".to_string());
+    }
+    let mut entries = Vec::new();
+    flatten(value, String::new(), &mut entries);
+    let mut out = String::new();
+    for (key, value) in entries {
+        if key.contains(['=', ' ', '\t', '\n']) || key.is_empty() {
+            return Err(format!("{:?} is not a valid variable name", key));
+        }
+        out.push_str(&format!("{}={}\n", key, quote(&value)));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "dotenv_test.rs"]
+mod dotenv_test;
@@ -0,0 +1,67 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_parse_env() {
+    let content = "\
+# database
+export DB_HOST=localhost
+DB_PORT = 5432 # default port
+EMPTY=
+LITERAL='no $expansion \\n here'
+MESSAGE=\"line one\\nline \\\"two\\\"\"
+MULTI=\"first
+second\" # trailing comment
+URL=http://example.com/#anchor
+";
+    assert_eq!(
+        parse_env(content).unwrap(),
+        json!({
+            "DB_HOST": "localhost",
+            "DB_PORT": "5432",
+            "EMPTY": "",
+            "LITERAL": "no $expansion \\n here",
+            "MESSAGE": "line one\nline \"two\"",
+            "MULTI": "first\nsecond",
+            "URL": "http://example.com/#anchor"
+        })
+    );
+}
+
+#[test]
+fn test_parse_env_reports_location() {
+    let error = parse_env("A=1\nnot a pair\n").unwrap_err();
+    assert_eq!(error.format, "env");
+    assert_eq!(error.location.unwrap().line, 2);
+
+    let error = parse_env("A=1\nB=\"open\n").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Eof);
+    assert_eq!(error.location.unwrap().line, 2);
+    assert_eq!(error.location.unwrap().column, 3);
+}
+
+#[test]
+fn test_to_env() {
+    let value = json!({
+        "PORT": 8080,
+        "NAME": "demo app",
+        "UNSET": null,
+        "db": {"host": "x", "replicas": ["a", "b"]},
+        "NOTE": "say \"hi\"\n$HOME"
+    });
+    let env = to_env(&value).unwrap();
+    assert_eq!(
+        env,
+        "\
+PORT=8080
+NAME=\"demo app\"
+UNSET=
+db__host=x
+db__replicas__0=a
+db__replicas__1=b
+NOTE=\"say \\\"hi\\\"\\n\\$HOME\"
+"
+    );
+    assert_eq!(parse_env(&env).unwrap()["NOTE"], json!("say \"hi\"\n$HOME"));
+    assert!(to_env(&json!("x")).is_err());
+}
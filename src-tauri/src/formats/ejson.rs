@@ -0,0 +1,125 @@
+//! MongoDB Extended JSON v2. Input in either mode is normalized to the relaxed form, which is
+//! also how `formats::bson` shows documents: numbers are plain and typed values such as
+//! `{"$oid": ...}` stay single-key objects. Output can be written in either mode.
+
+use crate::formats::tagged;
+use base64::prelude::*;
+use serde_json::{json, Map, Number, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendedJsonMode {
+    /// Every number and date carries its BSON type, e.g. `{"$numberLong": "1"}`.
+    Canonical,
+    /// Numbers are plain JSON and dates are ISO-8601 strings where possible.
+    Relaxed,
+}
+
+fn single<'a>(map: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    (map.len() == 1).then(|| map.get(key)).flatten()
+}
+
+/// Replaces canonical wrappers with the relaxed form, e.g. `{"$numberInt": "1"}` with `1`.
+fn normalize_typed(map: &Map<String, Value>) -> Option<Value> {
+    let text = |key: &str| single(map, key)?.as_str();
+    if let Some(n) = text("$numberInt").or_else(|| text("$numberLong")) {
+        return n.parse::<i64>().ok().map(Value::from);
+    }
+    if let Some(f) = text("$numberDouble") {
+        // Keep the original digits; only NaN and the infinities need the wrapper.
+        return f
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .and_then(|_| f.parse::<Number>().ok())
+            .map(Value::Number);
+    }
+    if let Some(date) = single(map, tagged::DATE) {
+        // ISO dates are rewritten the way `tagged::date` writes them.
+        if let Some(text) = date.as_str() {
+            let datetime = chrono::DateTime::parse_from_rfc3339(text).ok()?;
+            return Some(tagged::date(datetime.with_timezone(&chrono::Utc)));
+        }
+        let millis = date.get("$numberLong")?.as_str()?.parse::<i64>().ok()?;
+        // Dates chrono cannot represent keep the canonical form.
+        return Some(
+            chrono::DateTime::from_timestamp_millis(millis)
+                .map_or_else(|| Value::Object(map.clone()), tagged::date),
+        );
+    }
+    if let Some(binary) = single(map, "$binary") {
+        if binary.get("subType")?.as_str()? == "00" {
+            let data = BASE64_STANDARD
+                .decode(binary.get("base64")?.as_str()?)
+                .ok()?;
+            return Some(tagged::bytes(&data));
+        }
+        return None;
+    }
+    if let Some(uuid) = text("$uuid") {
+        let hex: String = uuid.chars().filter(|c| *c != '-').collect();
+        let data = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .filter(|data| data.len() == 16)?;
+        return Some(json!({
+            "$binary": { "base64": BASE64_STANDARD.encode(data), "subType": "04" }
+        }));
+    }
+    None
+}
+
+/// Converts Extended JSON in either mode to the relaxed tree.
+pub fn from_extended_json(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            if let Some(normalized) = normalize_typed(&map) {
+                return normalized;
+            }
+            Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, from_extended_json(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(from_extended_json).collect()),
+        other => other,
+    }
+}
+
+/// Writes `$bytes` the Extended JSON way, as binary of the generic subtype.
+fn bytes_as_binary(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => match tagged::as_bytes(value) {
+            Some(data) => json!({
+                "$binary": { "base64": BASE64_STANDARD.encode(data), "subType": "00" }
+            }),
+            None => Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), bytes_as_binary(value)))
+                    .collect(),
+            ),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(bytes_as_binary).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Reads the tree as Extended JSON into a BSON value. Numbers take the smallest of int32,
+/// int64 and double that holds them.
+pub fn to_bson(value: &Value) -> Result<bson::Bson, String> {
+    bson::Bson::try_from(bytes_as_binary(value)).map_err(|e| e.to_string())
+}
+
+/// Writes the tree as Extended JSON in `mode`.
+pub fn to_extended_json(value: &Value, mode: ExtendedJsonMode) -> Result<Value, String> {
+    let value = to_bson(value)?;
+    Ok(match mode {
+        ExtendedJsonMode::Canonical => value.into_canonical_extjson(),
+        ExtendedJsonMode::Relaxed => value.into_relaxed_extjson(),
+    })
+}
+
+#[cfg(test)]
+#[path = "ejson_test.rs"]
+mod ejson_test;
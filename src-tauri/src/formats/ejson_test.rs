@@ -0,0 +1,91 @@
+use super::*;
+
+#[test]
+fn test_canonical_input_is_normalized() {
+    let value = json!({
+        "_id": {"$oid": "6553f1000000000000000001"},
+        "n": {"$numberInt": "7"},
+        "l": {"$numberLong": "5000000000"},
+        "nan": {"$numberDouble": "NaN"},
+        "at": {"$date": {"$numberLong": "1700000000123"}},
+        "blob": {"$binary": {"base64": "3q0=", "subType": "00"}},
+        "id": {"$uuid": "00010203-0405-0607-0809-0a0b0c0d0e0f"},
+        "price": {"$numberDecimal": "19.99"}
+    });
+    let normalized = from_extended_json(value);
+    assert_eq!(
+        normalized,
+        json!({
+            "_id": {"$oid": "6553f1000000000000000001"},
+            "n": 7,
+            "l": 5000000000i64,
+            "nan": {"$numberDouble": "NaN"},
+            "at": {"$date": "2023-11-14T22:13:20.123Z"},
+            "blob": {"$bytes": "3q0="},
+            "id": {"$binary": {"base64": "AAECAwQFBgcICQoLDA0ODw==", "subType": "04"}},
+            "price": {"$numberDecimal": "19.99"}
+        })
+    );
+
+    // Doubles keep their original digits.
+    let double = from_extended_json(json!({"$numberDouble": "1.50"}));
+    assert_eq!(double.to_string(), "1.50");
+}
+
+#[test]
+fn test_relaxed_output_keeps_plain_numbers() {
+    let value = json!({"n": 1, "at": {"$date": "2023-11-14T22:13:20Z"}, "b": {"$bytes": "AA=="}});
+    assert_eq!(
+        to_extended_json(&value, ExtendedJsonMode::Relaxed).unwrap(),
+        json!({
+            "n": 1,
+            "at": {"$date": "2023-11-14T22:13:20Z"},
+            "b": {"$binary": {"base64": "AA==", "subType": "00"}}
+        })
+    );
+}
+
+#[test]
+fn test_canonical_output_types_every_number_and_date() {
+    let value = json!({
+        "i": 1,
+        "l": 5000000000i64,
+        "d": 2.5,
+        "whole": 3.0,
+        "at": {"$date": "2023-11-14T22:13:20.123Z"},
+        "ts": {"$timestamp": {"t": 1, "i": 2}},
+        "list": [-1]
+    });
+    assert_eq!(
+        to_extended_json(&value, ExtendedJsonMode::Canonical).unwrap(),
+        json!({
+            "i": {"$numberInt": "1"},
+            "l": {"$numberLong": "5000000000"},
+            "d": {"$numberDouble": "2.5"},
+            "whole": {"$numberDouble": "3.0"},
+            "at": {"$date": {"$numberLong": "1700000000123"}},
+            "ts": {"$timestamp": {"t": 1, "i": 2}},
+            "list": [{"$numberInt": "-1"}]
+        })
+    );
+}
+
+#[test]
+fn test_relaxed_output_uses_numbers_for_dates_outside_iso_years() {
+    let value = json!({"$date": "1969-12-31T23:59:59Z"});
+    assert_eq!(
+        to_extended_json(&value, ExtendedJsonMode::Relaxed).unwrap(),
+        json!({"$date": {"$numberLong": "-1000"}})
+    );
+}
+
+#[test]
+fn test_modes_round_trip_through_normalization() {
+    let value = json!({"n": 5, "at": {"$date": "2023-11-14T22:13:20Z"}, "x": [1.25]});
+    for mode in [ExtendedJsonMode::Canonical, ExtendedJsonMode::Relaxed] {
+        assert_eq!(
+            from_extended_json(to_extended_json(&value, mode).unwrap()),
+            value
+        );
+    }
+}
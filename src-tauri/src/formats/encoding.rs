@@ -0,0 +1,136 @@
+//! Text decoding for files that are not plain UTF-8: byte order marks, UTF-16 from Windows
+//! tools and Windows-1252/Latin-1 exports. The detected encoding is kept so a file can be
+//! written back the way it came in.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// Number of leading bytes inspected when looking for BOM-less UTF-16.
+const SNIFF_BYTES: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEncoding {
+    /// WHATWG name: `UTF-8`, `UTF-16LE`, `UTF-16BE` or a single-byte encoding such as
+    /// `windows-1252`.
+    pub name: String,
+    /// Whether the content started with a byte order mark.
+    pub bom: bool,
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        TextEncoding {
+            name: UTF_8.name().to_string(),
+            bom: false,
+        }
+    }
+}
+
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))
+}
+
+/// Spots UTF-16 without a BOM by the zero bytes that ASCII characters leave in every other
+/// position.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES) & !1];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|b| **b == 0)
+        .count();
+    if odd_zeros * 10 > pairs * 4 && even_zeros * 20 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 > pairs * 4 && odd_zeros * 20 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Decodes `bytes` with the encoding named by `label`, or the one detected from a BOM, the
+/// layout of UTF-16 or the validity of UTF-8, falling back to Windows-1252. Returns the text,
+/// the encoding used and whether any bytes had to be replaced.
+pub fn decode(bytes: &[u8], label: Option<&str>) -> Result<(String, TextEncoding, bool), String> {
+    let (bom_encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((encoding, len)) => (Some(encoding), len),
+        None => (None, 0),
+    };
+    let encoding = match label {
+        Some(label) => encoding_for_label(label)?,
+        None => bom_encoding
+            .or_else(|| sniff_utf16(bytes))
+            .unwrap_or_else(|| {
+                if std::str::from_utf8(bytes).is_ok() {
+                    UTF_8
+                } else {
+                    WINDOWS_1252
+                }
+            }),
+    };
+    let bom = bom_encoding == Some(encoding);
+    let body = if bom { &bytes[bom_len..] } else { bytes };
+    let (text, had_errors) = encoding.decode_without_bom_handling(body);
+    Ok((
+        text.into_owned(),
+        TextEncoding {
+            name: encoding.name().to_string(),
+            bom,
+        },
+        had_errors,
+    ))
+}
+
+/// Encodes text back into `encoding`, with its BOM if it had one. Characters the encoding
+/// cannot represent are an error rather than silently replaced.
+pub fn encode(text: &str, encoding: &TextEncoding) -> Result<Vec<u8>, String> {
+    let target = encoding_for_label(&encoding.name)?;
+    let mut out = Vec::with_capacity(text.len() + 3);
+    if target == UTF_16LE || target == UTF_16BE {
+        // encoding_rs only decodes UTF-16, so write the code units by hand.
+        let little_endian = target == UTF_16LE;
+        let units = encoding
+            .bom
+            .then_some(0xFEFF)
+            .into_iter()
+            .chain(text.encode_utf16());
+        for unit in units {
+            out.extend(if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            });
+        }
+        return Ok(out);
+    }
+
+    if encoding.bom && target == UTF_8 {
+        out.extend([0xEF, 0xBB, 0xBF]);
+    }
+    let (bytes, _, unmappable) = target.encode(text);
+    if unmappable {
+        let c = text
+            .chars()
+            .find(|c| target.encode(c.encode_utf8(&mut [0; 4])).2)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        return Err(format!(
+            "Character '{}' cannot be written in {}",
+            c,
+            target.name()
+        ));
+    }
+    out.extend_from_slice(&bytes);
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "encoding_test.rs"]
+mod encoding_test;
@@ -0,0 +1,90 @@
+use super::*;
+
+fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+    let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+    units
+        .flat_map(|u| {
+            if little_endian {
+                u.to_le_bytes()
+            } else {
+                u.to_be_bytes()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_decodes_utf8_with_and_without_bom() {
+    let (text, encoding, had_errors) = decode("{\"a\": \"é\"}".as_bytes(), None).unwrap();
+    assert_eq!(text, "{\"a\": \"é\"}");
+    assert_eq!(encoding, TextEncoding::default());
+    assert!(!had_errors);
+
+    let (text, encoding, _) = decode(b"\xEF\xBB\xBF{}", None).unwrap();
+    assert_eq!(text, "{}");
+    assert_eq!(encoding.name, "UTF-8");
+    assert!(encoding.bom);
+}
+
+#[test]
+fn test_decodes_utf16() {
+    for little_endian in [true, false] {
+        for bom in [true, false] {
+            let (text, encoding, _) =
+                decode(&utf16("name,city\nZoë,Köln\n", little_endian, bom), None).unwrap();
+            assert_eq!(text, "name,city\nZoë,Köln\n");
+            assert_eq!(
+                encoding.name,
+                if little_endian {
+                    "UTF-16LE"
+                } else {
+                    "UTF-16BE"
+                }
+            );
+            assert_eq!(encoding.bom, bom);
+        }
+    }
+}
+
+#[test]
+fn test_falls_back_to_windows_1252() {
+    let (text, encoding, had_errors) = decode(b"name\nCaf\xE9 \x80\n", None).unwrap();
+    assert_eq!(text, "name\nCafé €\n");
+    assert_eq!(encoding.name, "windows-1252");
+    assert!(!had_errors);
+}
+
+#[test]
+fn test_explicit_label() {
+    let (text, encoding, _) = decode(b"caf\xE9", Some("latin1")).unwrap();
+    assert_eq!(text, "café");
+    assert_eq!(encoding.name, "windows-1252");
+
+    let (_, _, had_errors) = decode(b"caf\xE9", Some("utf-8")).unwrap();
+    assert!(had_errors);
+
+    assert!(decode(b"", Some("klingon")).is_err());
+}
+
+#[test]
+fn test_encode_round_trips() {
+    for bytes in [
+        utf16("{\"a\": \"ü\"}", true, true),
+        utf16("{\"a\": \"ü\"}", false, false),
+        b"\xEF\xBB\xBF{\"a\": 1}".to_vec(),
+        b"{\"a\": \"caf\xE9\"}".to_vec(),
+    ] {
+        let (text, encoding, _) = decode(&bytes, None).unwrap();
+        assert_eq!(encode(&text, &encoding).unwrap(), bytes);
+    }
+}
+
+#[test]
+fn test_encode_rejects_unmappable_characters() {
+    let encoding = TextEncoding {
+        name: "windows-1252".to_string(),
+        bom: false,
+    };
+    let err = encode("price: 5€ 🙂", &encoding).unwrap_err();
+    assert_eq!(err, "Character '🙂' cannot be written in windows-1252");
+}
@@ -0,0 +1,275 @@
+//! HCL, as used by Terraform, mapped to the JSON shape of `.tf.json` files: each block label
+//! adds a level of nesting (`resource "aws_instance" "web" {}` is
+//! `{"resource": {"aws_instance": {"web": {}}}}`), repeated blocks become arrays, and
+//! expressions that are not plain values are kept as `"${...}"` strings.
+//!
+//! Going back, the JSON alone cannot say whether an object is a block or an attribute, so the
+//! block types Terraform defines are used: the top-level ones with their labels, `lifecycle` and
+//! the other meta blocks, and arrays of objects inside resources and providers. Any other object
+//! is written as an attribute.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use hcl::edit::parser::parse_expr;
+use hcl::expr::TemplateExpr;
+use hcl::{Attribute, Block, BlockLabel, Body, Expression, Identifier, ObjectKey, Structure};
+use serde_json::{Map, Number, Value};
+
+/// Block types allowed at the top level of a Terraform configuration, with their label count.
+const TOP_LEVEL_BLOCKS: [(&str, usize); 13] = [
+    ("resource", 2),
+    ("data", 2),
+    ("ephemeral", 2),
+    ("variable", 1),
+    ("output", 1),
+    ("module", 1),
+    ("provider", 1),
+    ("check", 1),
+    ("terraform", 0),
+    ("locals", 0),
+    ("moved", 0),
+    ("import", 0),
+    ("removed", 0),
+];
+
+/// Meta blocks that can appear inside other blocks, with their label count.
+const NESTED_BLOCKS: [(&str, usize); 13] = [
+    ("lifecycle", 0),
+    ("connection", 0),
+    ("provisioner", 1),
+    ("dynamic", 1),
+    ("content", 0),
+    ("backend", 1),
+    ("cloud", 0),
+    ("workspaces", 0),
+    ("required_providers", 0),
+    ("validation", 0),
+    ("precondition", 0),
+    ("postcondition", 0),
+    ("assert", 0),
+];
+
+/// Blocks whose bodies follow a provider schema, where arrays of objects are nested blocks.
+const SCHEMA_BLOCKS: [&str; 6] = [
+    "resource",
+    "data",
+    "ephemeral",
+    "provider",
+    "dynamic",
+    "content",
+];
+
+/// Literal strings get `${` escaped as `$${`, since in `.tf.json` every string is a template.
+fn escape_literals(expr: &mut Expression) {
+    match expr {
+        Expression::String(s) => *s = s.replace("${", "$${").replace("%{", "%%{"),
+        Expression::Array(items) => items.iter_mut().for_each(escape_literals),
+        Expression::Object(map) => map.values_mut().for_each(escape_literals),
+        _ => {}
+    }
+}
+
+fn escape_body(body: &mut Body) {
+    for structure in body.iter_mut() {
+        match structure {
+            Structure::Attribute(attribute) => escape_literals(&mut attribute.expr),
+            Structure::Block(block) => escape_body(&mut block.body),
+        }
+    }
+}
+
+pub fn parse_hcl(content: &str) -> Result<Value, ParseError> {
+    let mut body = hcl::parse(content).map_err(|e| match e {
+        hcl::Error::Parse(e) => ParseError::new("hcl", ParseErrorKind::Syntax, e.message())
+            .at_offset(content, e.location().offset()),
+        other => ParseError::new("hcl", ParseErrorKind::Syntax, other.to_string()),
+    })?;
+    escape_body(&mut body);
+    hcl::from_body(body).map_err(|e| ParseError::new("hcl", ParseErrorKind::Data, e.to_string()))
+}
+
+fn number(number: &Number) -> Result<hcl::Number, String> {
+    if let Some(n) = number.as_i64() {
+        return Ok(n.into());
+    }
+    if let Some(n) = number.as_u64() {
+        return Ok(n.into());
+    }
+    number
+        .as_f64()
+        .and_then(hcl::Number::from_f64)
+        .ok_or_else(|| format!("Number {} is out of range for HCL", number))
+}
+
+/// Escapes the literal parts of a template for a quoted string, leaving interpolations and
+/// directives as they are.
+fn quoted_template(text: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // `$${` and `%%{` are escapes for a literal `${` and `%{`.
+            '$' | '%' if depth == 0 && chars.peek() == Some(&c) => {
+                out.push(c);
+                out.push(chars.next().unwrap());
+            }
+            '$' | '%' if depth == 0 && chars.peek() == Some(&'{') => {
+                out.push(c);
+                out.push(chars.next().unwrap());
+                depth = 1;
+            }
+            '{' if depth > 0 => {
+                depth += 1;
+                out.push(c);
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                out.push(c);
+            }
+            '"' | '\\' if depth == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' if depth == 0 => out.push_str("\\n"),
+            '\r' if depth == 0 => out.push_str("\\r"),
+            '\t' if depth == 0 => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Strings are templates in `.tf.json`: a lone `"${expr}"` is the expression itself.
+fn string_expression(text: &str) -> Expression {
+    if !text.contains("${") && !text.contains("%{") {
+        return Expression::String(text.to_string());
+    }
+    let inner = text
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'));
+    if let Some(expr) = inner.and_then(|inner| parse_expr(inner).ok()) {
+        return Expression::from(expr);
+    }
+    Expression::TemplateExpr(Box::new(TemplateExpr::QuotedString(quoted_template(text))))
+}
+
+fn expression(value: &Value) -> Result<Expression, String> {
+    Ok(match value {
+        Value::Null => Expression::Null,
+        Value::Bool(b) => Expression::Bool(*b),
+        Value::Number(n) => Expression::Number(number(n)?),
+        Value::String(s) => string_expression(s),
+        Value::Array(items) => {
+            Expression::Array(items.iter().map(expression).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => Expression::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match Identifier::new(key.as_str()) {
+                        Ok(ident) => ObjectKey::Identifier(ident),
+                        Err(_) => ObjectKey::Expression(Expression::String(key.clone())),
+                    };
+                    Ok((key, expression(value)?))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+fn identifier(key: &str) -> Result<Identifier, String> {
+    Identifier::new(key).map_err(|_| format!("{:?} is not a valid HCL identifier", key))
+}
+
+#[derive(Clone, Copy)]
+enum Context {
+    Root,
+    Block { schema: bool },
+}
+
+/// Writes `value` as blocks named `ident`, taking `remaining` more labels from object keys.
+/// Arrays stand for several blocks at any level.
+fn push_blocks(
+    out: &mut Vec<Structure>,
+    ident: &str,
+    labels: &mut Vec<String>,
+    remaining: usize,
+    value: &Value,
+    context: Context,
+) -> Result<(), String> {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                push_blocks(out, ident, labels, remaining, item, context)?;
+            }
+        }
+        Value::Object(map) if remaining > 0 => {
+            for (label, inner) in map {
+                labels.push(label.clone());
+                push_blocks(out, ident, labels, remaining - 1, inner, context)?;
+                labels.pop();
+            }
+        }
+        Value::Object(map) => {
+            let body = body(map, context)?;
+            out.push(Structure::Block(Block {
+                identifier: identifier(ident)?,
+                labels: labels.iter().cloned().map(BlockLabel::String).collect(),
+                body,
+            }));
+        }
+        _ => return Err(format!("{} blocks need an object", ident)),
+    }
+    Ok(())
+}
+
+fn is_object_list(value: &Value) -> bool {
+    matches!(value, Value::Array(items)
+        if !items.is_empty() && items.iter().all(Value::is_object))
+}
+
+fn body(map: &Map<String, Value>, context: Context) -> Result<Body, String> {
+    let mut out = Vec::new();
+    for (key, value) in map {
+        let known = match context {
+            Context::Root => TOP_LEVEL_BLOCKS.iter(),
+            Context::Block { .. } => NESTED_BLOCKS.iter(),
+        }
+        .find(|(name, _)| name == key)
+        .map(|(_, labels)| *labels);
+        let block_labels = match (known, context) {
+            (Some(labels), _) if value.is_object() || is_object_list(value) => Some(labels),
+            (None, Context::Block { schema: true }) if is_object_list(value) => Some(0),
+            _ => None,
+        };
+        // The schema of a resource or provider carries over to its nested blocks.
+        let schema = SCHEMA_BLOCKS.contains(&key.as_str())
+            || matches!(context, Context::Block { schema: true });
+        match block_labels {
+            Some(labels) => push_blocks(
+                &mut out,
+                key,
+                &mut Vec::new(),
+                labels,
+                value,
+                Context::Block { schema },
+            )?,
+            None => out.push(Structure::Attribute(Attribute {
+                key: identifier(key)?,
+                expr: expression(value)?,
+            })),
+        }
+    }
+    Ok(Body(out))
+}
+
+/// Writes a `.tf.json`-shaped object as HCL.
+pub fn to_hcl(value: &Value) -> Result<String, String> {
+    let Value::Object(map) = value else {
+        return Err("HCL needs an object at the top level".to_string());
+    };
+    hcl::to_string(&body(map, Context::Root)?).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+#[path = "hcl_test.rs"]
+mod hcl_test;
@@ -0,0 +1,99 @@
+use super::*;
+use serde_json::json;
+
+const CONFIG: &str = r#"
+variable "region" {
+  type    = string
+  default = "us-east-1"
+}
+
+resource "aws_instance" "web" {
+  ami   = var.ami
+  count = 2
+  tags = {
+    Name = "web-${var.env}"
+  }
+
+  ebs_block_device {
+    size = 10
+  }
+
+  ebs_block_device {
+    size = 20
+  }
+
+  lifecycle {
+    create_before_destroy = true
+  }
+}
+
+resource "aws_instance" "db" {
+  ami = "ami-123"
+}
+"#;
+
+#[test]
+fn test_parse_hcl() {
+    assert_eq!(
+        parse_hcl(CONFIG).unwrap(),
+        json!({
+            "variable": {"region": {"type": "${string}", "default": "us-east-1"}},
+            "resource": {"aws_instance": {
+                "web": {
+                    "ami": "${var.ami}",
+                    "count": 2,
+                    "tags": {"Name": "web-${var.env}"},
+                    "ebs_block_device": [{"size": 10}, {"size": 20}],
+                    "lifecycle": {"create_before_destroy": true}
+                },
+                "db": {"ami": "ami-123"}
+            }}
+        })
+    );
+}
+
+#[test]
+fn test_parse_hcl_reports_location() {
+    let error = parse_hcl("a = 1\nb = \n").unwrap_err();
+    assert_eq!(error.format, "hcl");
+    assert_eq!(error.location.unwrap().line, 2);
+}
+
+#[test]
+fn test_to_hcl_round_trips_terraform() {
+    let value = parse_hcl(CONFIG).unwrap();
+    let hcl = to_hcl(&value).unwrap();
+    assert!(hcl.contains("resource \"aws_instance\" \"web\" {"));
+    assert!(hcl.contains("  ami = var.ami\n"));
+    assert!(hcl.contains("  ebs_block_device {\n    size = 20\n  }"));
+    assert!(hcl.contains("  lifecycle {\n"));
+    assert!(hcl.contains("  tags = {\n"));
+    assert_eq!(parse_hcl(&hcl).unwrap(), value);
+}
+
+#[test]
+fn test_to_hcl_templates() {
+    let value = json!({
+        "locals": {
+            "greeting": "say \"hi\" to ${lookup(var.names, \"first\")}",
+            "literal": "$${not_interpolated}",
+            "plain": "100%"
+        }
+    });
+    let hcl = to_hcl(&value).unwrap();
+    assert!(hcl.contains(r#"greeting = "say \"hi\" to ${lookup(var.names, "first")}""#));
+    assert_eq!(parse_hcl(&hcl).unwrap(), value);
+}
+
+#[test]
+fn test_to_hcl_keeps_attribute_objects_outside_schemas() {
+    let value = json!({
+        "variable": {"subnets": {"default": [{"cidr": "10.0.0.0/24"}]}},
+        "region": "eu-west-1"
+    });
+    let hcl = to_hcl(&value).unwrap();
+    assert!(hcl.contains("default = ["));
+    assert!(hcl.contains("region = \"eu-west-1\""));
+    assert_eq!(parse_hcl(&hcl).unwrap(), value);
+    assert!(to_hcl(&json!([1])).is_err());
+}
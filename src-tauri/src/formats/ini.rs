@@ -0,0 +1,261 @@
+//! INI files. Keys before the first section go at the top level and each `[section]` becomes
+//! an object. Dotted names and git-style subsections (`[remote "origin"]`) nest, so
+//! `[server.http]` is `{"server": {"http": {...}}}`.
+//!
+//! Values are strings, since INI has no types; a key without `=` is `null`, and `key[] = ...`
+//! lines collect into an array.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use serde_json::{Map, Value};
+
+fn ini_error(content: &str, offset: usize, message: String) -> ParseError {
+    ParseError::new("ini", ParseErrorKind::Syntax, message).at_offset(content, offset)
+}
+
+/// Cuts a `;` or `#` comment that follows whitespace, outside of quotes.
+fn strip_inline_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ';' | '#') if previous.is_whitespace() => return &value[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    value
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Splits `a.b` and `remote "origin"` into their parts.
+fn section_path(name: &str) -> Vec<String> {
+    match name.split_once(char::is_whitespace) {
+        Some((head, sub)) if sub.trim().starts_with('"') => {
+            let mut path = section_path(head);
+            path.push(unquote(sub.trim()).to_string());
+            path
+        }
+        _ => name
+            .split('.')
+            .map(|part| part.trim().to_string())
+            .collect(),
+    }
+}
+
+/// Returns the object at `path`, creating it if needed; `None` if a value is in the way.
+fn object_at<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+) -> Option<&'a mut Map<String, Value>> {
+    let mut current = root;
+    for part in path {
+        current = current
+            .entry(part.clone())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()?;
+    }
+    Some(current)
+}
+
+pub fn parse_ini(content: &str) -> Result<Value, ParseError> {
+    let mut root = Map::new();
+    let mut section: Vec<String> = Vec::new();
+    let mut offset = 0;
+
+    for raw_line in content.split_inclusive('\n') {
+        let line_offset = offset;
+        offset += raw_line.len();
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        let indent = raw_line.len() - raw_line.trim_start().len();
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name = strip_inline_comment(header).trim_end();
+            let name = name.strip_suffix(']').ok_or_else(|| {
+                ini_error(
+                    content,
+                    line_offset + indent,
+                    "Unterminated section header".to_string(),
+                )
+            })?;
+            section = section_path(name.trim());
+            if object_at(&mut root, &section).is_none() {
+                return Err(ini_error(
+                    content,
+                    line_offset + indent,
+                    format!(
+                        "Section [{}] conflicts with a key of the same name",
+                        name.trim()
+                    ),
+                ));
+            }
+            continue;
+        }
+
+        let (key, value) = match line.find(['=', ':']) {
+            Some(i) => (
+                line[..i].trim(),
+                Value::String(unquote(strip_inline_comment(&line[i + 1..]).trim()).to_string()),
+            ),
+            None => (strip_inline_comment(line).trim(), Value::Null),
+        };
+        if key.is_empty() {
+            return Err(ini_error(
+                content,
+                line_offset + indent,
+                "Missing key".to_string(),
+            ));
+        }
+        let target = object_at(&mut root, &section).expect("section was checked");
+        match key.strip_suffix("[]") {
+            Some(key) => {
+                let entry = target
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                match entry {
+                    Value::Array(items) => items.push(value),
+                    _ => {
+                        return Err(ini_error(
+                            content,
+                            line_offset + indent,
+                            format!("{}[] conflicts with the key {}", key, key),
+                        ))
+                    }
+                }
+            }
+            None => {
+                target.insert(key.to_string(), value);
+            }
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+/// Quotes values that would otherwise lose whitespace or be cut at a comment, with whichever
+/// quote character they do not contain, since INI has no escapes.
+fn scalar_text(key: &str, value: &Value) -> Result<String, String> {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains(['\n', '\r']) {
+        return Err(format!(
+            "{} holds a line break, which INI cannot write",
+            key
+        ));
+    }
+    let needs_quotes =
+        text != text.trim() || text.starts_with(['"', '\'']) || text.contains([';', '#']);
+    if !needs_quotes {
+        return Ok(text);
+    }
+    match ['"', '\''].into_iter().find(|&quote| !text.contains(quote)) {
+        Some(quote) => Ok(format!("{}{}{}", quote, text, quote)),
+        None => Err(format!(
+            "{} holds both quote characters, which INI cannot write",
+            key
+        )),
+    }
+}
+
+/// Whether `key` reads back as the same key: no separators, brackets, line breaks, comment
+/// starts or surrounding whitespace.
+fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key == key.trim()
+        && !key.contains(['=', ':', '[', ']', '\n', '\r'])
+        && !key.starts_with([';', '#'])
+        && strip_inline_comment(key) == key
+}
+
+/// Whether `name` reads back as one level of a section header; dots and quotes would split it.
+fn valid_section(name: &str) -> bool {
+    !name.is_empty()
+        && name == name.trim()
+        && !name.contains(['.', '"', '[', ']', '\n', '\r'])
+        && strip_inline_comment(name) == name
+}
+
+fn write_entries(out: &mut String, map: &Map<String, Value>) -> Result<(), String> {
+    for (key, value) in map {
+        if !value.is_object() && !valid_key(key) {
+            return Err(format!("{:?} is not a valid INI key", key));
+        }
+        match value {
+            Value::Object(_) => {}
+            Value::Null => {
+                out.push_str(key);
+                out.push('\n');
+            }
+            Value::Array(items) => {
+                for item in items {
+                    if item.is_array() || item.is_object() {
+                        return Err(format!(
+                            "{} holds nested values, which INI cannot write",
+                            key
+                        ));
+                    }
+                    out.push_str(&format!("{}[] = {}\n", key, scalar_text(key, item)?));
+                }
+            }
+            scalar => out.push_str(&format!("{} = {}\n", key, scalar_text(key, scalar)?)),
+        }
+    }
+    Ok(())
+}
+
+fn write_sections(out: &mut String, prefix: &str, map: &Map<String, Value>) -> Result<(), String> {
+    for (key, value) in map {
+        if let Value::Object(section) = value {
+            if !valid_section(key) {
+                return Err(format!("{:?} is not a valid INI section name", key));
+            }
+            let name = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            // Sections holding only subsections need no header of their own.
+            if section.values().any(|v| !v.is_object()) || section.is_empty() {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[{}]\n", name));
+                write_entries(out, section)?;
+            }
+            write_sections(out, &name, section)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes top-level scalars first, then one `[section]` per object, with nested objects as
+/// dotted section names.
+pub fn to_ini(value: &Value) -> Result<String, String> {
+    let Value::Object(root) = value else {
+        return Err("INI files need an object at the top level".to_string());
+    };
+    let mut out = String::new();
+    write_entries(&mut out, root)?;
+    write_sections(&mut out, "", root)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "ini_test.rs"]
+mod ini_test;
@@ -0,0 +1,134 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_parse_ini() {
+    let content = "\
+; global settings
+name = demo
+debug
+
+[server]
+host = \"0.0.0.0\"   ; all interfaces
+port: 8080
+path = /a#b
+
+[server.tls]
+cert = server.pem
+
+[remote \"origin\"]
+url = git@example.com:demo.git
+fetch[] = +refs/heads/*
+fetch[] = +refs/tags/*
+";
+    assert_eq!(
+        parse_ini(content).unwrap(),
+        json!({
+            "name": "demo",
+            "debug": null,
+            "server": {
+                "host": "0.0.0.0",
+                "port": "8080",
+                "path": "/a#b",
+                "tls": {"cert": "server.pem"}
+            },
+            "remote": {"origin": {
+                "url": "git@example.com:demo.git",
+                "fetch": ["+refs/heads/*", "+refs/tags/*"]
+            }}
+        })
+    );
+}
+
+#[test]
+fn test_parse_ini_merges_repeated_sections() {
+    let value = parse_ini("[a]\nx = 1\n[b]\ny = 2\n[a]\nz = 3\n").unwrap();
+    assert_eq!(value, json!({"a": {"x": "1", "z": "3"}, "b": {"y": "2"}}));
+}
+
+#[test]
+fn test_parse_ini_reports_location() {
+    let error = parse_ini("a = 1\n[broken\n").unwrap_err();
+    assert_eq!(error.format, "ini");
+    assert_eq!(error.location.unwrap().line, 2);
+
+    let error = parse_ini("a = 1\n[a]\n").unwrap_err();
+    assert!(error.message.contains("[a]"));
+    assert_eq!(error.location.unwrap().line, 2);
+}
+
+#[test]
+fn test_to_ini() {
+    let value = json!({
+        "name": "demo",
+        "debug": null,
+        "server": {"host": " padded ", "port": 8080, "tls": {"cert": "a;b"}},
+        "remote": {"origin": {"fetch": ["x", "y"]}}
+    });
+    let ini = to_ini(&value).unwrap();
+    assert_eq!(
+        ini,
+        "\
+name = demo
+debug
+
+[server]
+host = \" padded \"
+port = 8080
+
+[server.tls]
+cert = \"a;b\"
+
+[remote.origin]
+fetch[] = x
+fetch[] = y
+"
+    );
+    let round_trip = parse_ini(&ini).unwrap();
+    assert_eq!(round_trip["server"]["host"], json!(" padded "));
+    assert_eq!(round_trip["server"]["tls"]["cert"], json!("a;b"));
+    assert_eq!(round_trip["remote"]["origin"]["fetch"], json!(["x", "y"]));
+}
+
+#[test]
+fn test_to_ini_rejects_what_it_cannot_hold() {
+    assert!(to_ini(&json!([1, 2])).is_err());
+    assert!(to_ini(&json!({"a": [[1]]})).is_err());
+}
+
+#[test]
+fn test_to_ini_round_trips_quotes() {
+    let value = json!({
+        "s": {
+            "quoted": "say \"hi\" ; now",
+            "single": "'x'",
+            "inner": "a \"b\" c",
+            "empty": "",
+            "list": ["#1", "\"two\""]
+        }
+    });
+    let ini = to_ini(&value).unwrap();
+    assert!(ini.contains("quoted = 'say \"hi\" ; now'\n"), "{}", ini);
+    assert_eq!(parse_ini(&ini).unwrap(), value);
+}
+
+#[test]
+fn test_to_ini_rejects_unrepresentable_names_and_values() {
+    for (value, message) in [
+        (json!({"a": "one\ntwo"}), "line break"),
+        (json!({"a": " both ' and \" "}), "both quote characters"),
+        (json!({"a=b": "1"}), "not a valid INI key"),
+        (json!({"a[0]": "1"}), "not a valid INI key"),
+        (json!({" a": "1"}), "not a valid INI key"),
+        (json!({"s": {"x:y": null}}), "not a valid INI key"),
+        (json!({"a.b": {"x": "1"}}), "not a valid INI section name"),
+        (json!({"[s]": {"x": "1"}}), "not a valid INI section name"),
+        (
+            json!({"s": {"t]": {"x": "1"}}}),
+            "not a valid INI section name",
+        ),
+    ] {
+        let err = to_ini(&value).unwrap_err();
+        assert!(err.contains(message), "{}: {}", value, err);
+    }
+}
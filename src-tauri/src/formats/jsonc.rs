@@ -0,0 +1,144 @@
+use crate::commands::parser::{json_error, ParseError, ParseErrorKind};
+use serde_json::Value;
+
+/// Blanks out `//` and `/* */` comments and trailing commas with spaces.
+///
+/// Newlines and byte offsets are kept intact so errors reported by serde_json point at the
+/// same place in the original text.
+pub fn strip_jsonc(content: &str) -> String {
+    let bytes = content.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    let mut in_string = false;
+    // Position of the last comma seen outside a string, cleared by any significant character.
+    let mut pending_comma: Option<usize> = None;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_string {
+            match b {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match b {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    out[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = content[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |j| i + 2 + j + 2);
+                for byte in &mut out[i..end] {
+                    if !byte.is_ascii_whitespace() {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            }
+            b',' => pending_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    out[comma] = b' ';
+                }
+            }
+            b'"' => {
+                in_string = true;
+                pending_comma = None;
+            }
+            _ if b.is_ascii_whitespace() => {}
+            _ => pending_comma = None,
+        }
+        i += 1;
+    }
+
+    // Only ASCII bytes outside strings were replaced, so the buffer is still valid UTF-8.
+    String::from_utf8(out).unwrap_or_else(|_| content.to_string())
+}
+
+pub fn parse_jsonc(content: &str) -> Result<Value, ParseError> {
+    serde_json::from_str(&strip_jsonc(content)).map_err(|e| {
+        let mut error = json_error(content, e);
+        error.format = "jsonc".to_string();
+        error
+    })
+}
+
+pub fn parse_json5(content: &str) -> Result<Value, ParseError> {
+    json5::from_str(content).map_err(|e| {
+        let json5::Error::Message { msg, location } = e;
+        // pest renders a multi-line report; the "= expected ..." line is the useful part.
+        let message = msg
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("= "))
+            .unwrap_or(&msg)
+            .to_string();
+        let error = ParseError::new("json5", ParseErrorKind::Syntax, message);
+        match location {
+            Some(location) => error.at_line_column(content, location.line, location.column),
+            None => error,
+        }
+    })
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+fn write_json5(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    match value {
+        Value::Array(arr) if !arr.is_empty() => {
+            out.push_str("[\n");
+            for item in arr {
+                out.push_str(&pad);
+                write_json5(item, indent + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push(']');
+        }
+        Value::Object(map) if !map.is_empty() => {
+            out.push_str("{\n");
+            for (key, item) in map {
+                out.push_str(&pad);
+                if is_identifier(key) {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&Value::String(key.clone()).to_string());
+                }
+                out.push_str(": ");
+                write_json5(item, indent + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push('}');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// Pretty-prints a value as JSON5 with unquoted identifier keys and trailing commas.
+pub fn to_json5(value: &Value) -> String {
+    let mut out = String::new();
+    write_json5(value, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+#[path = "jsonc_test.rs"]
+mod jsonc_test;
@@ -0,0 +1,67 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_strip_jsonc_keeps_offsets() {
+    let content = "{\n  // comment\n  \"a\": 1, /* inline */\n}";
+    let stripped = strip_jsonc(content);
+    assert_eq!(stripped.len(), content.len());
+    assert_eq!(stripped.lines().count(), content.lines().count());
+    assert!(!stripped.contains("comment"));
+}
+
+#[test]
+fn test_strip_jsonc_ignores_markers_in_strings() {
+    let content = r#"{"url": "https://example.com", "list": "a,]"}"#;
+    assert_eq!(strip_jsonc(content), content);
+}
+
+#[test]
+fn test_parse_jsonc() {
+    let content = r#"{
+        // Compiler options
+        "compilerOptions": {
+            "strict": true, /* always */
+            "paths": ["src/*",],
+        },
+    }"#;
+    let value = parse_jsonc(content).unwrap();
+    assert_eq!(value["compilerOptions"]["strict"], true);
+    assert_eq!(value["compilerOptions"]["paths"], json!(["src/*"]));
+}
+
+#[test]
+fn test_parse_jsonc_error_points_at_original_text() {
+    let content = "{\n  // comment\n  \"a\": tru\n}";
+    let err = parse_jsonc(content).unwrap_err();
+    assert_eq!(err.format, "jsonc");
+    assert_eq!(err.location.unwrap().line, 3);
+    assert!(err.snippet.unwrap().contains("\"a\": tru"));
+}
+
+#[test]
+fn test_parse_json5() {
+    let content = "{unquoted: 'single', hex: 0x10, trailing: [1, 2,], // comment\n}";
+    let value = parse_json5(content).unwrap();
+    assert_eq!(value["unquoted"], "single");
+    assert_eq!(value["hex"], 16);
+    assert_eq!(value["trailing"], json!([1, 2]));
+}
+
+#[test]
+fn test_parse_json5_error_location() {
+    let err = parse_json5("{\n  a: ,\n}").unwrap_err();
+    assert_eq!(err.format, "json5");
+    assert_eq!(err.location.unwrap().line, 2);
+    assert!(!err.message.contains('\n'));
+}
+
+#[test]
+fn test_to_json5() {
+    let value = json!({"name": "test", "dashed-key": [1, {"x": null}], "empty": {}});
+    let output = to_json5(&value);
+    assert!(output.contains("  name: \"test\",\n"));
+    assert!(output.contains("  \"dashed-key\": [\n"));
+    assert!(output.contains("  empty: {},\n"));
+    assert_eq!(parse_json5(&output).unwrap(), value);
+}
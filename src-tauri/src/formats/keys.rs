@@ -0,0 +1,18 @@
+use serde_json::Value;
+
+/// Sorts the keys of every object in the tree. Objects otherwise keep the order they were read
+/// in, so this is only applied when a caller opts in.
+pub fn sort_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_keys);
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+#[path = "keys_test.rs"]
+mod keys_test;
@@ -0,0 +1,19 @@
+use super::*;
+
+#[test]
+fn test_objects_keep_insertion_order() {
+    let value: Value = serde_json::from_str(r#"{"b": 1, "a": 2, "c": 3}"#).unwrap();
+    let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["b", "a", "c"]);
+}
+
+#[test]
+fn test_sort_keys_recursive() {
+    let mut value: Value =
+        serde_json::from_str(r#"{"b": [{"z": 1, "y": 2}], "a": {"d": 1, "c": 2}}"#).unwrap();
+    sort_keys(&mut value);
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"a":{"c":2,"d":1},"b":[{"y":2,"z":1}]}"#
+    );
+}
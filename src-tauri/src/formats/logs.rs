@@ -0,0 +1,95 @@
+//! JSON embedded in log text, like `2024-01-01 INFO request={"id": 7}`. Every `{` or `[` that
+//! starts a complete JSON value is taken, whether the value fits on its line or is
+//! pretty-printed over several; values inside one already taken are not listed again. Arrays
+//! holding a single scalar, like `[200]` or `["INFO"]`, are left alone, since in logs they are
+//! far more often bracketed tags than payloads; `[1, 2]` is taken.
+//!
+//! The result is an array with one entry per value:
+//! `{"line": 1, "prefix": "2024-01-01 INFO request=", "value": {"id": 7}}`, where `prefix` is
+//! the text before the value on its line.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Finds the closing bracket of every `{` and `[` that has one, in a single pass. Brackets
+/// inside JSON strings do not count; a JSON string cannot hold a raw line break, so one ends
+/// any string. Unclosed and mismatched brackets get no entry and are never parsed.
+fn matching_brackets(bytes: &[u8]) -> HashMap<usize, usize> {
+    let mut matches = HashMap::new();
+    let mut open: Vec<(usize, u8)> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' | b'\n' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' if !open.is_empty() => in_string = true,
+            b'{' | b'[' => open.push((i, b)),
+            b'}' | b']' => match open.pop() {
+                Some((start, opener)) if opener == if b == b'}' { b'{' } else { b'[' } => {
+                    matches.insert(start, i);
+                }
+                // Text like `(1]` cannot be part of a value around it.
+                _ => open.clear(),
+            },
+            _ => {}
+        }
+    }
+    matches
+}
+
+fn is_bracketed_tag(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.len() == 1 && !items[0].is_array() && !items[0].is_object(),
+        _ => false,
+    }
+}
+
+pub fn parse_logs(content: &str) -> Value {
+    let bytes = content.as_bytes();
+    let closing = matching_brackets(bytes);
+    let mut entries = Vec::new();
+    let mut line = 1;
+    // Where the prefix of the next value starts: its line start, or the end of the value
+    // before it on the same line.
+    let mut prefix_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\n' {
+            line += 1;
+            prefix_start = i + 1;
+        }
+        let Some(&close) = closing.get(&i) else {
+            i += 1;
+            continue;
+        };
+        let end = close + 1;
+        match serde_json::from_str::<Value>(&content[i..end]) {
+            Ok(value) if !is_bracketed_tag(&value) => {
+                entries.push(json!({
+                    "line": line,
+                    "prefix": content[prefix_start..i].trim(),
+                    "value": value,
+                }));
+                // Pretty-printed values move the line count along.
+                line += content[i..end].matches('\n').count();
+                prefix_start = end;
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    Value::Array(entries)
+}
+
+#[cfg(test)]
+#[path = "logs_test.rs"]
+mod logs_test;
@@ -0,0 +1,72 @@
+use super::*;
+
+#[test]
+fn test_parse_logs_extracts_values_with_prefix() {
+    let content = "2024-01-01 INFO request={\"id\": 7, \"path\": \"/a\"}\n\
+                   2024-01-01 DEBUG nothing here\n\
+                   2024-01-01 WARN [worker] retries=[1, 2] err={\"code\": \"E1\"}\n";
+    assert_eq!(
+        parse_logs(content),
+        json!([
+            {"line": 1, "prefix": "2024-01-01 INFO request=", "value": {"id": 7, "path": "/a"}},
+            {"line": 3, "prefix": "2024-01-01 WARN [worker] retries=", "value": [1, 2]},
+            {"line": 3, "prefix": "err=", "value": {"code": "E1"}},
+        ])
+    );
+}
+
+#[test]
+fn test_parse_logs_multiline_values() {
+    let content = "start\npayload: {\n  \"a\": [1,\n    2]\n}\nnext {\"b\": true}\n";
+    let entries = parse_logs(content);
+    assert_eq!(entries[0]["line"], 2);
+    assert_eq!(entries[0]["value"], json!({"a": [1, 2]}));
+    assert_eq!(entries[1]["line"], 6);
+    assert_eq!(entries[1]["prefix"], "next");
+}
+
+#[test]
+fn test_parse_logs_skips_text_that_is_not_json() {
+    let content = "[INFO] {user} logged in {\"ok\": true} {\"broken\": \nplain\n";
+    assert_eq!(
+        parse_logs(content),
+        json!([{"line": 1, "prefix": "[INFO] {user} logged in", "value": {"ok": true}}])
+    );
+    assert_eq!(parse_logs("no json at all"), json!([]));
+}
+
+#[test]
+fn test_parse_logs_keeps_strings_with_brackets() {
+    let content = "msg={\"text\": \"a } b { c\"}";
+    assert_eq!(parse_logs(content)[0]["value"]["text"], "a } b { c");
+}
+
+#[test]
+fn test_parse_logs_skips_bracketed_tags() {
+    let content = "[INFO] GET /a [200] ids=[1, \"x\"] [] rows=[{\"id\": 1}] grid=[[1, 2]]\n";
+    assert_eq!(
+        parse_logs(content),
+        json!([
+            {"line": 1, "prefix": "[INFO] GET /a [200] ids=", "value": [1, "x"]},
+            {"line": 1, "prefix": "", "value": []},
+            {"line": 1, "prefix": "rows=", "value": [{"id": 1}]},
+            {"line": 1, "prefix": "grid=", "value": [[1, 2]]},
+        ])
+    );
+}
+
+#[test]
+fn test_parse_logs_truncated_nesting() {
+    // Nothing here closes, so no candidate is parsed; this used to take quadratic time.
+    let content = format!("data={}", "[".repeat(200_000));
+    assert_eq!(parse_logs(&content), json!([]));
+
+    let content = "a={\"x\": [1, {\"y\": 2}\nb=(1] {\"z\": 3}\n";
+    assert_eq!(
+        parse_logs(content),
+        json!([
+            {"line": 1, "prefix": "a={\"x\": [1,", "value": {"y": 2}},
+            {"line": 2, "prefix": "b=(1]", "value": {"z": 3}},
+        ])
+    );
+}
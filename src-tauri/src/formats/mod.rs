@@ -0,0 +1,24 @@
+pub mod avro;
+pub mod bson;
+pub mod cbor;
+pub mod compression;
+pub mod csv;
+pub mod dotenv;
+pub mod ejson;
+pub mod encoding;
+pub mod hcl;
+pub mod ini;
+pub mod jsonc;
+pub mod keys;
+pub mod logs;
+pub mod msgpack;
+pub mod ndjson;
+pub mod numbers;
+pub mod plist;
+pub mod positions;
+pub mod properties;
+pub mod protobuf;
+pub mod repair;
+pub mod tagged;
+pub mod xml;
+pub mod yaml;
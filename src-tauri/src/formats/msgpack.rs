@@ -0,0 +1,175 @@
+//! MessagePack decoding and encoding. Binary data becomes `{"$bytes": ...}`, the timestamp
+//! extension becomes `{"$date": ...}` and any other extension type is kept as
+//! `{"$ext": {"type": n, "data": "<base64>"}}` so it can be written back unchanged.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::tagged::{self, key_string};
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+
+pub const EXT: &str = "$ext";
+
+/// Extension type the MessagePack spec reserves for timestamps.
+const TIMESTAMP_EXT: i8 = -1;
+
+/// Nesting limit, so a crafted input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+fn error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("msgpack", kind, message)
+}
+
+fn timestamp(data: &[u8]) -> Option<DateTime<Utc>> {
+    let (seconds, nanos) = match data.len() {
+        4 => (u32::from_be_bytes(data.try_into().ok()?) as i64, 0),
+        8 => {
+            let packed = u64::from_be_bytes(data.try_into().ok()?);
+            ((packed & 0x3_ffff_ffff) as i64, (packed >> 34) as u32)
+        }
+        12 => (
+            i64::from_be_bytes(data[4..].try_into().ok()?),
+            u32::from_be_bytes(data[..4].try_into().ok()?),
+        ),
+        _ => return None,
+    };
+    DateTime::from_timestamp(seconds, nanos)
+}
+
+fn to_json(value: rmpv::Value) -> Result<Value, ParseError> {
+    Ok(match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(n) => match n.as_u64() {
+            Some(n) => Value::from(n),
+            None => Value::from(n.as_i64().unwrap_or_default()),
+        },
+        rmpv::Value::F32(f) => tagged::float(f as f64),
+        rmpv::Value::F64(f) => tagged::float(f),
+        rmpv::Value::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => {
+                return Err(error(
+                    ParseErrorKind::Data,
+                    "Invalid UTF-8 in string".to_string(),
+                ))
+            }
+        },
+        rmpv::Value::Binary(data) => tagged::bytes(&data),
+        rmpv::Value::Array(items) => {
+            Value::Array(items.into_iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        rmpv::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                map.insert(key_string(to_json(key)?), to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        rmpv::Value::Ext(TIMESTAMP_EXT, data) => timestamp(&data)
+            .map(tagged::date)
+            .ok_or_else(|| error(ParseErrorKind::Data, "Invalid timestamp".to_string()))?,
+        rmpv::Value::Ext(kind, data) => tagged::tagged(
+            EXT,
+            json!({ "type": kind, "data": BASE64_STANDARD.encode(data) }),
+        ),
+    })
+}
+
+pub fn parse_msgpack(bytes: &[u8]) -> Result<Value, ParseError> {
+    let mut rest = bytes;
+    let value = rmpv::decode::read_value_with_max_depth(&mut rest, MAX_DEPTH).map_err(|e| {
+        let at = bytes.len() - rest.len();
+        match e {
+            rmpv::decode::Error::DepthLimitExceeded => error(
+                ParseErrorKind::Data,
+                format!("Nesting deeper than {} levels at byte {}", MAX_DEPTH, at),
+            ),
+            rmpv::decode::Error::InvalidMarkerRead(e) | rmpv::decode::Error::InvalidDataRead(e)
+                if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                error(
+                    ParseErrorKind::Eof,
+                    format!("Unexpected end of data at byte {}", bytes.len()),
+                )
+            }
+            e => error(ParseErrorKind::Syntax, format!("{} at byte {}", e, at)),
+        }
+    })?;
+    if !rest.is_empty() {
+        return Err(error(
+            ParseErrorKind::Syntax,
+            format!(
+                "Unexpected data after the value at byte {}",
+                bytes.len() - rest.len()
+            ),
+        ));
+    }
+    to_json(value)
+}
+
+fn timestamp_ext(datetime: DateTime<Utc>) -> rmpv::Value {
+    let seconds = datetime.timestamp();
+    let nanos = datetime.timestamp_subsec_nanos();
+    let data = if (0..1 << 34).contains(&seconds) {
+        if nanos == 0 && seconds <= u32::MAX as i64 {
+            (seconds as u32).to_be_bytes().to_vec()
+        } else {
+            (((nanos as u64) << 34) | seconds as u64)
+                .to_be_bytes()
+                .to_vec()
+        }
+    } else {
+        let mut data = nanos.to_be_bytes().to_vec();
+        data.extend(seconds.to_be_bytes());
+        data
+    };
+    rmpv::Value::Ext(TIMESTAMP_EXT, data)
+}
+
+/// Reads back an `{"$ext": ...}` object produced by `parse_msgpack`.
+fn as_ext(value: &Value) -> Option<(i8, Vec<u8>)> {
+    let ext = tagged::untag(value, EXT)?;
+    let kind = i8::try_from(ext.get("type")?.as_i64()?).ok()?;
+    let data = BASE64_STANDARD.decode(ext.get("data")?.as_str()?).ok()?;
+    Some((kind, data))
+}
+
+fn from_json(value: &Value) -> rmpv::Value {
+    match value {
+        Value::Null => rmpv::Value::Nil,
+        Value::Bool(b) => rmpv::Value::Boolean(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => rmpv::Value::from(n),
+            (_, Some(n)) => rmpv::Value::from(n),
+            // Out-of-range integers and decimals are rounded; see `numbers::serializer_warnings`.
+            _ => rmpv::Value::F64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => rmpv::Value::from(s.as_str()),
+        Value::Array(items) => rmpv::Value::Array(items.iter().map(from_json).collect()),
+        Value::Object(map) => {
+            if let Some(data) = tagged::as_bytes(value) {
+                rmpv::Value::Binary(data)
+            } else if let Some(datetime) = tagged::as_date(value) {
+                timestamp_ext(datetime)
+            } else if let Some((kind, data)) = as_ext(value) {
+                rmpv::Value::Ext(kind, data)
+            } else {
+                let entries = map
+                    .iter()
+                    .map(|(key, item)| (rmpv::Value::from(key.as_str()), from_json(item)));
+                rmpv::Value::Map(entries.collect())
+            }
+        }
+    }
+}
+
+pub fn to_msgpack(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    rmpv::encode::write_value(&mut out, &from_json(value)).expect("writing to a Vec");
+    out
+}
+
+#[cfg(test)]
+#[path = "msgpack_test.rs"]
+mod msgpack_test;
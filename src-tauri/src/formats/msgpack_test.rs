@@ -0,0 +1,96 @@
+use super::*;
+
+#[test]
+fn test_parses_scalars_and_containers() {
+    // {"a": [1, -1, true, nil], "b": "hi"}
+    let bytes = [
+        0x82, 0xa1, b'a', 0x94, 0x01, 0xff, 0xc3, 0xc0, 0xa1, b'b', 0xa2, b'h', b'i',
+    ];
+    assert_eq!(
+        parse_msgpack(&bytes).unwrap(),
+        json!({"a": [1, -1, true, null], "b": "hi"})
+    );
+}
+
+#[test]
+fn test_parses_wide_integers_and_floats() {
+    let mut bytes = vec![0x93, 0xcf];
+    bytes.extend(u64::MAX.to_be_bytes());
+    bytes.push(0xd3);
+    bytes.extend(i64::MIN.to_be_bytes());
+    bytes.push(0xcb);
+    bytes.extend(2.5f64.to_be_bytes());
+    assert_eq!(
+        parse_msgpack(&bytes).unwrap(),
+        json!([u64::MAX, i64::MIN, 2.5])
+    );
+}
+
+#[test]
+fn test_binary_and_extensions_are_tagged() {
+    let bytes = [0x92, 0xc4, 0x02, 0xde, 0xad, 0xd4, 0x05, 0x2a];
+    assert_eq!(
+        parse_msgpack(&bytes).unwrap(),
+        json!([{"$bytes": "3q0="}, {"$ext": {"type": 5, "data": "Kg=="}}])
+    );
+}
+
+#[test]
+fn test_timestamps_become_dates() {
+    let bytes = [0xd6, 0xff, 0x65, 0x53, 0xf1, 0x00];
+    assert_eq!(
+        parse_msgpack(&bytes).unwrap(),
+        json!({"$date": "2023-11-14T22:13:20Z"})
+    );
+}
+
+#[test]
+fn test_non_string_keys_are_stringified() {
+    let bytes = [0x81, 0x01, 0xa1, b'x'];
+    assert_eq!(parse_msgpack(&bytes).unwrap(), json!({"1": "x"}));
+}
+
+#[test]
+fn test_truncated_input_is_an_eof_error() {
+    let error = parse_msgpack(&[0x92, 0x01]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Eof);
+    assert_eq!(error.format, "msgpack");
+}
+
+#[test]
+fn test_trailing_bytes_are_rejected() {
+    let error = parse_msgpack(&[0x01, 0x02]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+    assert!(error.message.contains("byte 1"));
+}
+
+#[test]
+fn test_deep_nesting_is_rejected() {
+    let error = parse_msgpack(&[0x91; 200]).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Data);
+}
+
+#[test]
+fn test_encoding_round_trips_tagged_values() {
+    let value = json!({
+        "id": 300,
+        "neg": -200,
+        "big": u64::MAX,
+        "ratio": 0.25,
+        "name": "x".repeat(40),
+        "blob": {"$bytes": "3q2+7w=="},
+        "when": {"$date": "2023-11-14T22:13:20.500Z"},
+        "old": {"$date": "1900-01-01T00:00:00Z"},
+        "ext": {"$ext": {"type": 7, "data": "AQID"}},
+        "list": (0..20).collect::<Vec<_>>()
+    });
+    assert_eq!(parse_msgpack(&to_msgpack(&value)).unwrap(), value);
+}
+
+#[test]
+fn test_encoding_uses_the_smallest_integer_form() {
+    assert_eq!(to_msgpack(&json!(5)), [0x05]);
+    assert_eq!(to_msgpack(&json!(-5)), [0xfb]);
+    assert_eq!(to_msgpack(&json!(200)), [0xcc, 200]);
+    assert_eq!(to_msgpack(&json!(-100)), [0xd0, 0x9c]);
+}
@@ -0,0 +1,54 @@
+use crate::commands::parser::{json_error, ParseError};
+use serde_json::Value;
+
+/// Reads one JSON value per line into an array.
+///
+/// Lines that fail to parse are pushed to `errors` (positioned in the whole document) and
+/// skipped, so one corrupt log line does not hide the rest of the file. Only a file where no
+/// line parses at all is a hard error.
+pub fn parse_ndjson(content: &str, errors: &mut Vec<ParseError>) -> Result<Value, ParseError> {
+    let mut values = Vec::new();
+    let mut line_errors = Vec::new();
+    let mut offset = 0;
+
+    for raw_line in content.split_inclusive('\n') {
+        let line = raw_line.trim_end_matches(['\n', '\r']);
+        if !line.trim().is_empty() {
+            match serde_json::from_str(line) {
+                Ok(value) => values.push(value),
+                Err(e) => {
+                    let mut error = json_error(line, e);
+                    let line_offset = error.location.map_or(0, |l| l.offset);
+                    error.format = "ndjson".to_string();
+                    line_errors.push(error.at_offset(content, offset + line_offset));
+                }
+            }
+        }
+        offset += raw_line.len();
+    }
+
+    if values.is_empty() && !line_errors.is_empty() {
+        return Err(line_errors.remove(0));
+    }
+    errors.extend(line_errors);
+    Ok(Value::Array(values))
+}
+
+/// Writes each element of an array on its own line; any other value becomes a single line.
+pub fn to_ndjson(value: &Value) -> Result<String, String> {
+    let items = match value {
+        Value::Array(arr) => arr.iter().collect(),
+        _ => vec![value],
+    };
+
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item).map_err(|e| e.to_string())?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "ndjson_test.rs"]
+mod ndjson_test;
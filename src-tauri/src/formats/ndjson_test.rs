@@ -0,0 +1,41 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_parse_ndjson() {
+    let content = "{\"id\": 1}\n\n{\"id\": 2}\r\n[3]\n";
+    let mut errors = Vec::new();
+    let value = parse_ndjson(content, &mut errors).unwrap();
+    assert_eq!(value, json!([{"id": 1}, {"id": 2}, [3]]));
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_parse_ndjson_reports_bad_lines() {
+    let content = "{\"id\": 1}\n{\"id\": }\n{\"id\": 3}\nnot json\n";
+    let mut errors = Vec::new();
+    let value = parse_ndjson(content, &mut errors).unwrap();
+    assert_eq!(value, json!([{"id": 1}, {"id": 3}]));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].format, "ndjson");
+    assert_eq!(errors[0].location.unwrap().line, 2);
+    assert_eq!(errors[0].location.unwrap().column, 8);
+    assert_eq!(errors[1].location.unwrap().line, 4);
+}
+
+#[test]
+fn test_parse_ndjson_fails_when_nothing_parses() {
+    let mut errors = Vec::new();
+    let err = parse_ndjson("nope\n", &mut errors).unwrap_err();
+    assert_eq!(err.location.unwrap().line, 1);
+}
+
+#[test]
+fn test_to_ndjson() {
+    let value = json!([{"id": 1, "tags": ["a"]}, {"id": 2}]);
+    assert_eq!(
+        to_ndjson(&value).unwrap(),
+        "{\"id\":1,\"tags\":[\"a\"]}\n{\"id\":2}\n"
+    );
+    assert_eq!(to_ndjson(&json!({"id": 1})).unwrap(), "{\"id\":1}\n");
+}
@@ -0,0 +1,170 @@
+//! serde_json is built with `arbitrary_precision`, so numbers parsed from JSON keep their
+//! original text. These helpers find the ones a consumer limited to doubles or 64-bit
+//! integers would round, so callers can warn instead of silently corrupting them.
+
+use serde::{Serialize, Serializer};
+use serde_json::{Number, Value};
+
+/// Splits a decimal literal into sign, significant digits and the exponent of the last digit,
+/// e.g. `-12.50e3` becomes `(true, "125", 2)`. Zero has no digits.
+fn normalize(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], unsigned[i + 1..].parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if !int_part
+        .chars()
+        .chain(frac_part.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits = format!("{}{}", int_part, frac_part);
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    let exponent = exponent - frac_part.len() as i64 + (digits.len() - trimmed.len()) as i64;
+
+    if trimmed.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    Some((negative, trimmed.to_string(), exponent))
+}
+
+/// Whether a numeric literal comes back with the same value after a trip through `f64`.
+pub fn survives_f64(text: &str) -> bool {
+    match text.parse::<f64>() {
+        Ok(f) if f.is_finite() => normalize(text) == normalize(&format!("{:e}", f)),
+        _ => false,
+    }
+}
+
+/// Whether a number can be written exactly by serializers limited to `i64`, `u64` and `f64`.
+pub fn survives_serde(number: &Number) -> bool {
+    number.is_i64() || number.is_u64() || survives_f64(&number.to_string())
+}
+
+/// Appends an object key to a JSONPath, bracket-quoting keys that are not identifiers.
+pub fn push_key(path: &str, key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::String(key.to_string()))
+    }
+}
+
+fn collect(
+    value: &Value,
+    path: String,
+    keep: &dyn Fn(&Number) -> bool,
+    out: &mut Vec<(String, String)>,
+) {
+    match value {
+        Value::Number(n) if !keep(n) => out.push((path, n.to_string())),
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                collect(item, format!("{}[{}]", path, i), keep, out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                collect(item, push_key(&path, key), keep, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the JSONPath and original text of every number that `keep` rejects.
+pub fn find_numbers(value: &Value, keep: &dyn Fn(&Number) -> bool) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    collect(value, "$".to_string(), keep, &mut out);
+    out
+}
+
+/// Replaces numbers that a double cannot hold with strings of their original text, so the
+/// webview shows every digit.
+pub fn stringify_unsafe_numbers(value: &mut Value) {
+    match value {
+        Value::Number(n) if !survives_f64(&n.to_string()) => {
+            *value = Value::String(n.to_string());
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(stringify_unsafe_numbers),
+        Value::Object(map) => map.values_mut().for_each(stringify_unsafe_numbers),
+        _ => {}
+    }
+}
+
+/// Warnings for numbers the webview will round once it parses the IPC response.
+pub fn viewer_warnings(value: &Value) -> Vec<String> {
+    find_numbers(value, &|n| survives_f64(&n.to_string()))
+        .into_iter()
+        .map(|(path, text)| {
+            format!(
+                "Number {} at {} exceeds double precision and will be rounded in the viewer; use lossless numbers to keep it exact",
+                text, path
+            )
+        })
+        .collect()
+}
+
+/// Warnings for numbers a `target` serializer can only write rounded.
+pub fn serializer_warnings(value: &Value, target: &str) -> Vec<String> {
+    find_numbers(value, &survives_serde)
+        .into_iter()
+        .map(|(path, text)| {
+            format!(
+                "Number {} at {} cannot be represented exactly in {} and was rounded",
+                text,
+                path,
+                target.to_uppercase()
+            )
+        })
+        .collect()
+}
+
+/// Serializes a `Value` with plain `i64`/`u64`/`f64` numbers.
+///
+/// With `arbitrary_precision`, `Number` serializes as a private newtype that only serde_json
+/// understands, so every non-JSON serializer has to go through this wrapper.
+pub struct Portable<'a>(pub &'a Value);
+
+impl Serialize for Portable<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    serializer.serialize_i64(i)
+                } else if let Some(u) = n.as_u64() {
+                    serializer.serialize_u64(u)
+                } else if let Some(f) = n.as_f64() {
+                    serializer.serialize_f64(f)
+                } else {
+                    // Out of range for a double: keep the digits rather than write infinity.
+                    serializer.serialize_str(&n.to_string())
+                }
+            }
+            Value::Array(arr) => serializer.collect_seq(arr.iter().map(Portable)),
+            Value::Object(map) => {
+                serializer.collect_map(map.iter().map(|(key, item)| (key, Portable(item))))
+            }
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "numbers_test.rs"]
+mod numbers_test;
@@ -0,0 +1,67 @@
+use super::*;
+use serde_json::json;
+
+fn parse(text: &str) -> Value {
+    serde_json::from_str(text).unwrap()
+}
+
+#[test]
+fn test_survives_f64() {
+    assert!(survives_f64("0.1"));
+    assert!(survives_f64("1.50"));
+    assert!(survives_f64("-2.5e-3"));
+    assert!(survives_f64("9007199254740992"));
+    assert!(!survives_f64("9007199254740993"));
+    assert!(!survives_f64("1234567890123456789"));
+    assert!(!survives_f64("0.1000000000000000055"));
+    assert!(!survives_f64("1e400"));
+}
+
+#[test]
+fn test_arbitrary_precision_keeps_text() {
+    let value = parse(r#"{"id": 1234567890123456789012, "price": 0.1000000000000000055}"#);
+    assert_eq!(value["id"].to_string(), "1234567890123456789012");
+    assert_eq!(value["price"].to_string(), "0.1000000000000000055");
+}
+
+#[test]
+fn test_find_numbers_paths() {
+    let value = parse(r#"{"ids": [1, 1311768467463790320], "a b": {"x": 0.30000000000000000001}}"#);
+    let found = find_numbers(&value, &|n| survives_f64(&n.to_string()));
+    assert_eq!(
+        found,
+        vec![
+            ("$.ids[1]".to_string(), "1311768467463790320".to_string()),
+            (
+                "$[\"a b\"].x".to_string(),
+                "0.30000000000000000001".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_stringify_unsafe_numbers() {
+    let mut value = parse(r#"{"id": 1311768467463790320, "count": 3}"#);
+    stringify_unsafe_numbers(&mut value);
+    assert_eq!(value, json!({"id": "1311768467463790320", "count": 3}));
+}
+
+#[test]
+fn test_portable_serializes_plain_numbers() {
+    let value = parse(r#"{"int": 42, "big": 18446744073709551615, "float": 1.5}"#);
+    let yaml = serde_yaml::to_string(&Portable(&value)).unwrap();
+    assert!(yaml.contains("int: 42"));
+    assert!(yaml.contains("big: 18446744073709551615"));
+    assert!(yaml.contains("float: 1.5"));
+    assert!(!yaml.contains("serde_json"));
+}
+
+#[test]
+fn test_serializer_warnings() {
+    let value = parse(r#"{"ok": 18446744073709551615, "lossy": 123456789012345678901234}"#);
+    let warnings = serializer_warnings(&value, "toml");
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("$.lossy"));
+    assert!(warnings[0].contains("TOML"));
+}
@@ -0,0 +1,266 @@
+//! Apple property lists in the XML and the binary (`bplist00`) flavor.
+//!
+//! `<date>` values become `{"$date": ...}` and `<data>` values `{"$bytes": ...}`, as in the
+//! binary formats. Keyed-archiver UIDs, which only binary plists can hold, become
+//! `{"CF$UID": n}`, the same dictionary XML plists use for them. Property lists have no null, so
+//! writing a value that contains one is an error.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::numbers::push_key;
+use crate::formats::tagged;
+use chrono::{DateTime, Utc};
+use plist::stream::{BinaryReader, OwnedEvent, XmlReader};
+use plist::{Date, Dictionary, Integer, Uid};
+use serde_json::{Map, Number, Value};
+use std::io::Cursor;
+use std::time::SystemTime;
+
+pub const BINARY_MAGIC: &[u8] = b"bplist00";
+
+/// Key of the dictionary that stands for a UID.
+const UID_KEY: &str = "CF$UID";
+
+/// Nesting limit, so a crafted input cannot exhaust memory through deep nesting.
+const MAX_DEPTH: usize = 128;
+
+/// Objects in a binary plist can be shared, so a small file can expand to a huge tree. This is
+/// why reading goes through the event stream instead of `plist::Value::from_reader`.
+const MAX_VALUES: usize = 1 << 24;
+
+/// Splits the `(offset N)` suffix off a `plist::Error` message.
+fn split_offset(error: &plist::Error) -> (String, Option<usize>) {
+    let message = error.to_string();
+    if let Some((text, rest)) = message.rsplit_once(" (offset ") {
+        if let Ok(offset) = rest.trim_end_matches(')').parse() {
+            return (text.to_string(), Some(offset));
+        }
+    }
+    (message, None)
+}
+
+fn error_kind(error: &plist::Error) -> ParseErrorKind {
+    if error.is_eof() {
+        ParseErrorKind::Eof
+    } else {
+        ParseErrorKind::Syntax
+    }
+}
+
+enum Frame {
+    Dict(Map<String, Value>, Option<String>),
+    Array(Vec<Value>),
+}
+
+fn integer(n: Integer) -> Value {
+    match n.as_signed() {
+        Some(n) => Value::from(n),
+        None => Value::from(n.as_unsigned().unwrap_or_default()),
+    }
+}
+
+fn real(f: f64) -> Value {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| tagged::float(f))
+}
+
+/// Builds the JSON value from a plist event stream without recursing.
+fn build<E>(
+    events: impl Iterator<Item = Result<OwnedEvent, plist::Error>>,
+    error: E,
+) -> Result<Value, ParseError>
+where
+    E: Fn(ParseErrorKind, String, Option<usize>) -> ParseError,
+{
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut values = 0usize;
+    for event in events {
+        let event = event.map_err(|e| {
+            let (message, offset) = split_offset(&e);
+            error(error_kind(&e), message, offset)
+        })?;
+        values += 1;
+        if values > MAX_VALUES {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!("More than {} values", MAX_VALUES),
+                None,
+            ));
+        }
+        let value = match event {
+            OwnedEvent::StartArray(_) | OwnedEvent::StartDictionary(_) => {
+                if stack.len() >= MAX_DEPTH {
+                    return Err(error(
+                        ParseErrorKind::Data,
+                        format!("Nesting deeper than {} levels", MAX_DEPTH),
+                        None,
+                    ));
+                }
+                stack.push(match event {
+                    OwnedEvent::StartArray(_) => Frame::Array(Vec::new()),
+                    _ => Frame::Dict(Map::new(), None),
+                });
+                continue;
+            }
+            OwnedEvent::EndCollection => match stack.pop() {
+                Some(Frame::Dict(map, None)) => Value::Object(map),
+                Some(Frame::Dict(_, Some(key))) => {
+                    let message = format!("Key {} has no value", key);
+                    return Err(error(ParseErrorKind::Syntax, message, None));
+                }
+                Some(Frame::Array(items)) => Value::Array(items),
+                None => {
+                    let message = "Unexpected end of collection".to_string();
+                    return Err(error(ParseErrorKind::Syntax, message, None));
+                }
+            },
+            OwnedEvent::String(text) => {
+                if let Some(Frame::Dict(_, key @ None)) = stack.last_mut() {
+                    *key = Some(text.into_owned());
+                    continue;
+                }
+                Value::String(text.into_owned())
+            }
+            OwnedEvent::Boolean(b) => Value::Bool(b),
+            OwnedEvent::Integer(n) => integer(n),
+            OwnedEvent::Real(f) => real(f),
+            OwnedEvent::Date(date) => tagged::date(DateTime::<Utc>::from(SystemTime::from(date))),
+            OwnedEvent::Data(data) => tagged::bytes(&data),
+            OwnedEvent::Uid(uid) => tagged::tagged(UID_KEY, Value::from(uid.get())),
+            _ => {
+                let message = "Unsupported property list value".to_string();
+                return Err(error(ParseErrorKind::Unsupported, message, None));
+            }
+        };
+        match stack.last_mut() {
+            Some(Frame::Array(items)) => items.push(value),
+            Some(Frame::Dict(map, key)) => match key.take() {
+                Some(key) => {
+                    map.insert(key, value);
+                }
+                None => {
+                    let message = "Dictionary key is not a string".to_string();
+                    return Err(error(ParseErrorKind::Syntax, message, None));
+                }
+            },
+            None => return Ok(value),
+        }
+    }
+    Err(error(
+        ParseErrorKind::Eof,
+        "Unexpected end of property list".to_string(),
+        None,
+    ))
+}
+
+/// Parses an XML property list.
+pub fn parse_xml_plist(content: &str) -> Result<Value, ParseError> {
+    build(
+        XmlReader::new(content.as_bytes()),
+        |kind, message, offset| {
+            let error = ParseError::new("plist", kind, message);
+            match offset {
+                Some(offset) => error.at_offset(content, offset.min(content.len())),
+                None => error,
+            }
+        },
+    )
+}
+
+/// Parses a binary (`bplist00`) property list.
+pub fn parse_binary_plist(bytes: &[u8]) -> Result<Value, ParseError> {
+    if !bytes.starts_with(BINARY_MAGIC) {
+        return Err(binary_error(
+            ParseErrorKind::Syntax,
+            "Not a binary property list (missing bplist00 header)".to_string(),
+        ));
+    }
+    build(
+        BinaryReader::new(Cursor::new(bytes)),
+        |kind, message, offset| match offset {
+            Some(offset) => binary_error(kind, format!("{} at byte {}", message, offset)),
+            None => binary_error(kind, message),
+        },
+    )
+}
+
+fn binary_error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("bplist", kind, message)
+}
+
+fn null_error(path: &str) -> String {
+    format!("Property lists cannot hold null (at {})", path)
+}
+
+fn is_integer(number: &Number) -> bool {
+    number.as_i64().is_some()
+        || number.as_u64().is_some()
+        || !number.to_string().contains(['.', 'e', 'E'])
+}
+
+/// Converts JSON to a `plist::Value`. UIDs only exist in binary plists, so the `CF$UID`
+/// dictionary stays a dictionary when `uids` is off.
+fn to_plist_value(value: &Value, path: &str, uids: bool) -> Result<plist::Value, String> {
+    Ok(match value {
+        Value::Null => return Err(null_error(path)),
+        Value::Bool(b) => plist::Value::Boolean(*b),
+        Value::Number(n) if is_integer(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => plist::Value::Integer(i.into()),
+            (_, Some(u)) => plist::Value::Integer(u.into()),
+            _ => return Err(format!("Integer {} is out of range (at {})", n, path)),
+        },
+        Value::Number(n) => plist::Value::Real(n.as_f64().unwrap_or(f64::NAN)),
+        Value::String(s) => plist::Value::String(s.clone()),
+        Value::Array(items) => plist::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| to_plist_value(item, &format!("{}[{}]", path, i), uids))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => {
+            if let Some(data) = tagged::as_bytes(value) {
+                return Ok(plist::Value::Data(data));
+            }
+            if let Some(datetime) = tagged::as_date(value) {
+                return Ok(plist::Value::Date(Date::from(SystemTime::from(datetime))));
+            }
+            if let Some(uid) = tagged::untag(value, UID_KEY).and_then(Value::as_u64) {
+                if uids {
+                    return Ok(plist::Value::Uid(Uid::new(uid)));
+                }
+            }
+            let mut dict = Dictionary::new();
+            for (key, item) in map {
+                dict.insert(
+                    key.clone(),
+                    to_plist_value(item, &push_key(path, key), uids)?,
+                );
+            }
+            plist::Value::Dictionary(dict)
+        }
+    })
+}
+
+/// Writes an XML property list the way `plutil` does, with tab indentation.
+pub fn to_xml_plist(value: &Value) -> Result<String, String> {
+    let mut out = Vec::new();
+    to_plist_value(value, "$", false)?
+        .to_writer_xml(&mut out)
+        .map_err(|e| e.to_string())?;
+    out.push(b'\n');
+    String::from_utf8(out).map_err(|e| e.to_string())
+}
+
+/// Writes a binary (`bplist00`) property list.
+pub fn to_binary_plist(value: &Value) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    to_plist_value(value, "$", true)?
+        .to_writer_binary(&mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "plist_test.rs"]
+mod plist_test;
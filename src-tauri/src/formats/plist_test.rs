@@ -0,0 +1,113 @@
+use super::*;
+use serde_json::json;
+
+const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleName</key>
+	<string>JSON &amp; Map</string>
+	<key>Version</key>
+	<integer>42</integer>
+	<key>Scale</key>
+	<real>1.5</real>
+	<key>Enabled</key>
+	<true/>
+	<key>Built</key>
+	<date>2023-11-14T22:13:20Z</date>
+	<key>Icon</key>
+	<data>
+	3q2+7w==
+	</data>
+	<key>Tags</key>
+	<array>
+		<string>a</string>
+		<dict/>
+	</array>
+</dict>
+</plist>
+"#;
+
+fn sample_value() -> Value {
+    json!({
+        "CFBundleName": "JSON & Map",
+        "Version": 42,
+        "Scale": 1.5,
+        "Enabled": true,
+        "Built": {"$date": "2023-11-14T22:13:20Z"},
+        "Icon": {"$bytes": "3q2+7w=="},
+        "Tags": ["a", {}]
+    })
+}
+
+#[test]
+fn test_parses_xml_plist() {
+    assert_eq!(parse_xml_plist(SAMPLE).unwrap(), sample_value());
+}
+
+#[test]
+fn test_writes_xml_plist_like_plutil() {
+    assert_eq!(to_xml_plist(&sample_value()).unwrap(), SAMPLE);
+}
+
+#[test]
+fn test_xml_errors_have_locations() {
+    let error = parse_xml_plist("<plist><integer>1.5</integer></plist>").unwrap_err();
+    assert_eq!(error.format, "plist");
+    assert_eq!(error.kind, ParseErrorKind::Syntax);
+    assert_eq!(error.location.unwrap().column, 30);
+
+    let error = parse_xml_plist("<plist><dict><key>x</key></dict></plist>").unwrap_err();
+    assert!(error.message.contains("no value"), "{}", error.message);
+}
+
+#[test]
+fn test_deep_nesting_is_rejected() {
+    let content = format!(
+        "<plist>{}{}</plist>",
+        "<array>".repeat(200),
+        "</array>".repeat(200)
+    );
+    let error = parse_xml_plist(&content).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Data);
+}
+
+#[test]
+fn test_null_cannot_be_written() {
+    let error = to_xml_plist(&json!({"a": [null]})).unwrap_err();
+    assert!(error.contains("$.a[0]"), "{error}");
+    assert!(to_binary_plist(&json!(null)).is_err());
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let mut value = sample_value();
+    value["Negative"] = json!(-7);
+    value["Huge"] = json!(u64::MAX);
+    value["Unicode"] = json!("Zoë ✓");
+    value["Long"] = json!("x".repeat(300));
+    value["Archiver"] = json!({"$top": {"root": {"CF$UID": 1}}});
+    let bytes = to_binary_plist(&value).unwrap();
+    assert!(bytes.starts_with(BINARY_MAGIC));
+    assert_eq!(parse_binary_plist(&bytes).unwrap(), value);
+}
+
+#[test]
+fn test_binary_dates_count_from_2001() {
+    let bytes = to_binary_plist(&json!({"$date": "2001-01-01T00:00:10Z"})).unwrap();
+    // The date object follows the header: marker 0x33 and a big-endian double.
+    assert_eq!(bytes[8], 0x33);
+    assert_eq!(f64::from_be_bytes(bytes[9..17].try_into().unwrap()), 10.0);
+}
+
+#[test]
+fn test_binary_rejects_bad_input() {
+    assert_eq!(
+        parse_binary_plist(b"not a plist").unwrap_err().kind,
+        ParseErrorKind::Syntax
+    );
+    let mut bytes = to_binary_plist(&json!(["a", "b"])).unwrap();
+    let len = bytes.len();
+    bytes.truncate(len - 40);
+    assert!(parse_binary_plist(&bytes).is_err());
+}
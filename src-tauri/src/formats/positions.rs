@@ -0,0 +1,552 @@
+//! Where each value of a parsed document sits in its source text, so the editor can highlight
+//! the text of a graph node and place errors found on the value.
+//!
+//! Spans cover the value itself, not its key: from the first character of a scalar, `{` or `[`
+//! to just past its last character. Block collections in YAML and tables in TOML have no
+//! closing bracket, so they end with their last child. Offsets are bytes into the text that was
+//! parsed, after any decoding.
+//!
+//! The same scan finds keys repeated within an object, which serde_json and serde_yaml accept
+//! silently by keeping the last value.
+
+use crate::commands::parser::SourceLocation;
+use crate::formats::numbers::push_key;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// One step of a path: an object key or an array index, as in the graph's node paths.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodePosition {
+    pub path: Vec<PathSegment>,
+    pub start: SourceLocation,
+    pub end: SourceLocation,
+}
+
+/// A key that appears more than once in the same object. Parsers keep only the last value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateKey {
+    pub path: Vec<PathSegment>,
+    /// Where each occurrence of the key starts.
+    pub locations: Vec<SourceLocation>,
+}
+
+/// Writes a path as JSONPath, e.g. `$.users[0].name`, for messages.
+pub fn json_path(path: &[PathSegment]) -> String {
+    path.iter()
+        .fold("$".to_string(), |out, segment| match segment {
+            PathSegment::Key(key) => push_key(&out, key),
+            PathSegment::Index(index) => format!("{}[{}]", out, index),
+        })
+}
+
+/// Line starts of a text, so each offset converts to a location without rescanning.
+struct LineIndex<'a> {
+    content: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(content: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { content, starts }
+    }
+
+    fn location(&self, offset: usize) -> SourceLocation {
+        let mut offset = offset.min(self.content.len());
+        while !self.content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        SourceLocation {
+            line: line + 1,
+            column: self.content[self.starts[line]..offset].chars().count() + 1,
+            offset,
+        }
+    }
+}
+
+/// Collects spans and duplicate keys as byte offsets, then converts them all at once.
+#[derive(Default)]
+struct Spans {
+    /// Whether to record the span of every node, or only look for duplicate keys.
+    keep: bool,
+    spans: Vec<(Vec<PathSegment>, Range<usize>)>,
+    duplicates: Vec<(Vec<PathSegment>, Vec<usize>)>,
+}
+
+impl Spans {
+    fn new(keep: bool) -> Self {
+        Spans {
+            keep,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a node before its children are known, returning its slot so the end can be set later.
+    fn open(&mut self, path: &[PathSegment], start: usize) -> Option<usize> {
+        if !self.keep {
+            return None;
+        }
+        self.spans.push((path.to_vec(), start..start));
+        Some(self.spans.len() - 1)
+    }
+
+    fn set(&mut self, slot: Option<usize>, range: Range<usize>) {
+        if let Some(slot) = slot {
+            self.spans[slot].1 = range;
+        }
+    }
+
+    fn close(&mut self, slot: Option<usize>, end: usize) {
+        if let Some(slot) = slot {
+            self.spans[slot].1.end = end;
+        }
+    }
+
+    fn finish(self, content: &str) -> Vec<NodePosition> {
+        let lines = LineIndex::new(content);
+        self.spans
+            .into_iter()
+            .map(|(path, range)| NodePosition {
+                path,
+                start: lines.location(range.start),
+                end: lines.location(range.end),
+            })
+            .collect()
+    }
+
+    fn finish_duplicates(self, content: &str) -> Vec<DuplicateKey> {
+        let lines = LineIndex::new(content);
+        self.duplicates
+            .into_iter()
+            .map(|(path, offsets)| DuplicateKey {
+                path,
+                locations: offsets.into_iter().map(|o| lines.location(o)).collect(),
+            })
+            .collect()
+    }
+}
+
+/// The keys of one object in order, with where each occurrence starts.
+#[derive(Default)]
+struct Keys {
+    keys: Vec<(String, Vec<usize>)>,
+    index: HashMap<String, usize>,
+}
+
+impl Keys {
+    fn add(&mut self, key: &str, offset: usize) {
+        match self.index.get(key) {
+            Some(&i) => self.keys[i].1.push(offset),
+            None => {
+                self.index.insert(key.to_string(), self.keys.len());
+                self.keys.push((key.to_string(), vec![offset]));
+            }
+        }
+    }
+
+    fn report(self, path: &[PathSegment], spans: &mut Spans) {
+        for (key, offsets) in self.keys {
+            if offsets.len() > 1 {
+                let mut path = path.to_vec();
+                path.push(PathSegment::Key(key));
+                spans.duplicates.push((path, offsets));
+            }
+        }
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+fn json_string_end(bytes: &[u8], mut i: usize) -> usize {
+    i += 1;
+    while i < bytes.len() && bytes[i] != b'"' {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// Records the value starting at `i` and everything in it. Returns the offset just past it.
+fn json_value(content: &str, i: usize, path: &mut Vec<PathSegment>, spans: &mut Spans) -> usize {
+    let bytes = content.as_bytes();
+    let start = skip_whitespace(bytes, i);
+    let slot = spans.open(path, start);
+    let end = match bytes.get(start) {
+        Some(b'{') => {
+            let mut i = skip_whitespace(bytes, start + 1);
+            let mut keys = Keys::default();
+            while bytes.get(i) == Some(&b'"') {
+                let key_end = json_string_end(bytes, i);
+                let key: String = serde_json::from_str(&content[i..key_end])
+                    .unwrap_or_else(|_| content[i + 1..key_end - 1].to_string());
+                keys.add(&key, i);
+                let colon = skip_whitespace(bytes, key_end);
+                path.push(PathSegment::Key(key));
+                i = json_value(content, colon + 1, path, spans);
+                path.pop();
+                i = skip_whitespace(bytes, i);
+                if bytes.get(i) == Some(&b',') {
+                    i = skip_whitespace(bytes, i + 1);
+                }
+            }
+            keys.report(path, spans);
+            (i + 1).min(bytes.len())
+        }
+        Some(b'[') => {
+            let mut i = skip_whitespace(bytes, start + 1);
+            let mut index = 0;
+            while i < bytes.len() && bytes[i] != b']' {
+                path.push(PathSegment::Index(index));
+                i = skip_whitespace(bytes, json_value(content, i, path, spans));
+                path.pop();
+                if bytes.get(i) == Some(&b',') {
+                    i = skip_whitespace(bytes, i + 1);
+                }
+                index += 1;
+            }
+            (i + 1).min(bytes.len())
+        }
+        Some(b'"') => json_string_end(bytes, start),
+        _ => {
+            let mut i = start;
+            while i < bytes.len() && !matches!(bytes[i], b',' | b']' | b'}') {
+                i += 1;
+            }
+            start + content[start..i].trim_end().len()
+        }
+    };
+    spans.close(slot, end);
+    end
+}
+
+/// Positions in JSON that has already parsed. JSONC works too once its comments and trailing
+/// commas are blanked out, which keeps offsets intact.
+pub fn json_positions(content: &str) -> Vec<NodePosition> {
+    let mut spans = Spans::new(true);
+    json_value(content, 0, &mut Vec::new(), &mut spans);
+    spans.finish(content)
+}
+
+/// Duplicate keys in JSON that has already parsed, in document order.
+pub fn json_duplicate_keys(content: &str) -> Vec<DuplicateKey> {
+    let mut spans = Spans::new(false);
+    json_value(content, 0, &mut Vec::new(), &mut spans);
+    spans.finish_duplicates(content)
+}
+
+enum YamlEvent {
+    DocumentStart,
+    MappingStart,
+    SequenceStart,
+    /// Ends a mapping or a sequence.
+    End,
+    Scalar(String),
+    Alias,
+    Other,
+}
+
+/// serde_yaml does not expose where nodes are, so they come from the event stream of
+/// saphyr-parser, which marks the span of every event.
+struct YamlEvents<'a> {
+    parser: saphyr_parser::Parser<'a, saphyr_parser::StrInput<'a>>,
+    content: &'a str,
+    /// The byte offset of each character, since the parser counts characters. Empty for ASCII.
+    offsets: Vec<usize>,
+    done: bool,
+}
+
+impl<'a> YamlEvents<'a> {
+    fn new(content: &'a str) -> Self {
+        let offsets = if content.is_ascii() {
+            Vec::new()
+        } else {
+            content.char_indices().map(|(i, _)| i).collect()
+        };
+        YamlEvents {
+            parser: saphyr_parser::Parser::new_from_str(content),
+            content,
+            offsets,
+            done: false,
+        }
+    }
+
+    fn offset(&self, marker: &saphyr_parser::Marker) -> usize {
+        if self.offsets.is_empty() {
+            return marker.index().min(self.content.len());
+        }
+        self.offsets
+            .get(marker.index())
+            .copied()
+            .unwrap_or(self.content.len())
+    }
+}
+
+impl Iterator for YamlEvents<'_> {
+    type Item = (YamlEvent, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let Some(Ok((event, span))) = self.parser.next_event() else {
+            self.done = true;
+            return None;
+        };
+        let mut range = self.offset(&span.start)..self.offset(&span.end);
+        let kind = match event {
+            saphyr_parser::Event::DocumentStart(_) => YamlEvent::DocumentStart,
+            saphyr_parser::Event::MappingStart(..) => YamlEvent::MappingStart,
+            saphyr_parser::Event::SequenceStart(..) => YamlEvent::SequenceStart,
+            saphyr_parser::Event::MappingEnd | saphyr_parser::Event::SequenceEnd => YamlEvent::End,
+            saphyr_parser::Event::Scalar(value, style, ..) => {
+                // Block scalars run on to the indentation of the next line.
+                if matches!(
+                    style,
+                    saphyr_parser::ScalarStyle::Literal | saphyr_parser::ScalarStyle::Folded
+                ) {
+                    range.end = range.start + self.content[range.clone()].trim_end().len();
+                }
+                YamlEvent::Scalar(value.into_owned())
+            }
+            saphyr_parser::Event::Alias(_) => YamlEvent::Alias,
+            saphyr_parser::Event::StreamEnd => {
+                self.done = true;
+                YamlEvent::Other
+            }
+            _ => YamlEvent::Other,
+        };
+        Some((kind, range))
+    }
+}
+
+/// Byte offsets where the documents of a YAML stream start: the `---` marker, or the first
+/// content of a document without one. Stops where the stream fails to parse.
+pub(crate) fn yaml_document_starts(content: &str) -> Vec<usize> {
+    YamlEvents::new(content)
+        .filter_map(|(kind, range)| matches!(kind, YamlEvent::DocumentStart).then_some(range.start))
+        .collect()
+}
+
+/// Records the node that `first` starts. Returns the offset just past it.
+fn yaml_node(
+    content: &str,
+    first: (YamlEvent, Range<usize>),
+    events: &mut YamlEvents<'_>,
+    path: &mut Vec<PathSegment>,
+    spans: &mut Spans,
+) -> usize {
+    let (kind, range) = first;
+    let slot = spans.open(path, range.start);
+    let end = match kind {
+        YamlEvent::MappingStart | YamlEvent::SequenceStart => {
+            let is_mapping = matches!(kind, YamlEvent::MappingStart);
+            let mut last_end = range.end;
+            let mut index = 0;
+            let mut keys = Keys::default();
+            while let Some(event) = events.next() {
+                if let YamlEvent::End = event.0 {
+                    // Flow collections end at their bracket, block ones with their last child.
+                    if matches!(content.as_bytes().get(range.start), Some(b'{' | b'[')) {
+                        last_end = event.1.end;
+                    }
+                    break;
+                }
+                if !is_mapping {
+                    path.push(PathSegment::Index(index));
+                    last_end = yaml_node(content, event, events, path, spans);
+                    path.pop();
+                    index += 1;
+                    continue;
+                }
+                let key = match &event.0 {
+                    YamlEvent::Scalar(key) => {
+                        keys.add(key, event.1.start);
+                        key.clone()
+                    }
+                    // Complex keys have no path of their own, so they only count as text.
+                    _ => {
+                        let start = event.1.start;
+                        let end = yaml_node(
+                            content,
+                            event,
+                            events,
+                            &mut Vec::new(),
+                            &mut Spans::new(false),
+                        );
+                        content[start..end].to_string()
+                    }
+                };
+                let Some(value) = events.next() else {
+                    break;
+                };
+                path.push(PathSegment::Key(key));
+                last_end = yaml_node(content, value, events, path, spans);
+                path.pop();
+            }
+            keys.report(path, spans);
+            last_end
+        }
+        _ => range.end,
+    };
+    spans.close(slot, end);
+    end
+}
+
+fn in_document<T>(index: usize, (mut path, value): (Vec<PathSegment>, T)) -> (Vec<PathSegment>, T) {
+    path.insert(0, PathSegment::Index(index));
+    (path, value)
+}
+
+/// Scans a YAML stream. Several documents make an array, as `yaml::parse_yaml` returns them,
+/// so their paths start with the document index.
+fn yaml_scan(content: &str, keep: bool) -> Spans {
+    let mut events = YamlEvents::new(content);
+    let mut documents = Vec::new();
+    while let Some((kind, _)) = events.next() {
+        if let YamlEvent::DocumentStart = kind {
+            let Some(root) = events.next() else {
+                break;
+            };
+            let mut spans = Spans::new(keep);
+            let start = root.1.start;
+            let end = yaml_node(content, root, &mut events, &mut Vec::new(), &mut spans);
+            documents.push((spans, start..end));
+        }
+    }
+    drop(events);
+
+    if documents.len() <= 1 {
+        return documents
+            .pop()
+            .map_or_else(|| Spans::new(keep), |(spans, _)| spans);
+    }
+    let mut stream = Spans::new(keep);
+    let range = documents[0].1.start..documents[documents.len() - 1].1.end;
+    let slot = stream.open(&[], range.start);
+    stream.set(slot, range);
+    for (index, (document, _)) in documents.into_iter().enumerate() {
+        stream
+            .spans
+            .extend((document.spans.into_iter()).map(|entry| in_document(index, entry)));
+        stream
+            .duplicates
+            .extend((document.duplicates.into_iter()).map(|entry| in_document(index, entry)));
+    }
+    stream
+}
+
+/// Positions in YAML that has already parsed.
+pub fn yaml_positions(content: &str) -> Vec<NodePosition> {
+    yaml_scan(content, true).finish(content)
+}
+
+/// Duplicate keys in YAML that has already parsed, in document order.
+pub fn yaml_duplicate_keys(content: &str) -> Vec<DuplicateKey> {
+    yaml_scan(content, false).finish_duplicates(content)
+}
+
+fn toml_value(value: &toml_edit::Value, path: &mut Vec<PathSegment>, spans: &mut Spans) -> usize {
+    let range = value.span().unwrap_or_default();
+    let slot = spans.open(path, range.start);
+    match value {
+        toml_edit::Value::Array(array) => {
+            for (index, item) in array.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                toml_value(item, path, spans);
+                path.pop();
+            }
+        }
+        toml_edit::Value::InlineTable(table) => {
+            for (key, item) in table.iter() {
+                path.push(PathSegment::Key(key.to_string()));
+                toml_value(item, path, spans);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+    spans.close(slot, range.end);
+    range.end
+}
+
+/// Records a table and returns its span. Tables without a header of their own, like `a` in
+/// `[a.b]` or `a.b = 1`, span their contents.
+fn toml_table(
+    table: &toml_edit::Table,
+    path: &mut Vec<PathSegment>,
+    spans: &mut Spans,
+) -> Range<usize> {
+    let slot = spans.open(path, 0);
+    let mut range: Option<Range<usize>> = table.span();
+    let mut extend = |other: Range<usize>| {
+        range = Some(match range.take() {
+            Some(range) => range.start.min(other.start)..range.end.max(other.end),
+            None => other,
+        });
+    };
+    for (key, item) in table.iter() {
+        if let Some(span) = table.key(key).and_then(toml_edit::Key::span) {
+            if !item.is_table() && !item.is_array_of_tables() {
+                extend(span);
+            }
+        }
+        path.push(PathSegment::Key(key.to_string()));
+        match item {
+            toml_edit::Item::Value(value) => {
+                let start = value.span().unwrap_or_default().start;
+                extend(start..toml_value(value, path, spans));
+            }
+            toml_edit::Item::Table(table) => extend(toml_table(table, path, spans)),
+            toml_edit::Item::ArrayOfTables(tables) => {
+                let array_slot = spans.open(path, 0);
+                let mut array_range: Option<Range<usize>> = None;
+                for (index, table) in tables.iter().enumerate() {
+                    path.push(PathSegment::Index(index));
+                    let table_range = toml_table(table, path, spans);
+                    path.pop();
+                    array_range = Some(match array_range {
+                        Some(range) => range.start..table_range.end.max(range.end),
+                        None => table_range,
+                    });
+                }
+                let array_range = array_range.unwrap_or_default();
+                spans.set(array_slot, array_range.clone());
+                extend(array_range);
+            }
+            toml_edit::Item::None => {}
+        }
+        path.pop();
+    }
+    let range = range.unwrap_or_default();
+    spans.set(slot, range.clone());
+    range
+}
+
+/// Positions in TOML that has already parsed.
+pub fn toml_positions(content: &str) -> Vec<NodePosition> {
+    let Ok(document) = toml_edit::ImDocument::parse(content) else {
+        return Vec::new();
+    };
+    let mut spans = Spans::new(true);
+    toml_table(document.as_table(), &mut Vec::new(), &mut spans);
+    spans.finish(content)
+}
+
+#[cfg(test)]
+#[path = "positions_test.rs"]
+mod positions_test;
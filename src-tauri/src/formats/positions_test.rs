@@ -0,0 +1,225 @@
+use super::*;
+
+fn key(k: &str) -> PathSegment {
+    PathSegment::Key(k.to_string())
+}
+
+/// The source text of the node at `path`.
+fn text_at<'a>(content: &'a str, positions: &[NodePosition], path: &[PathSegment]) -> &'a str {
+    let node = positions
+        .iter()
+        .find(|node| node.path == path)
+        .unwrap_or_else(|| panic!("no position for {:?}", path));
+    &content[node.start.offset..node.end.offset]
+}
+
+#[test]
+fn test_json_positions() {
+    let content =
+        "{\n  \"name\": \"Ada\",\n  \"tags\": [1, true, null],\n  \"a/b\": {\"x\": -1.5e3}\n}\n";
+    let positions = json_positions(content);
+
+    assert_eq!(positions.len(), 8);
+    assert_eq!(positions[0].path, Vec::<PathSegment>::new());
+    assert_eq!(text_at(content, &positions, &[]), content.trim_end());
+    assert_eq!(text_at(content, &positions, &[key("name")]), "\"Ada\"");
+    assert_eq!(
+        text_at(content, &positions, &[key("tags")]),
+        "[1, true, null]"
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("tags"), PathSegment::Index(2)]),
+        "null"
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("a/b"), key("x")]),
+        "-1.5e3"
+    );
+
+    let name = positions.iter().find(|n| n.path == [key("name")]).unwrap();
+    assert_eq!((name.start.line, name.start.column), (2, 11));
+    assert_eq!((name.end.line, name.end.column), (2, 16));
+}
+
+#[test]
+fn test_json_positions_escaped_keys_and_unicode() {
+    let content = r#"{"café": "naïve", "q\"k": ["é", {}]}"#;
+    let positions = json_positions(content);
+    assert_eq!(text_at(content, &positions, &[key("café")]), "\"naïve\"");
+    let item = &positions
+        .iter()
+        .find(|n| n.path == [key("q\"k"), PathSegment::Index(1)])
+        .unwrap();
+    assert_eq!(&content[item.start.offset..item.end.offset], "{}");
+    assert_eq!(item.start.column, 33);
+}
+
+#[test]
+fn test_yaml_positions_block_and_flow() {
+    let content = "server:\n  host: example.com\n  ports: [80, 443]\nusers:\n  - name: Ada\n    admin: true\n  - Bob\n";
+    let positions = yaml_positions(content);
+
+    assert_eq!(
+        text_at(content, &positions, &[key("server"), key("host")]),
+        "example.com"
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("server"), key("ports")]),
+        "[80, 443]"
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("server")]),
+        "host: example.com\n  ports: [80, 443]"
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("users"), PathSegment::Index(1)]),
+        "Bob"
+    );
+    let admin = positions
+        .iter()
+        .find(|n| n.path == [key("users"), PathSegment::Index(0), key("admin")])
+        .unwrap();
+    assert_eq!((admin.start.line, admin.start.column), (6, 12));
+}
+
+#[test]
+fn test_yaml_positions_multiple_documents() {
+    let content = "a: 1\n---\nb: [x]\n";
+    let positions = yaml_positions(content);
+    assert_eq!(positions[0].path, Vec::<PathSegment>::new());
+    assert_eq!(
+        text_at(content, &positions, &[PathSegment::Index(0), key("a")]),
+        "1"
+    );
+    assert_eq!(
+        text_at(
+            content,
+            &positions,
+            &[PathSegment::Index(1), key("b"), PathSegment::Index(0)]
+        ),
+        "x"
+    );
+}
+
+#[test]
+fn test_yaml_document_starts() {
+    assert_eq!(yaml_document_starts("a: 1\n--- \nb: 2\n"), vec![0, 5]);
+    // A document after `...` needs no marker, and leading comments are not part of it.
+    assert_eq!(yaml_document_starts("a: 1\n...\nb: 2\n"), vec![0, 9]);
+    assert_eq!(yaml_document_starts("# c\n%YAML 1.2\n---\na\n"), vec![14]);
+}
+
+#[test]
+fn test_yaml_positions_unicode_and_block_scalars() {
+    let content = "café: \"naïve\"\nnote: |\n  first\n  second\n\nlast: é\n";
+    let positions = yaml_positions(content);
+    assert_eq!(text_at(content, &positions, &[key("café")]), "\"naïve\"");
+    assert_eq!(
+        text_at(content, &positions, &[key("note")]),
+        "first\n  second"
+    );
+    let last = positions.iter().find(|n| n.path == [key("last")]).unwrap();
+    assert_eq!(&content[last.start.offset..last.end.offset], "é");
+    assert_eq!((last.start.line, last.start.column), (6, 7));
+}
+
+#[test]
+fn test_toml_positions() {
+    let content = "title = \"demo\"\nport.http = 80\n\n[owner]\nname = \"Ada\"\n\n[[items]]\nid = 1\n\n[[items]]\nid = 2\ntags = [\"a\", { b = 2 }]\n";
+    let positions = toml_positions(content);
+
+    assert_eq!(text_at(content, &positions, &[key("title")]), "\"demo\"");
+    assert_eq!(
+        text_at(content, &positions, &[key("port"), key("http")]),
+        "80"
+    );
+    assert_eq!(text_at(content, &positions, &[key("port")]), "http = 80");
+    assert_eq!(
+        text_at(content, &positions, &[key("owner")]),
+        "[owner]\nname = \"Ada\""
+    );
+    assert_eq!(
+        text_at(content, &positions, &[key("items"), PathSegment::Index(1)]),
+        "[[items]]\nid = 2\ntags = [\"a\", { b = 2 }]"
+    );
+    assert_eq!(
+        text_at(
+            content,
+            &positions,
+            &[
+                key("items"),
+                PathSegment::Index(1),
+                key("tags"),
+                PathSegment::Index(1),
+                key("b")
+            ]
+        ),
+        "2"
+    );
+    let owner_name = positions
+        .iter()
+        .find(|n| n.path == [key("owner"), key("name")])
+        .unwrap();
+    assert_eq!(owner_name.start.line, 5);
+}
+
+#[test]
+fn test_positions_serialize_paths_as_graph_paths() {
+    let positions = json_positions(r#"{"a": [1]}"#);
+    assert_eq!(
+        serde_json::to_value(&positions[2]).unwrap(),
+        serde_json::json!({
+            "path": ["a", 0],
+            "start": {"line": 1, "column": 8, "offset": 7},
+            "end": {"line": 1, "column": 9, "offset": 8},
+        })
+    );
+}
+
+#[test]
+fn test_json_duplicate_keys() {
+    let content = "{\n  \"a\": 1,\n  \"b\": {\"x\": 1, \"x\": 2},\n  \"a\": 3,\n  \"a\": 4\n}";
+    let duplicates = json_duplicate_keys(content);
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(duplicates[0].path, [key("b"), key("x")]);
+    assert_eq!(duplicates[1].path, [key("a")]);
+    assert_eq!(
+        duplicates[1]
+            .locations
+            .iter()
+            .map(|l| (l.line, l.column))
+            .collect::<Vec<_>>(),
+        [(2, 3), (4, 3), (5, 3)]
+    );
+    assert_eq!(json_path(&duplicates[0].path), "$.b.x");
+
+    assert!(json_duplicate_keys(r#"[{"a": 1}, {"a": 2}]"#).is_empty());
+    // Escapes are compared by the key they spell.
+    assert_eq!(json_duplicate_keys(r#"{"a": 1, "\u0061": 2}"#).len(), 1);
+}
+
+#[test]
+fn test_yaml_duplicate_keys() {
+    let content = "name: a\nlist:\n  - id: 1\n    id: 2\nname: b\n---\nname: c\n";
+    let duplicates = yaml_duplicate_keys(content);
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(
+        duplicates[0].path,
+        [
+            PathSegment::Index(0),
+            key("list"),
+            PathSegment::Index(0),
+            key("id")
+        ]
+    );
+    assert_eq!(
+        duplicates[0]
+            .locations
+            .iter()
+            .map(|l| l.line)
+            .collect::<Vec<_>>(),
+        [3, 4]
+    );
+    assert_eq!(duplicates[1].path, [PathSegment::Index(0), key("name")]);
+    assert_eq!(json_path(&duplicates[0].path), "$[0].list[0].id");
+}
@@ -0,0 +1,182 @@
+//! Java `.properties` files, following `java.util.Properties.load`: `#` and `!` comments, `=`,
+//! `:` or whitespace between key and value, backslash line continuations and `\uXXXX` escapes.
+//!
+//! Keys are kept as written, so `server.port` stays a single key rather than nesting; that keeps
+//! the file's exact key set when converting back. When writing, nested values are flattened as
+//! `a.b` and `a[0]`.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use serde_json::{Map, Value};
+
+fn properties_error(content: &str, offset: usize, message: String) -> ParseError {
+    ParseError::new("properties", ParseErrorKind::Syntax, message).at_offset(content, offset)
+}
+
+/// A line ending in an odd number of backslashes continues on the next one.
+fn continues(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// Joins continued lines, returning each logical line with the offset it starts at.
+fn logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    let mut offset = 0;
+    for raw_line in content.split_inclusive('\n') {
+        let line_offset = offset;
+        offset += raw_line.len();
+        let line = raw_line.trim_end_matches(['\n', '\r']).trim_start();
+        let (start, mut text) = match current.take() {
+            Some(open) => open,
+            None if line.is_empty() || line.starts_with(['#', '!']) => continue,
+            None => (line_offset, String::new()),
+        };
+        if continues(line) {
+            text.push_str(&line[..line.len() - 1]);
+            current = Some((start, text));
+        } else {
+            text.push_str(line);
+            lines.push((start, text));
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+/// Resolves the escapes in `text`. Also returns the byte offset it stopped at, so the key can be
+/// cut at the first unescaped separator.
+fn unescape(text: &str, stop_at_separator: bool) -> Result<(String, usize), String> {
+    let mut out = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '=' | ':' | ' ' | '\t' | '\x0c' if stop_at_separator => return Ok((out, i)),
+            '\\' => match chars.next() {
+                Some((_, 't')) => out.push('\t'),
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 'r')) => out.push('\r'),
+                Some((_, 'f')) => out.push('\x0c'),
+                Some((_, 'u')) => {
+                    let hex: String = (0..4)
+                        .filter_map(|_| chars.next().map(|(_, c)| c))
+                        .collect();
+                    let code = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .filter(|_| hex.len() == 4)
+                        .ok_or_else(|| format!("Malformed \\u escape: \\u{}", hex))?;
+                    // Surrogate pairs arrive as two escapes.
+                    let code = if (0xd800..0xdc00).contains(&code) {
+                        let mut low = chars.clone();
+                        let pair = match (low.next(), low.next()) {
+                            (Some((_, '\\')), Some((_, 'u'))) => {
+                                let hex: String =
+                                    (0..4).filter_map(|_| low.next().map(|(_, c)| c)).collect();
+                                u32::from_str_radix(&hex, 16)
+                                    .ok()
+                                    .filter(|low| (0xdc00..0xe000).contains(low))
+                            }
+                            _ => None,
+                        };
+                        match pair {
+                            Some(low_code) => {
+                                chars = low;
+                                0x10000 + ((code - 0xd800) << 10) + (low_code - 0xdc00)
+                            }
+                            None => 0xfffd,
+                        }
+                    } else {
+                        code
+                    };
+                    out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                Some((_, other)) => out.push(other),
+                None => {}
+            },
+            c => out.push(c),
+        }
+    }
+    Ok((out, text.len()))
+}
+
+pub fn parse_properties(content: &str) -> Result<Value, ParseError> {
+    let mut map = Map::new();
+    for (offset, line) in logical_lines(content) {
+        let error = |message| properties_error(content, offset, message);
+        let (key, key_end) = unescape(&line, true).map_err(error)?;
+        let rest = line[key_end..].trim_start_matches([' ', '\t', '\x0c']);
+        let rest = rest
+            .strip_prefix(['=', ':'])
+            .map_or(rest, |rest| rest.trim_start_matches([' ', '\t', '\x0c']));
+        let (value, _) = unescape(rest, false).map_err(error)?;
+        map.insert(key, Value::String(value));
+    }
+    Ok(Value::Object(map))
+}
+
+fn flatten(value: &Value, prefix: String, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(item, key, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(item, format!("{}[{}]", prefix, i), out);
+            }
+        }
+        Value::Null => out.push((prefix, String::new())),
+        Value::String(s) => out.push((prefix, s.clone())),
+        other => out.push((prefix, other.to_string())),
+    }
+}
+
+fn escape(text: &str, is_key: bool) -> String {
+    let mut out = String::new();
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x0c' => out.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // Spaces separate the key, and lead a value only when escaped.
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            c if c.is_ascii() => out.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Writes one `key=value` line per leaf, escaping non-ASCII as `\uXXXX` so the file also reads
+/// correctly as ISO-8859-1, which older Java versions assume.
+pub fn to_properties(value: &Value) -> Result<String, String> {
+    if !value.is_object() {
+        return Err("Properties files need an object at the top level".to_string());
+    }
+    let mut entries = Vec::new();
+    flatten(value, String::new(), &mut entries);
+    Ok(entries
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", escape(key, true), escape(value, false)))
+        .collect())
+}
+
+#[cfg(test)]
+#[path = "properties_test.rs"]
+mod properties_test;
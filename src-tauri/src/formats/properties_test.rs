@@ -0,0 +1,66 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_parse_properties() {
+    let content = "\
+# comment
+! also a comment
+server.port=8080
+server.host : example.com
+greeting   Hello, world
+path=C:\\\\temp
+empty
+list = one, \\
+       two, \\
+       three
+key\\ with\\=separators = value
+unicode=caf\\u00e9 \\uD83D\\uDE00
+";
+    assert_eq!(
+        parse_properties(content).unwrap(),
+        json!({
+            "server.port": "8080",
+            "server.host": "example.com",
+            "greeting": "Hello, world",
+            "path": "C:\\temp",
+            "empty": "",
+            "list": "one, two, three",
+            "key with=separators": "value",
+            "unicode": "café 😀"
+        })
+    );
+}
+
+#[test]
+fn test_parse_properties_reports_bad_escapes() {
+    let error = parse_properties("a=1\nb=\\u12\n").unwrap_err();
+    assert_eq!(error.format, "properties");
+    assert_eq!(error.location.unwrap().line, 2);
+}
+
+#[test]
+fn test_to_properties() {
+    let value = json!({
+        "server": {"port": 8080, "hosts": ["a", "b"]},
+        "key with=sep": " leading space",
+        "name": "café",
+        "unset": null
+    });
+    let properties = to_properties(&value).unwrap();
+    assert_eq!(
+        properties,
+        "\
+server.port=8080
+server.hosts[0]=a
+server.hosts[1]=b
+key\\ with\\=sep=\\ leading space
+name=caf\\u00E9
+unset=
+"
+    );
+    let round_trip = parse_properties(&properties).unwrap();
+    assert_eq!(round_trip["key with=sep"], json!(" leading space"));
+    assert_eq!(round_trip["name"], json!("café"));
+    assert!(to_properties(&json!([1])).is_err());
+}
@@ -0,0 +1,349 @@
+//! Protocol Buffers. With a descriptor (a compiled `FileDescriptorSet` or `.proto` sources) and
+//! a message type, messages map to JSON with the canonical proto3 mapping: lowerCamelCase field
+//! names, 64-bit integers as strings, enums by name and well-known types such as `Timestamp` in
+//! their JSON form.
+//!
+//! Without a descriptor, the wire format is shown as it is: a list of
+//! `{"field": n, "wireType": "varint", "value": ...}` entries in the order they occur.
+//! Length-delimited values show as text when they are printable UTF-8, as a nested list when
+//! they parse as a message and as `{"$bytes": ...}` otherwise. The list encodes back to the
+//! same bytes.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use crate::formats::tagged;
+use base64::prelude::*;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use protobuf::Message as _;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Wire type names, indexed by their number in the encoding.
+const WIRE_TYPES: [&str; 6] = ["varint", "i64", "len", "sgroup", "egroup", "i32"];
+
+/// Nesting limit for messages and groups in the wire dump.
+const MAX_DEPTH: usize = 64;
+
+const MAX_FIELD: u64 = (1 << 29) - 1;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProtobufOptions {
+    /// Base64 of a compiled `FileDescriptorSet`, e.g. from `protoc --descriptor_set_out`.
+    pub descriptor_set: Option<String>,
+    /// `.proto` sources by file name, compiled in addition to `descriptor_set`. Imports of the
+    /// well-known types (`google/protobuf/*.proto`) need not be included.
+    pub proto_files: BTreeMap<String, String>,
+    /// Fully qualified message name, e.g. `my.package.Request`. Without it, the wire format is
+    /// shown instead.
+    pub message_type: Option<String>,
+}
+
+impl ProtobufOptions {
+    pub fn is_empty(&self) -> bool {
+        self.descriptor_set.is_none() && self.proto_files.is_empty() && self.message_type.is_none()
+    }
+}
+
+fn error(kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("protobuf", kind, message)
+}
+
+/// Compiles `.proto` sources into an encoded `FileDescriptorSet`. The parser reads from disk,
+/// so the sources go to a scratch directory for the duration.
+fn compile_protos(files: &BTreeMap<String, String>) -> Result<Vec<u8>, String> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "json-map-proto-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut inputs = Vec::new();
+        for (name, source) in files {
+            let relative = Path::new(name);
+            if !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(format!("Invalid .proto file name: {}", name));
+            }
+            let path = dir.join(relative);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, source).map_err(|e| e.to_string())?;
+            inputs.push(path);
+        }
+        protobuf_parse::Parser::new()
+            .pure()
+            .include(&dir)
+            .inputs(&inputs)
+            .file_descriptor_set()
+            .map_err(|e| format!("{:#}", e))?
+            .write_to_bytes()
+            .map_err(|e| e.to_string())
+    })();
+    let _ = std::fs::remove_dir_all(&dir);
+    // Keep the scratch directory out of error messages.
+    result.map_err(|e| e.replace(&format!("{}/", dir.display()), ""))
+}
+
+/// The well-known types plus every file from the descriptor set and `.proto` sources.
+pub fn descriptor_pool(options: &ProtobufOptions) -> Result<DescriptorPool, String> {
+    let mut pool = DescriptorPool::global();
+    if let Some(set) = &options.descriptor_set {
+        let bytes = BASE64_STANDARD
+            .decode(set.split_whitespace().collect::<String>())
+            .map_err(|e| format!("Descriptor set must be base64-encoded: {}", e))?;
+        pool.decode_file_descriptor_set(bytes.as_slice())
+            .map_err(|e| format!("Invalid descriptor set: {}", e))?;
+    }
+    if !options.proto_files.is_empty() {
+        let bytes = compile_protos(&options.proto_files)?;
+        pool.decode_file_descriptor_set(bytes.as_slice())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(pool)
+}
+
+fn message_descriptor(pool: &DescriptorPool, name: &str) -> Result<MessageDescriptor, String> {
+    let name = name.trim_start_matches('.');
+    pool.get_message_by_name(name)
+        .ok_or_else(|| format!("Unknown message type: {}", name))
+}
+
+/// Message types defined by the descriptor set and `.proto` sources, without map entries and
+/// the well-known types.
+pub fn message_types(options: &ProtobufOptions) -> Result<Vec<String>, String> {
+    let pool = descriptor_pool(options)?;
+    Ok(pool
+        .all_messages()
+        .filter(|message| !message.is_map_entry())
+        .map(|message| message.full_name().to_string())
+        .filter(|name| !name.starts_with("google.protobuf."))
+        .collect())
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn eof(&self) -> ParseError {
+        error(
+            ParseErrorKind::Eof,
+            format!("Unexpected end of data at byte {}", self.bytes.len()),
+        )
+    }
+
+    fn varint(&mut self) -> Result<u64, ParseError> {
+        let start = self.pos;
+        let mut value = 0u64;
+        for shift in (0..70).step_by(7) {
+            let byte = *self.bytes.get(self.pos).ok_or_else(|| self.eof())?;
+            self.pos += 1;
+            if shift == 63 && byte > 1 {
+                break;
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(error(
+            ParseErrorKind::Syntax,
+            format!("Varint longer than 64 bits at byte {}", start),
+        ))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| self.eof())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Reads fields until the end of the data, or until the end tag of `group`.
+    fn fields(&mut self, depth: usize, group: Option<u64>) -> Result<Vec<Value>, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(error(
+                ParseErrorKind::Data,
+                format!(
+                    "Nesting deeper than {} levels at byte {}",
+                    MAX_DEPTH, self.pos
+                ),
+            ));
+        }
+        let mut fields = Vec::new();
+        while self.pos < self.bytes.len() {
+            let start = self.pos;
+            let tag = self.varint()?;
+            let (field, wire_type) = (tag >> 3, (tag & 7) as usize);
+            if field == 0 || field > MAX_FIELD || wire_type >= WIRE_TYPES.len() {
+                return Err(error(
+                    ParseErrorKind::Syntax,
+                    format!("Invalid field tag {} at byte {}", tag, start),
+                ));
+            }
+            let value = match wire_type {
+                0 => Value::from(self.varint()?),
+                1 => Value::from(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+                2 => {
+                    let len = self.varint()? as usize;
+                    length_delimited(self.take(len)?, depth)
+                }
+                3 => Value::Array(self.fields(depth + 1, Some(field))?),
+                4 if group == Some(field) => return Ok(fields),
+                4 => {
+                    return Err(error(
+                        ParseErrorKind::Syntax,
+                        format!("Unmatched group end for field {} at byte {}", field, start),
+                    ))
+                }
+                _ => Value::from(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            };
+            fields
+                .push(json!({ "field": field, "wireType": WIRE_TYPES[wire_type], "value": value }));
+        }
+        match group {
+            Some(_) => Err(self.eof()),
+            None => Ok(fields),
+        }
+    }
+}
+
+fn length_delimited(bytes: &[u8], depth: usize) -> Value {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if !text
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+        {
+            return Value::String(text.to_string());
+        }
+    }
+    let mut nested = Decoder { bytes, pos: 0 };
+    match nested.fields(depth + 1, None) {
+        Ok(fields) => Value::Array(fields),
+        Err(_) => tagged::bytes(bytes),
+    }
+}
+
+/// Decodes a message without a schema; see the module documentation for the shape.
+pub fn decode_wire(bytes: &[u8]) -> Result<Value, ParseError> {
+    Decoder { bytes, pos: 0 }.fields(0, None).map(Value::Array)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn integer_bits(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_i64().map(|n| n as u64))
+}
+
+fn write_fields(out: &mut Vec<u8>, fields: &Value, path: &str) -> Result<(), String> {
+    let fields = fields
+        .as_array()
+        .ok_or_else(|| format!("Expected a list of fields at {}", path))?;
+    for (i, entry) in fields.iter().enumerate() {
+        let at = format!("{}[{}]", path, i);
+        let field = entry
+            .get("field")
+            .and_then(Value::as_u64)
+            .filter(|field| (1..=MAX_FIELD).contains(field))
+            .ok_or_else(|| format!("Missing or invalid field number at {}", at))?;
+        let wire_type = entry
+            .get("wireType")
+            .and_then(Value::as_str)
+            .and_then(|name| WIRE_TYPES.iter().position(|t| *t == name))
+            .filter(|wire_type| *wire_type != 4)
+            .ok_or_else(|| format!("Missing or invalid wire type at {}", at))?;
+        let value = entry.get("value").unwrap_or(&Value::Null);
+        let invalid = || format!("Invalid {} value at {}", WIRE_TYPES[wire_type], at);
+        write_varint(out, field << 3 | wire_type as u64);
+        match wire_type {
+            0 => write_varint(out, integer_bits(value).ok_or_else(invalid)?),
+            1 => {
+                let bits = integer_bits(value)
+                    .or_else(|| value.as_f64().map(f64::to_bits))
+                    .ok_or_else(invalid)?;
+                out.extend(bits.to_le_bytes());
+            }
+            2 => {
+                let bytes = match value {
+                    Value::String(s) => s.as_bytes().to_vec(),
+                    Value::Array(_) => {
+                        let mut nested = Vec::new();
+                        write_fields(&mut nested, value, &format!("{}.value", at))?;
+                        nested
+                    }
+                    other => tagged::as_bytes(other).ok_or_else(invalid)?,
+                };
+                write_varint(out, bytes.len() as u64);
+                out.extend(bytes);
+            }
+            3 => {
+                write_fields(out, value, &format!("{}.value", at))?;
+                write_varint(out, field << 3 | 4);
+            }
+            _ => {
+                let bits = integer_bits(value)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .or_else(|| value.as_f64().map(|f| (f as f32).to_bits()))
+                    .ok_or_else(invalid)?;
+                out.extend(bits.to_le_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encodes a wire dump produced by `decode_wire`.
+pub fn encode_wire(value: &Value) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    write_fields(&mut out, value, "$")?;
+    Ok(out)
+}
+
+pub fn parse_protobuf(bytes: &[u8], options: &ProtobufOptions) -> Result<Value, ParseError> {
+    let Some(message_type) = &options.message_type else {
+        return decode_wire(bytes);
+    };
+    let descriptor = descriptor_pool(options)
+        .and_then(|pool| message_descriptor(&pool, message_type))
+        .map_err(|e| error(ParseErrorKind::Data, e))?;
+    let message = DynamicMessage::decode(descriptor, bytes).map_err(|e| {
+        error(
+            ParseErrorKind::Syntax,
+            format!("Cannot decode as {}: {}", message_type, e),
+        )
+    })?;
+    serde_json::to_value(&message).map_err(|e| error(ParseErrorKind::Data, e.to_string()))
+}
+
+pub fn to_protobuf(value: &Value, options: &ProtobufOptions) -> Result<Vec<u8>, String> {
+    let Some(message_type) = &options.message_type else {
+        return encode_wire(value);
+    };
+    let descriptor = message_descriptor(&descriptor_pool(options)?, message_type)?;
+    let message = DynamicMessage::deserialize(descriptor, value.clone())
+        .map_err(|e| format!("Cannot encode as {}: {}", message_type, e))?;
+    Ok(message.encode_to_vec())
+}
+
+#[cfg(test)]
+#[path = "protobuf_test.rs"]
+mod protobuf_test;
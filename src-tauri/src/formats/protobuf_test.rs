@@ -0,0 +1,174 @@
+use super::*;
+use serde_json::json;
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+import "google/protobuf/timestamp.proto";
+
+message Person {
+  enum Kind {
+    KIND_UNKNOWN = 0;
+    KIND_ADMIN = 1;
+  }
+  string name = 1;
+  int64 id = 2;
+  repeated string tags = 3;
+  google.protobuf.Timestamp created = 4;
+  Kind kind = 5;
+  map<string, int32> scores = 6;
+  bytes avatar = 7;
+  Address home_address = 8;
+}
+
+message Address {
+  string city = 1;
+}
+"#;
+
+fn options() -> ProtobufOptions {
+    ProtobufOptions {
+        proto_files: BTreeMap::from([("demo/person.proto".to_string(), PROTO.to_string())]),
+        message_type: Some("demo.Person".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_protobuf_round_trip_with_proto_files() {
+    let value = json!({
+        "name": "Ann",
+        "id": "12345678901234567",
+        "tags": ["a", "b"],
+        "created": "2024-01-02T03:04:05Z",
+        "kind": "KIND_ADMIN",
+        "scores": {"x": 3},
+        "avatar": "AAE=",
+        "homeAddress": {"city": "Oslo"}
+    });
+    let bytes = to_protobuf(&value, &options()).unwrap();
+    assert_eq!(parse_protobuf(&bytes, &options()).unwrap(), value);
+
+    // Default values are left out, as in the canonical mapping.
+    let bytes = to_protobuf(&json!({"name": "", "kind": "KIND_UNKNOWN"}), &options()).unwrap();
+    assert!(bytes.is_empty());
+    assert_eq!(parse_protobuf(&bytes, &options()).unwrap(), json!({}));
+}
+
+#[test]
+fn test_protobuf_with_descriptor_set() {
+    let set = compile_protos(&options().proto_files).unwrap();
+    let options = ProtobufOptions {
+        descriptor_set: Some(BASE64_STANDARD.encode(set)),
+        message_type: Some(".demo.Address".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        parse_protobuf(&[0x0a, 0x04, b'O', b's', b'l', b'o'], &options).unwrap(),
+        json!({"city": "Oslo"})
+    );
+    assert_eq!(
+        message_types(&options).unwrap(),
+        vec!["demo.Person", "demo.Address"]
+    );
+}
+
+#[test]
+fn test_protobuf_schema_errors() {
+    let unknown = ProtobufOptions {
+        message_type: Some("demo.Missing".to_string()),
+        ..options()
+    };
+    let error = parse_protobuf(&[], &unknown).unwrap_err();
+    assert_eq!(error.message, "Unknown message type: demo.Missing");
+
+    let error = parse_protobuf(&[0x0a, 0x05], &options()).unwrap_err();
+    assert_eq!(error.format, "protobuf");
+    assert!(error.message.starts_with("Cannot decode as demo.Person"));
+
+    let error = to_protobuf(&json!({"nope": 1}), &options()).unwrap_err();
+    assert!(error.contains("nope"));
+
+    let broken = ProtobufOptions {
+        proto_files: BTreeMap::from([(
+            "bad.proto".to_string(),
+            "syntax = \"proto3\";\nmessage A {\n  strin x = 1;\n}\n".to_string(),
+        )]),
+        ..Default::default()
+    };
+    let error = message_types(&broken).unwrap_err();
+    assert!(error.contains("`bad.proto`"));
+    assert!(!error.contains("json-map-proto"));
+
+    let escaping = ProtobufOptions {
+        proto_files: BTreeMap::from([("../x.proto".to_string(), String::new())]),
+        ..Default::default()
+    };
+    assert!(message_types(&escaping).is_err());
+}
+
+#[test]
+fn test_decode_wire() {
+    let bytes = [
+        0x08, 0x96, 0x01, // 1: varint 150
+        0x12, 0x02, b'h', b'i', // 2: "hi"
+        0x1a, 0x03, 0x08, 0x01, 0x00, // 3: ends in 0x00, which is no valid tag
+    ];
+    assert_eq!(
+        decode_wire(&bytes).unwrap(),
+        json!([
+            {"field": 1, "wireType": "varint", "value": 150},
+            {"field": 2, "wireType": "len", "value": "hi"},
+            {"field": 3, "wireType": "len", "value": {"$bytes": "CAEA"}}
+        ])
+    );
+
+    let bytes = [
+        0x0a, 0x02, 0x08, 0x07, // 1: message {1: 7}
+        0x15, 0x01, 0x00, 0x00, 0x00, // 2: i32 1
+        0x19, 0x02, 0, 0, 0, 0, 0, 0, 0, // 3: i64 2
+        0x23, 0x08, 0x05, 0x24, // 4: group {1: 5}
+    ];
+    let dump = decode_wire(&bytes).unwrap();
+    assert_eq!(
+        dump,
+        json!([
+            {"field": 1, "wireType": "len", "value": [{"field": 1, "wireType": "varint", "value": 7}]},
+            {"field": 2, "wireType": "i32", "value": 1},
+            {"field": 3, "wireType": "i64", "value": 2},
+            {"field": 4, "wireType": "sgroup", "value": [{"field": 1, "wireType": "varint", "value": 5}]}
+        ])
+    );
+    assert_eq!(encode_wire(&dump).unwrap(), bytes);
+}
+
+#[test]
+fn test_decode_wire_errors() {
+    let error = decode_wire(&[0x12, 0x05, b'a']).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Eof);
+    let error = decode_wire(&[0x00]).unwrap_err();
+    assert_eq!(error.message, "Invalid field tag 0 at byte 0");
+    let error = decode_wire(&[0x0c]).unwrap_err();
+    assert_eq!(error.message, "Unmatched group end for field 1 at byte 0");
+}
+
+#[test]
+fn test_encode_wire() {
+    let dump = json!([
+        {"field": 1, "wireType": "varint", "value": -1},
+        {"field": 2, "wireType": "len", "value": {"$bytes": "AP8="}},
+        {"field": 3, "wireType": "i64", "value": 1.5}
+    ]);
+    let bytes = encode_wire(&dump).unwrap();
+    assert_eq!(
+        &bytes[..11],
+        &[0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+    );
+    assert_eq!(&bytes[11..15], &[0x12, 0x02, 0x00, 0xff]);
+    assert_eq!(&bytes[16..], &1.5f64.to_le_bytes());
+
+    let error = encode_wire(&json!([{"field": 1, "wireType": "nope"}])).unwrap_err();
+    assert_eq!(error, "Missing or invalid wire type at $[0]");
+    assert!(encode_wire(&json!({"field": 1})).is_err());
+}
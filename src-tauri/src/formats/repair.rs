@@ -0,0 +1,331 @@
+//! Best-effort repair of broken JSON, such as objects cut off mid-way in a log line or Python
+//! dict literals. The input is rewritten into valid JSON and every change is recorded with
+//! the position in the original text where it was made.
+
+use crate::commands::parser::SourceLocation;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Nesting depth past which repair gives up instead of risking the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Repair {
+    pub message: String,
+    #[serde(flatten)]
+    pub location: SourceLocation,
+}
+
+struct Repairer<'a> {
+    src: &'a str,
+    pos: usize,
+    out: String,
+    /// Closing brackets of the containers currently open, innermost last.
+    open: Vec<char>,
+    repairs: Vec<(usize, String)>,
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | ':' | '{' | '}' | '[' | ']' | '"' | '\'')
+}
+
+impl Repairer<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn fix(&mut self, offset: usize, message: impl Into<String>) {
+        self.repairs.push((offset, message.into()));
+    }
+
+    /// Skips whitespace and drops `//` and `/* */` comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            let start = self.pos;
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.pos += trimmed.find("*/").map_or(trimmed.len(), |i| i + 2);
+            } else {
+                return;
+            }
+            self.fix(start, "Removed comment");
+        }
+    }
+
+    /// Consumes the commas before the next item, writing exactly one when it follows another.
+    fn separators(&mut self, has_previous: bool) {
+        let mut commas = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(',') {
+                break;
+            }
+            commas.push(self.pos);
+            self.bump();
+        }
+        let closing = matches!(self.peek(), None | Some('}') | Some(']'));
+        let expected = usize::from(has_previous && !closing);
+        for &offset in commas.iter().skip(expected) {
+            self.fix(offset, "Removed extra comma");
+        }
+        if expected == 1 {
+            if commas.is_empty() {
+                let pos = self.pos;
+                self.fix(pos, "Inserted missing comma");
+            }
+            self.out.push(',');
+        }
+    }
+
+    /// Closes the innermost container if the input ends it. A bracket that belongs to an
+    /// outer container closes this one implicitly and is left for the outer one. Returns
+    /// `false` when the container is not closed yet.
+    fn close(&mut self) -> bool {
+        let pos = self.pos;
+        let close = *self.open.last().unwrap_or(&'}');
+        match self.peek() {
+            None => {
+                let kind = if close == '}' { "object" } else { "array" };
+                self.fix(pos, format!("Closed unterminated {}", kind));
+            }
+            Some(c) if c == close => {
+                self.bump();
+            }
+            Some(c @ ('}' | ']')) if self.open.contains(&c) => {
+                self.fix(pos, format!("Inserted missing '{}'", close));
+            }
+            Some(c @ ('}' | ']')) => {
+                self.bump();
+                self.fix(pos, format!("Replaced '{}' with '{}'", c, close));
+            }
+            Some(_) => return false,
+        }
+        self.open.pop();
+        self.out.push(close);
+        true
+    }
+
+    fn object(&mut self, depth: usize) -> Result<(), String> {
+        self.bump();
+        self.out.push('{');
+        self.open.push('}');
+        let mut has_previous = false;
+        loop {
+            self.separators(has_previous);
+            if self.close() {
+                return Ok(());
+            }
+            self.key()?;
+            self.skip_whitespace();
+            if self.peek() == Some(':') {
+                self.bump();
+            } else {
+                let pos = self.pos;
+                self.fix(pos, "Inserted missing colon");
+            }
+            self.out.push(':');
+            self.value(depth + 1)?;
+            has_previous = true;
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<(), String> {
+        self.bump();
+        self.out.push('[');
+        self.open.push(']');
+        let mut has_previous = false;
+        loop {
+            self.separators(has_previous);
+            if self.close() {
+                return Ok(());
+            }
+            self.value(depth + 1)?;
+            has_previous = true;
+        }
+    }
+
+    fn string(&mut self) {
+        let start = self.pos;
+        let quote = self.bump().unwrap_or('"');
+        if quote == '\'' {
+            self.fix(start, "Replaced single quotes with double quotes");
+        }
+        self.out.push('"');
+        loop {
+            let pos = self.pos;
+            match self.bump() {
+                None => {
+                    self.fix(pos, "Closed unterminated string");
+                    break;
+                }
+                Some(c) if c == quote => break,
+                Some('\\') => match self.bump() {
+                    Some('\'') => self.out.push('\''),
+                    Some(c) => {
+                        self.out.push('\\');
+                        self.out.push(c);
+                    }
+                    None => {
+                        self.fix(pos, "Closed unterminated string");
+                        break;
+                    }
+                },
+                Some('"') => self.out.push_str("\\\""),
+                Some(c) if c.is_control() => {
+                    self.fix(pos, "Escaped control character in string");
+                    let escaped = serde_json::to_string(&c.to_string()).unwrap_or_default();
+                    self.out.push_str(escaped.trim_matches('"'));
+                }
+                Some(c) => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn word(&mut self) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !is_delimiter(c)) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn key(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        match self.peek() {
+            Some('"' | '\'') => self.string(),
+            _ => {
+                let word = self.word().to_string();
+                if word.is_empty() {
+                    return Err(format!("Cannot repair an object key at byte {}", start));
+                }
+                self.fix(start, "Quoted key");
+                self.out.push_str(&Value::String(word).to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("Document is nested too deeply to repair".to_string());
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            None | Some(',' | '}' | ']') => {
+                self.fix(start, "Inserted missing value");
+                self.out.push_str("null");
+            }
+            Some('{') => self.object(depth)?,
+            Some('[') => self.array(depth)?,
+            Some('"' | '\'') => self.string(),
+            Some(_) => {
+                let word = self.word().to_string();
+                if word.is_empty() {
+                    return Err(format!("Cannot repair a value at byte {}", start));
+                }
+                let (text, message) = literal(&word, self.pos == self.src.len());
+                if let Some(message) = message {
+                    self.fix(start, message);
+                }
+                self.out.push_str(&text);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites a bare word in value position into a JSON scalar, with the fix it took.
+fn literal(word: &str, at_end: bool) -> (String, Option<String>) {
+    if serde_json::from_str::<serde_json::Number>(word).is_ok()
+        || matches!(word, "true" | "false" | "null")
+    {
+        return (word.to_string(), None);
+    }
+    let python = match word {
+        "True" => Some("true"),
+        "False" => Some("false"),
+        "None" => Some("null"),
+        _ => None,
+    };
+    if let Some(json) = python {
+        return (
+            json.to_string(),
+            Some(format!("Converted Python {} to {}", word, json)),
+        );
+    }
+    if matches!(word, "undefined" | "NaN" | "Infinity" | "-Infinity") {
+        return (
+            "null".to_string(),
+            Some(format!("Replaced {} with null", word)),
+        );
+    }
+    if at_end {
+        if let Some(full) = ["true", "false", "null"]
+            .into_iter()
+            .find(|full| full.starts_with(word))
+        {
+            return (
+                full.to_string(),
+                Some(format!("Completed truncated {}", full)),
+            );
+        }
+        let trimmed = word.trim_end_matches(['.', 'e', 'E', '+', '-']);
+        if serde_json::from_str::<serde_json::Number>(trimmed).is_ok() {
+            return (
+                trimmed.to_string(),
+                Some("Completed truncated number".to_string()),
+            );
+        }
+    }
+    (
+        Value::String(word.to_string()).to_string(),
+        Some("Quoted bare word".to_string()),
+    )
+}
+
+/// Repairs `content` and parses the result. Returns `None` when the text could not be turned
+/// into valid JSON.
+pub fn repair_json(content: &str) -> Option<(Value, Vec<Repair>)> {
+    let mut repairer = Repairer {
+        src: content,
+        pos: 0,
+        out: String::with_capacity(content.len()),
+        open: Vec::new(),
+        repairs: Vec::new(),
+    };
+    repairer.skip_whitespace();
+    repairer.peek()?;
+    repairer.value(0).ok()?;
+    repairer.skip_whitespace();
+    if repairer.pos < content.len() {
+        let pos = repairer.pos;
+        repairer.fix(pos, "Removed trailing text");
+    }
+
+    let value = serde_json::from_str(&repairer.out).ok()?;
+    let repairs = repairer
+        .repairs
+        .into_iter()
+        .map(|(offset, message)| Repair {
+            message,
+            location: SourceLocation::from_offset(content, offset),
+        })
+        .collect();
+    Some((value, repairs))
+}
+
+#[cfg(test)]
+#[path = "repair_test.rs"]
+mod repair_test;
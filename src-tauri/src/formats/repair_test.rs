@@ -0,0 +1,92 @@
+use super::*;
+use serde_json::json;
+
+fn repair(content: &str) -> (Value, Vec<String>) {
+    let (value, repairs) = repair_json(content).unwrap();
+    (value, repairs.into_iter().map(|r| r.message).collect())
+}
+
+#[test]
+fn test_closes_truncated_object() {
+    let (value, fixes) = repair(r#"{"user": {"name": "Ada", "tags": ["a", "b"#);
+    assert_eq!(value, json!({"user": {"name": "Ada", "tags": ["a", "b"]}}));
+    assert_eq!(
+        fixes,
+        vec![
+            "Closed unterminated string",
+            "Closed unterminated array",
+            "Closed unterminated object",
+            "Closed unterminated object"
+        ]
+    );
+}
+
+#[test]
+fn test_fills_missing_values() {
+    let (value, _) = repair(r#"{"a": 1, "b":"#);
+    assert_eq!(value, json!({"a": 1, "b": null}));
+    let (value, _) = repair(r#"{"a": 1, "b""#);
+    assert_eq!(value, json!({"a": 1, "b": null}));
+    let (value, fixes) = repair("[1, 2, tr");
+    assert_eq!(value, json!([1, 2, true]));
+    assert!(fixes.contains(&"Completed truncated true".to_string()));
+    let (value, _) = repair("[1, 2.");
+    assert_eq!(value, json!([1, 2]));
+}
+
+#[test]
+fn test_converts_python_literals_and_quotes() {
+    let (value, fixes) =
+        repair("{'ok': True, 'missing': None, 'msg': 'it\\'s \"fine\"', count: 3}");
+    assert_eq!(
+        value,
+        json!({"ok": true, "missing": null, "msg": "it's \"fine\"", "count": 3})
+    );
+    assert!(fixes.contains(&"Converted Python True to true".to_string()));
+    assert!(fixes.contains(&"Quoted key".to_string()));
+}
+
+#[test]
+fn test_fixes_commas_and_brackets() {
+    let (value, fixes) = repair("{\"a\": [1 2,, 3,], \"b\": {\"c\": 1}}");
+    assert_eq!(value, json!({"a": [1, 2, 3], "b": {"c": 1}}));
+    assert_eq!(
+        fixes,
+        vec![
+            "Inserted missing comma",
+            "Removed extra comma",
+            "Removed extra comma"
+        ]
+    );
+
+    let (value, fixes) = repair(r#"{"a": [1, 2}"#);
+    assert_eq!(value, json!({"a": [1, 2]}));
+    assert_eq!(fixes, vec!["Inserted missing ']'"]);
+
+    let (value, fixes) = repair("[1, 2}");
+    assert_eq!(value, json!([1, 2]));
+    assert_eq!(fixes, vec!["Replaced '}' with ']'"]);
+}
+
+#[test]
+fn test_strips_trailing_text_and_comments() {
+    let (value, fixes) = repair("// header\n{\"a\": 1} extra log text");
+    assert_eq!(value, json!({"a": 1}));
+    assert_eq!(fixes, vec!["Removed comment", "Removed trailing text"]);
+}
+
+#[test]
+fn test_reports_locations() {
+    let (_, repairs) = repair_json("{\n  \"a\": True\n").unwrap();
+    assert_eq!(repairs[0].message, "Converted Python True to true");
+    assert_eq!(repairs[0].location.line, 2);
+    assert_eq!(repairs[0].location.column, 8);
+    assert_eq!(repairs[1].location.line, 3);
+}
+
+#[test]
+fn test_gives_up_on_hopeless_input() {
+    assert!(repair_json("").is_none());
+    assert!(repair_json("{:}").is_none());
+    assert!(repair_json(&"[".repeat(1000)).is_none());
+}
@@ -0,0 +1,74 @@
+//! Values JSON has no type for, such as raw bytes or timestamps, are shown as single-key
+//! objects whose key starts with `$`. Every binary format maps its native types to these
+//! same shapes, so they look alike in the tree and convert between formats:
+//!
+//! - `{"$bytes": "<base64>"}` for binary data
+//! - `{"$date": "<RFC 3339>"}` for timestamps
+
+use base64::prelude::*;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+pub const BYTES: &str = "$bytes";
+pub const DATE: &str = "$date";
+
+/// Builds a single-key tagged object.
+pub fn tagged(key: &str, value: Value) -> Value {
+    Value::Object(Map::from_iter([(key.to_string(), value)]))
+}
+
+/// Returns the content of a single-key object tagged with `key`.
+pub fn untag<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.get(key),
+        _ => None,
+    }
+}
+
+pub fn bytes(data: &[u8]) -> Value {
+    tagged(BYTES, Value::String(BASE64_STANDARD.encode(data)))
+}
+
+pub fn as_bytes(value: &Value) -> Option<Vec<u8>> {
+    BASE64_STANDARD.decode(untag(value, BYTES)?.as_str()?).ok()
+}
+
+pub fn date(datetime: DateTime<Utc>) -> Value {
+    tagged(
+        DATE,
+        Value::String(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+    )
+}
+
+pub fn as_date(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(untag(value, DATE)?.as_str()?)
+        .ok()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// JSON numbers cannot be NaN or infinite, so those floats become strings.
+pub fn float(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| {
+            Value::String(if f.is_nan() {
+                "NaN".to_string()
+            } else if f > 0.0 {
+                "Infinity".to_string()
+            } else {
+                "-Infinity".to_string()
+            })
+        })
+}
+
+/// Binary formats allow any value as a map key; JSON keys are strings.
+pub fn key_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[path = "tagged_test.rs"]
+mod tagged_test;
@@ -0,0 +1,31 @@
+use super::*;
+use chrono::TimeZone;
+use serde_json::json;
+
+#[test]
+fn test_bytes_round_trip_through_base64() {
+    let value = bytes(&[0, 1, 254, 255]);
+    assert_eq!(value, json!({"$bytes": "AAH+/w=="}));
+    assert_eq!(as_bytes(&value), Some(vec![0, 1, 254, 255]));
+}
+
+#[test]
+fn test_date_keeps_subsecond_precision() {
+    let datetime = Utc.timestamp_opt(1_700_000_000, 123_000_000).unwrap();
+    let value = date(datetime);
+    assert_eq!(value, json!({"$date": "2023-11-14T22:13:20.123Z"}));
+    assert_eq!(as_date(&value), Some(datetime));
+}
+
+#[test]
+fn test_objects_with_other_keys_are_not_tagged() {
+    assert_eq!(as_bytes(&json!({"$bytes": "AA==", "extra": 1})), None);
+    assert_eq!(as_date(&json!({"$date": "yesterday"})), None);
+}
+
+#[test]
+fn test_non_finite_floats_become_strings() {
+    assert_eq!(float(1.5), json!(1.5));
+    assert_eq!(float(f64::NAN), json!("NaN"));
+    assert_eq!(float(f64::NEG_INFINITY), json!("-Infinity"));
+}
@@ -0,0 +1,539 @@
+//! Maps XML to JSON and back without losing attributes, CDATA, namespaces or mixed content.
+//!
+//! The convention:
+//!
+//! - The document is an object with a single key, the root element name.
+//! - Element names keep their namespace prefix (`"soap:Envelope"`), and namespace declarations
+//!   are ordinary attributes (`"@xmlns:soap"`).
+//! - Attributes are keys prefixed with `@`; their values are always strings.
+//! - An element with only text becomes that string, whitespace included, and an empty element
+//!   becomes `null`. Next to attributes or children, text goes under `#text` and CDATA under
+//!   `#cdata`.
+//! - Sibling elements with the same name become an array, as does any element listed in
+//!   `forceArrays`, so repeated elements keep a stable shape.
+//! - Comments go under `#comment` and processing instructions under `#pi`, as a string or an
+//!   array of strings when there are several. Outside the root element they sit next to it.
+//! - Text interleaved with elements (mixed content), and siblings whose names interleave, keep
+//!   their order in a `#mixed` array of strings, single-key `{"#cdata": ...}`,
+//!   `{"#comment": ...}` and `{"#pi": ...}` objects, and single-key element objects.
+//!
+//! The doctype is not kept.
+
+use crate::commands::parser::{ParseError, ParseErrorKind};
+use quick_xml::escape::{escape, partial_escape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt::Write;
+
+const TEXT_KEY: &str = "#text";
+const CDATA_KEY: &str = "#cdata";
+const MIXED_KEY: &str = "#mixed";
+const COMMENT_KEY: &str = "#comment";
+const PI_KEY: &str = "#pi";
+
+/// Name of the root element written when a value does not have exactly one top-level key.
+const DEFAULT_ROOT: &str = "root";
+
+/// Name of the elements written for items of a nested array.
+const ITEM_ELEMENT: &str = "item";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlOptions {
+    /// Element names that always map to arrays, even when they occur once.
+    pub force_arrays: Vec<String>,
+}
+
+enum Content {
+    Element(String, Value),
+    Text(String),
+    CData(String),
+    Comment(String),
+    Pi(String),
+}
+
+struct Node {
+    name: String,
+    attributes: Map<String, Value>,
+    content: Vec<Content>,
+}
+
+fn xml_error(content: &str, offset: usize, kind: ParseErrorKind, message: String) -> ParseError {
+    ParseError::new("xml", kind, message).at_offset(content, offset)
+}
+
+fn open_node(
+    content: &str,
+    reader: &Reader<&[u8]>,
+    start: &BytesStart,
+) -> Result<Node, ParseError> {
+    let syntax_error = |message: String| {
+        xml_error(
+            content,
+            reader.buffer_position(),
+            ParseErrorKind::Syntax,
+            message,
+        )
+    };
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+    let mut attributes = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| syntax_error(e.to_string()))?;
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| syntax_error(e.to_string()))?;
+        attributes.insert(
+            format!("@{}", String::from_utf8_lossy(attribute.key.as_ref())),
+            Value::String(value.into_owned()),
+        );
+    }
+    Ok(Node {
+        name,
+        attributes,
+        content: Vec::new(),
+    })
+}
+
+fn is_blank(text: &str) -> bool {
+    text.trim().is_empty()
+}
+
+/// Whether a name comes back after a differently named sibling, as in `<a/><b/><a/>`.
+fn is_interleaved(content: &[Content]) -> bool {
+    let mut seen: Vec<&str> = Vec::new();
+    for c in content {
+        if let Content::Element(name, _) = c {
+            match seen.last() {
+                Some(last) if last == name => {}
+                _ if seen.contains(&name.as_str()) => return true,
+                _ => seen.push(name),
+            }
+        }
+    }
+    false
+}
+
+fn single(key: &str, value: String) -> Value {
+    Value::Object(Map::from_iter([(key.to_string(), Value::String(value))]))
+}
+
+/// Adds a comment or processing instruction under `key`, turning it into an array once there
+/// are several.
+fn push_markup(map: &mut Map<String, Value>, key: &str, text: String) {
+    match map.get_mut(key) {
+        Some(Value::Array(items)) => items.push(Value::String(text)),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, Value::String(text)]);
+        }
+        None => {
+            map.insert(key.to_string(), Value::String(text));
+        }
+    }
+}
+
+fn close_node(node: Node, options: &XmlOptions) -> Value {
+    let Node {
+        attributes,
+        content,
+        ..
+    } = node;
+    let has_elements = content.iter().any(|c| matches!(c, Content::Element(..)));
+    let has_text = content
+        .iter()
+        .any(|c| matches!(c, Content::Text(t) if !is_blank(t)));
+    let has_cdata = content.iter().any(|c| matches!(c, Content::CData(_)));
+
+    let mixed_text = (has_elements && (has_text || has_cdata)) || (has_text && has_cdata);
+    if mixed_text || is_interleaved(&content) {
+        let mixed = content
+            .into_iter()
+            .filter_map(|c| match c {
+                Content::Element(name, value) => {
+                    Some(Value::Object(Map::from_iter([(name, value)])))
+                }
+                // Indentation between interleaved elements is not content.
+                Content::Text(text) if !mixed_text && is_blank(&text) => None,
+                Content::Text(text) => Some(Value::String(text)),
+                Content::CData(text) => Some(single(CDATA_KEY, text)),
+                Content::Comment(text) => Some(single(COMMENT_KEY, text)),
+                Content::Pi(text) => Some(single(PI_KEY, text)),
+            })
+            .collect();
+        let mut map = attributes;
+        map.insert(MIXED_KEY.to_string(), Value::Array(mixed));
+        return Value::Object(map);
+    }
+
+    // Whitespace is kept when it is all the element holds.
+    let has_text =
+        has_text || (!content.is_empty() && content.iter().all(|c| matches!(c, Content::Text(_))));
+    let mut map = attributes;
+    let mut text = String::new();
+    for c in content {
+        match c {
+            Content::Element(name, value) => match map.get_mut(&name) {
+                Some(Value::Array(items)) => items.push(value),
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = Value::Array(vec![first, value]);
+                }
+                None if options.force_arrays.contains(&name) => {
+                    map.insert(name, Value::Array(vec![value]));
+                }
+                None => {
+                    map.insert(name, value);
+                }
+            },
+            Content::Text(t) | Content::CData(t) => text.push_str(&t),
+            Content::Comment(t) => push_markup(&mut map, COMMENT_KEY, t),
+            Content::Pi(t) => push_markup(&mut map, PI_KEY, t),
+        }
+    }
+    if has_elements {
+        return Value::Object(map);
+    }
+
+    let key = if has_cdata { CDATA_KEY } else { TEXT_KEY };
+    match (map.is_empty(), has_text || has_cdata) {
+        (true, false) => Value::Null,
+        (true, true) if !has_cdata => Value::String(text),
+        (_, true) => {
+            map.insert(key.to_string(), Value::String(text));
+            Value::Object(map)
+        }
+        (false, false) => Value::Object(map),
+    }
+}
+
+/// Parses an XML document into the JSON mapping described in the module docs.
+pub fn parse_xml(content: &str, options: &XmlOptions) -> Result<Value, ParseError> {
+    let mut reader = Reader::from_str(content);
+    let mut stack: Vec<Node> = Vec::new();
+    let mut root: Option<(String, Value)> = None;
+    // Comments and processing instructions outside the root element.
+    let mut document = Map::new();
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            xml_error(
+                content,
+                reader.buffer_position(),
+                ParseErrorKind::Syntax,
+                e.to_string(),
+            )
+        })?;
+        let position = reader.buffer_position();
+        let closed = match event {
+            Event::Start(start) => {
+                stack.push(open_node(content, &reader, &start)?);
+                None
+            }
+            Event::Empty(start) => {
+                let node = open_node(content, &reader, &start)?;
+                Some((node.name.clone(), close_node(node, options)))
+            }
+            Event::End(_) => stack
+                .pop()
+                .map(|node| (node.name.clone(), close_node(node, options))),
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| {
+                    xml_error(content, position, ParseErrorKind::Syntax, e.to_string())
+                })?;
+                match stack.last_mut() {
+                    Some(node) => node.content.push(Content::Text(text.into_owned())),
+                    None if is_blank(&text) => {}
+                    None => {
+                        return Err(xml_error(
+                            content,
+                            position,
+                            ParseErrorKind::Syntax,
+                            "Text outside of the root element".to_string(),
+                        ))
+                    }
+                }
+                None
+            }
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                if let Some(node) = stack.last_mut() {
+                    node.content.push(Content::CData(text));
+                }
+                None
+            }
+            Event::Comment(text) => {
+                let text = String::from_utf8_lossy(&text.into_inner()).into_owned();
+                match stack.last_mut() {
+                    Some(node) => node.content.push(Content::Comment(text)),
+                    None => push_markup(&mut document, COMMENT_KEY, text),
+                }
+                None
+            }
+            Event::PI(text) => {
+                let text = String::from_utf8_lossy(&text.into_inner()).into_owned();
+                match stack.last_mut() {
+                    Some(node) => node.content.push(Content::Pi(text)),
+                    None => push_markup(&mut document, PI_KEY, text),
+                }
+                None
+            }
+            Event::Eof => break,
+            // The declaration is written back on output; doctypes are not kept.
+            _ => None,
+        };
+
+        if let Some((name, value)) = closed {
+            match stack.last_mut() {
+                Some(parent) => parent.content.push(Content::Element(name, value)),
+                None if root.is_some() => {
+                    return Err(xml_error(
+                        content,
+                        position,
+                        ParseErrorKind::Syntax,
+                        "Document has more than one root element".to_string(),
+                    ))
+                }
+                None => root = Some((name, value)),
+            }
+        }
+    }
+
+    if let Some(node) = stack.last() {
+        return Err(xml_error(
+            content,
+            content.len(),
+            ParseErrorKind::Eof,
+            format!("Unclosed element <{}>", node.name),
+        ));
+    }
+    match root {
+        Some((name, value)) => {
+            document.insert(name, value);
+            Ok(Value::Object(document))
+        }
+        None => Err(xml_error(
+            content,
+            content.len(),
+            ParseErrorKind::Eof,
+            "Document has no root element".to_string(),
+        )),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        // Numbers keep their original digits since XML has no numeric type to round them to.
+        other => other.to_string(),
+    }
+}
+
+fn write_cdata(out: &mut String, text: &str) {
+    out.push_str("<![CDATA[");
+    out.push_str(&text.replace("]]>", "]]]]><![CDATA[>"));
+    out.push_str("]]>");
+}
+
+fn start_line(out: &mut String, indent: Option<usize>) {
+    if let Some(depth) = indent {
+        out.push_str(&"  ".repeat(depth));
+    }
+}
+
+fn end_line(out: &mut String, indent: Option<usize>) {
+    if indent.is_some() {
+        out.push('\n');
+    }
+}
+
+/// Writes `value` as one element, or one element per item for arrays. `indent` is the depth
+/// for pretty output, or `None` inside mixed content where whitespace is significant.
+fn write_element(
+    out: &mut String,
+    name: &str,
+    value: &Value,
+    indent: Option<usize>,
+) -> Result<(), String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .try_for_each(|item| write_single(out, name, item, indent)),
+        _ => write_single(out, name, value, indent),
+    }
+}
+
+fn write_single(
+    out: &mut String,
+    name: &str,
+    value: &Value,
+    indent: Option<usize>,
+) -> Result<(), String> {
+    if !is_valid_name(name) {
+        return Err(format!("'{}' is not a valid XML element name", name));
+    }
+    let child_indent = indent.map(|depth| depth + 1);
+    start_line(out, indent);
+    let _ = write!(out, "<{}", name);
+
+    match value {
+        Value::Null => out.push_str("/>"),
+        Value::Array(items) => {
+            out.push('>');
+            end_line(out, indent);
+            for item in items {
+                write_element(out, ITEM_ELEMENT, item, child_indent)?;
+            }
+            start_line(out, indent);
+            let _ = write!(out, "</{}>", name);
+        }
+        Value::Object(map) => {
+            let mut children = Vec::new();
+            for (key, item) in map {
+                match key.strip_prefix('@') {
+                    Some(attribute) => {
+                        if !is_valid_name(attribute) {
+                            return Err(format!(
+                                "'{}' is not a valid XML attribute name",
+                                attribute
+                            ));
+                        }
+                        let _ = write!(out, " {}=\"{}\"", attribute, escape(&scalar_text(item)));
+                    }
+                    None => children.push((key.as_str(), item)),
+                }
+            }
+            if children.is_empty() {
+                out.push_str("/>");
+                end_line(out, indent);
+                return Ok(());
+            }
+            out.push('>');
+
+            let inline = children.iter().all(|(key, _)| key.starts_with('#'));
+            for (key, item) in &children {
+                match *key {
+                    TEXT_KEY => out.push_str(&partial_escape(&scalar_text(item))),
+                    CDATA_KEY => write_cdata(out, &scalar_text(item)),
+                    MIXED_KEY => write_mixed(out, item)?,
+                    COMMENT_KEY | PI_KEY if inline => write_markup(out, key, item, None)?,
+                    _ => {}
+                }
+            }
+            if !inline {
+                end_line(out, indent);
+                for (key, item) in &children {
+                    if matches!(*key, COMMENT_KEY | PI_KEY) {
+                        write_markup(out, key, item, child_indent)?;
+                    }
+                }
+                for (key, item) in children.iter().filter(|(key, _)| !key.starts_with('#')) {
+                    write_element(out, key, item, child_indent)?;
+                }
+                start_line(out, indent);
+            }
+            let _ = write!(out, "</{}>", name);
+        }
+        scalar => {
+            let _ = write!(out, ">{}</{}>", partial_escape(&scalar_text(scalar)), name);
+        }
+    }
+    end_line(out, indent);
+    Ok(())
+}
+
+/// Writes `#comment` or `#pi` values, one per line for pretty output.
+fn write_markup(
+    out: &mut String,
+    key: &str,
+    value: &Value,
+    indent: Option<usize>,
+) -> Result<(), String> {
+    let items = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    for item in items {
+        let text = scalar_text(item);
+        start_line(out, indent);
+        if key == COMMENT_KEY {
+            if text.contains("--") || text.ends_with('-') {
+                return Err(format!("Comment '{}' cannot contain '--'", text));
+            }
+            let _ = write!(out, "<!--{}-->", text);
+        } else {
+            let target = text.split_whitespace().next().unwrap_or_default();
+            if text.contains("?>") || !is_valid_name(target) || target.eq_ignore_ascii_case("xml") {
+                return Err(format!("'{}' is not a valid processing instruction", text));
+            }
+            let _ = write!(out, "<?{}?>", text);
+        }
+        end_line(out, indent);
+    }
+    Ok(())
+}
+
+fn write_mixed(out: &mut String, value: &Value) -> Result<(), String> {
+    let items = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    for item in items {
+        match item {
+            Value::Object(map) if map.len() == 1 => {
+                let (key, child) = map.iter().next().unwrap();
+                match key.as_str() {
+                    CDATA_KEY => write_cdata(out, &scalar_text(child)),
+                    COMMENT_KEY | PI_KEY => write_markup(out, key, child, None)?,
+                    _ => write_element(out, key, child, None)?,
+                }
+            }
+            Value::Object(_) | Value::Array(_) => {
+                return Err(format!(
+                    "{} items must be text or objects with a single element name",
+                    MIXED_KEY
+                ))
+            }
+            scalar => out.push_str(&partial_escape(&scalar_text(scalar))),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a value back to XML using the mapping described in the module docs. Values that are
+/// not an object with a single element key, next to optional `#comment` and `#pi` keys, are
+/// wrapped in a `<root>` element.
+pub fn to_xml(value: &Value) -> Result<String, String> {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let is_markup = |key: &str| matches!(key, COMMENT_KEY | PI_KEY);
+    match value {
+        Value::Object(map)
+            if map.keys().filter(|key| !is_markup(key)).count() == 1
+                && map.iter().all(|(key, item)| {
+                    is_markup(key) || (!key.starts_with(['@', '#']) && !item.is_array())
+                }) =>
+        {
+            for (key, item) in map.iter().filter(|(key, _)| is_markup(key)) {
+                write_markup(&mut out, key, item, Some(0))?;
+            }
+            let (name, root) = map.iter().find(|(key, _)| !is_markup(key)).unwrap();
+            write_element(&mut out, name, root, Some(0))?;
+        }
+        _ => write_single(&mut out, DEFAULT_ROOT, value, Some(0))?,
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "xml_test.rs"]
+mod xml_test;
@@ -0,0 +1,183 @@
+use super::*;
+use serde_json::json;
+
+fn parse(content: &str) -> Value {
+    parse_xml(content, &XmlOptions::default()).unwrap()
+}
+
+#[test]
+fn test_maps_attributes_text_and_repeats() {
+    let value = parse(
+        r#"<?xml version="1.0"?>
+<catalog version="2">
+  <!-- comment -->
+  <book id="1" lang="en">Dune</book>
+  <book id="2">Emma</book>
+  <empty/>
+  <title>Books &amp; more</title>
+</catalog>"#,
+    );
+    assert_eq!(
+        value,
+        json!({
+            "catalog": {
+                "@version": "2",
+                "#comment": " comment ",
+                "book": [
+                    {"@id": "1", "@lang": "en", "#text": "Dune"},
+                    {"@id": "2", "#text": "Emma"}
+                ],
+                "empty": null,
+                "title": "Books & more"
+            }
+        })
+    );
+}
+
+#[test]
+fn test_keeps_namespaces_and_cdata() {
+    let value = parse(
+        r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><script><![CDATA[if (a < b) {}]]></script></soap:Body></soap:Envelope>"#,
+    );
+    assert_eq!(
+        value,
+        json!({
+            "soap:Envelope": {
+                "@xmlns:soap": "http://www.w3.org/2003/05/soap-envelope",
+                "soap:Body": {"script": {"#cdata": "if (a < b) {}"}}
+            }
+        })
+    );
+}
+
+#[test]
+fn test_mixed_content_keeps_order() {
+    let value = parse("<p>Hello <b>big</b> world</p>");
+    assert_eq!(
+        value,
+        json!({"p": {"#mixed": ["Hello ", {"b": "big"}, " world"]}})
+    );
+}
+
+#[test]
+fn test_interleaved_siblings_keep_order() {
+    let content = "<r><a>1</a><b>2</b><a>3</a></r>";
+    let value = parse(content);
+    assert_eq!(
+        value,
+        json!({"r": {"#mixed": [{"a": "1"}, {"b": "2"}, {"a": "3"}]}})
+    );
+    assert!(to_xml(&value).unwrap().ends_with(&format!("{}\n", content)));
+
+    // Grouped siblings still map to keys.
+    assert_eq!(
+        parse("<r>\n  <a>1</a>\n  <a>3</a>\n  <b>2</b>\n</r>"),
+        json!({"r": {"a": ["1", "3"], "b": "2"}})
+    );
+}
+
+#[test]
+fn test_whitespace_only_text_is_kept() {
+    let value = parse("<s> </s>");
+    assert_eq!(value, json!({"s": " "}));
+    assert_eq!(parse(&to_xml(&value).unwrap()), value);
+    assert_eq!(parse("<s></s>"), json!({"s": null}));
+    assert_eq!(parse("<r>\n  <s/>\n</r>"), json!({"r": {"s": null}}));
+}
+
+#[test]
+fn test_keeps_comments_and_processing_instructions() {
+    let content = r#"<?xml version="1.0"?>
+<!-- generated -->
+<?xml-stylesheet href="style.css"?>
+<config>
+  <!-- first -->
+  <!-- second -->
+  <?render fast?>
+  <name>app</name>
+  <p>Hi <!-- inline --><b>there</b></p>
+</config>"#;
+    let value = parse(content);
+    assert_eq!(
+        value,
+        json!({
+            "#comment": " generated ",
+            "#pi": "xml-stylesheet href=\"style.css\"",
+            "config": {
+                "#comment": [" first ", " second "],
+                "#pi": "render fast",
+                "name": "app",
+                "p": {"#mixed": ["Hi ", {"#comment": " inline "}, {"b": "there"}]}
+            }
+        })
+    );
+    assert_eq!(parse(&to_xml(&value).unwrap()), value);
+    assert!(to_xml(&json!({"a": {"#comment": "x -- y"}})).is_err());
+    assert!(to_xml(&json!({"a": {"#pi": "xml version"}})).is_err());
+}
+
+#[test]
+fn test_force_arrays() {
+    let options = XmlOptions {
+        force_arrays: vec!["item".to_string()],
+    };
+    let value = parse_xml("<list><item>a</item></list>", &options).unwrap();
+    assert_eq!(value, json!({"list": {"item": ["a"]}}));
+}
+
+#[test]
+fn test_round_trip() {
+    let content = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:media="http://search.yahoo.com/mrss/">
+  <title type="text">News &lt;daily&gt;</title>
+  <entry id="1">
+    <media:thumbnail url="a.png"/>
+    <summary>Read <a href="x?a=1&amp;b=2">this</a> now</summary>
+    <code><![CDATA[x ]]]]><![CDATA[> y]]></code>
+  </entry>
+  <entry id="2"/>
+</feed>"#;
+    let value = parse(content);
+    let written = to_xml(&value).unwrap();
+    assert_eq!(parse(&written), value);
+    assert_eq!(value["feed"]["entry"][0]["code"]["#cdata"], "x ]]> y");
+}
+
+#[test]
+fn test_writes_pretty_xml() {
+    let value = json!({"root": {"@id": "1", "name": "a & b", "tags": ["x", "y"], "none": null}});
+    assert_eq!(
+        to_xml(&value).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root id=\"1\">\n  <name>a &amp; b</name>\n  <tags>x</tags>\n  <tags>y</tags>\n  <none/>\n</root>\n"
+    );
+}
+
+#[test]
+fn test_wraps_values_without_single_root() {
+    let value: Value =
+        serde_json::from_str(r#"{"a": 1, "b": [true, 12345678901234567890123]}"#).unwrap();
+    let written = to_xml(&value).unwrap();
+    assert_eq!(
+        parse(&written),
+        json!({"root": {"a": "1", "b": ["true", "12345678901234567890123"]}})
+    );
+    let written = to_xml(&json!([1, 2])).unwrap();
+    assert_eq!(parse(&written), json!({"root": {"item": ["1", "2"]}}));
+}
+
+#[test]
+fn test_rejects_invalid_names() {
+    assert!(to_xml(&json!({"root": {"a b": 1}})).is_err());
+}
+
+#[test]
+fn test_errors() {
+    let err = parse_xml("<a><b></a>", &XmlOptions::default()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Syntax);
+
+    let err = parse_xml("<a>\n<b>", &XmlOptions::default()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Eof);
+    assert_eq!(err.location.unwrap().line, 2);
+
+    let err = parse_xml("<a/><b/>", &XmlOptions::default()).unwrap_err();
+    assert!(err.message.contains("more than one root"));
+}
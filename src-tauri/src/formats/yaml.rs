@@ -0,0 +1,58 @@
+use crate::commands::parser::{yaml_error, ParseError, SourceLocation};
+use crate::formats::numbers::Portable;
+use crate::formats::positions;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Where one document of a multi-document YAML stream starts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentInfo {
+    pub index: usize,
+    pub line: usize,
+    pub offset: usize,
+}
+
+/// Parses a YAML stream. A single document comes back as-is; several `---`-separated documents
+/// come back as an array together with where each one starts.
+pub fn parse_yaml(content: &str) -> Result<(Value, Option<Vec<DocumentInfo>>), ParseError> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        // The stream keeps yielding the same error once it fails, so stop at the first one.
+        documents.push(Value::deserialize(document).map_err(|e| yaml_error(content, e))?);
+    }
+
+    if documents.len() <= 1 {
+        return Ok((documents.pop().unwrap_or(Value::Null), None));
+    }
+
+    let info = positions::yaml_document_starts(content)
+        .into_iter()
+        .take(documents.len())
+        .enumerate()
+        .map(|(index, offset)| DocumentInfo {
+            index,
+            line: SourceLocation::from_offset(content, offset).line,
+            offset,
+        })
+        .collect();
+    Ok((Value::Array(documents), Some(info)))
+}
+
+/// Writes every element of an array as its own `---`-separated document.
+pub fn to_multi_document_yaml(value: &Value) -> Result<String, String> {
+    let documents = match value {
+        Value::Array(arr) => arr.iter().collect(),
+        _ => vec![value],
+    };
+
+    let mut out = String::new();
+    for document in documents {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml::to_string(&Portable(document)).map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "yaml_test.rs"]
+mod yaml_test;
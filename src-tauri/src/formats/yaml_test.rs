@@ -0,0 +1,57 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn test_parse_single_document() {
+    let (value, documents) = parse_yaml("---\nname: test\n").unwrap();
+    assert_eq!(value, json!({"name": "test"}));
+    assert!(documents.is_none());
+}
+
+#[test]
+fn test_parse_multi_document() {
+    let content =
+        "# manifest\napiVersion: v1\nkind: Service\n---\napiVersion: apps/v1\nkind: Deployment\n";
+    let (value, documents) = parse_yaml(content).unwrap();
+    assert_eq!(value[0]["kind"], "Service");
+    assert_eq!(value[1]["kind"], "Deployment");
+
+    let documents = documents.unwrap();
+    assert_eq!(documents.len(), 2);
+    assert_eq!((documents[0].index, documents[0].line), (0, 2));
+    assert_eq!((documents[1].index, documents[1].line), (1, 4));
+}
+
+#[test]
+fn test_parse_multi_document_with_leading_marker() {
+    let content = "%YAML 1.2\n---\na: 1\n--- \nb: 2\n";
+    let (value, documents) = parse_yaml(content).unwrap();
+    assert_eq!(value, json!([{"a": 1}, {"b": 2}]));
+    let lines: Vec<usize> = documents.unwrap().iter().map(|d| d.line).collect();
+    assert_eq!(lines, vec![2, 4]);
+}
+
+#[test]
+fn test_parse_multi_document_with_end_markers() {
+    let content = "a: 1\n...\n# next\n---\nb: 2\n";
+    let (value, documents) = parse_yaml(content).unwrap();
+    assert_eq!(value, json!([{"a": 1}, {"b": 2}]));
+    let documents = documents.unwrap();
+    assert_eq!(documents.len(), 2);
+    assert_eq!((documents[1].line, documents[1].offset), (4, 16));
+}
+
+#[test]
+fn test_parse_multi_document_error_location() {
+    let err = parse_yaml("a: 1\n---\nb: [\n").unwrap_err();
+    assert_eq!(err.format, "yaml");
+    assert!(err.location.unwrap().line >= 3);
+}
+
+#[test]
+fn test_to_multi_document_yaml() {
+    let output = to_multi_document_yaml(&json!([{"a": 1}, {"b": 2}])).unwrap();
+    assert_eq!(output, "---\na: 1\n---\nb: 2\n");
+    let (value, _) = parse_yaml(&output).unwrap();
+    assert_eq!(value, json!([{"a": 1}, {"b": 2}]));
+}
@@ -1,5 +1,6 @@
 use tauri::{Emitter, Manager};
 mod commands;
+mod formats;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -9,6 +10,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_notification::init())
+        .manage(commands::documents::DocumentStore::default())
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
 
@@ -99,10 +101,28 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::parser::parse_content,
+            commands::parser::parse_document,
+            commands::parser::parse_bytes,
+            commands::parser::list_archive_entries,
+            commands::parser::protobuf_message_types,
+            commands::detect::detect_format,
+            commands::documents::open_document,
+            commands::documents::close_document,
+            commands::documents::get_document_value,
+            commands::documents::query_document,
+            commands::documents::export_document,
+            commands::documents::save_document,
+            commands::documents::list_file_archive_entries,
+            commands::documents::read_avro_records,
+            commands::documents::document_schema,
             commands::converter::convert_format,
+            commands::converter::convert_document,
+            commands::converter::encode_text,
             commands::tools::run_jq,
+            commands::tools::run_jq_with_warnings,
             commands::tools::run_jsonpath,
             commands::tools::anonymize_data,
+            commands::tools::unflatten_json,
             commands::tools::decode_jwt,
             commands::storage::add_recent_file,
             commands::storage::get_recent_files,
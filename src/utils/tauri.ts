@@ -2,25 +2,277 @@ import { invoke } from '@tauri-apps/api/core';
 
 export type FileFormat = 'json' | 'yaml' | 'xml' | 'toml' | 'csv' | 'typescript' | 'python' | 'go' | 'rust';
 
+export interface ParseError {
+    format: string;
+    kind: 'syntax' | 'eof' | 'data' | 'unsupported' | 'io';
+    message: string;
+    line?: number;
+    column?: number;
+    offset?: number;
+    snippet?: string | null;
+}
+
+export interface CsvOptions {
+    delimiter?: string;
+    quote?: string;
+    hasHeaders?: boolean;
+    trim?: boolean;
+    inferTypes?: boolean;
+    /** Rebuild nested objects and arrays from columns like `user.name` and `tags[0]`. */
+    unflatten?: boolean;
+    pathSeparator?: string;
+}
+
+export interface CsvExportOptions {
+    delimiter?: string;
+    /** Flattened paths to write, in this order, such as `user.name`. */
+    columns?: string[];
+    /** Header text per column path. */
+    headers?: Record<string, string>;
+    /** One column per item, all items in one cell, or one row per item. */
+    arrays?: 'columns' | 'join' | 'rows';
+    arraySeparator?: string;
+    /** Separator between keys in column names, `.` by default. */
+    pathSeparator?: string;
+    bom?: boolean;
+}
+
+export interface CsvDialect {
+    delimiter: string;
+    quote: string;
+    hasHeaders: boolean;
+    columns: { name: string; type: 'null' | 'boolean' | 'integer' | 'number' | 'date' | 'datetime' | 'string' }[];
+}
+
+export interface ProtobufOptions {
+    /** Base64 of a compiled `FileDescriptorSet`. */
+    descriptorSet?: string;
+    /** `.proto` sources by file name. */
+    protoFiles?: Record<string, string>;
+    /** Fully qualified message name; without it the wire format is shown. */
+    messageType?: string;
+}
+
+export interface ParseOptions {
+    filename?: string;
+    losslessNumbers?: boolean;
+    sortKeys?: boolean;
+    csv?: CsvOptions;
+    xml?: { forceArrays?: string[] };
+    repair?: boolean;
+    encoding?: string;
+    entry?: string;
+    protobuf?: ProtobufOptions;
+    positions?: boolean;
+    rejectDuplicateKeys?: boolean;
+}
+
+export type Compression = 'gzip' | 'zstd' | 'zip';
+
+export interface ArchiveEntry {
+    name: string;
+    size: number;
+    compressedSize: number;
+}
+
+export interface TextEncoding {
+    name: string;
+    bom: boolean;
+}
+
+export interface JsonRepair {
+    message: string;
+    line: number;
+    column: number;
+    offset: number;
+}
+
+export interface ParsedDocument {
+    value: any;
+    format: string;
+    confidence: number;
+    errors: ParseError[];
+    warnings: string[];
+    documents?: { index: number; line: number; offset: number }[];
+    dialect?: CsvDialect;
+    repairs?: JsonRepair[];
+    encoding?: TextEncoding;
+    compression?: Compression;
+    entry?: string;
+    /** Avro input only: the writer schema embedded in the file. */
+    schema?: any;
+    positions?: NodePosition[];
+    duplicateKeys?: DuplicateKey[];
+}
+
+export interface SourceLocation {
+    line: number;
+    column: number;
+    offset: number;
+}
+
+/** Where a value is in the source; `path` is the graph node path of the value. */
+export interface NodePosition {
+    path: (string | number)[];
+    start: SourceLocation;
+    end: SourceLocation;
+}
+
+/** A key repeated within one object; `locations` holds where each occurrence starts. */
+export interface DuplicateKey {
+    path: (string | number)[];
+    locations: SourceLocation[];
+}
+
+export interface ConvertOptions {
+    multiDocument?: boolean;
+    sortKeys?: boolean;
+    compression?: 'gzip' | 'zstd';
+    protobuf?: ProtobufOptions;
+    csv?: CsvExportOptions;
+}
+
+export interface ConvertedDocument {
+    /** Text output, or base64 for binary targets such as `msgpack` and `cbor`. */
+    output: string;
+    warnings: string[];
+    base64?: boolean;
+    compressed?: number[];
+}
+
+export interface QueryResult {
+    value: any;
+    warnings: string[];
+}
+
+export interface FormatDetection {
+    format: string;
+    confidence: number;
+}
+
+export interface DocumentSummary {
+    handle: number;
+    path: string;
+    format: string;
+    confidence: number;
+    bytes: number;
+    rootType: string;
+    entries: number;
+    nodes: number;
+    depth: number;
+    errors: ParseError[];
+    warnings: string[];
+    documents?: { index: number; line: number; offset: number }[];
+    dialect?: CsvDialect;
+    repairs?: JsonRepair[];
+    encoding: TextEncoding;
+    compression?: Compression;
+    entry?: string;
+    schema?: any;
+    duplicateKeys?: DuplicateKey[];
+    /** The document holds only the first page of an Avro file; `readAvroRecords` reads the rest. */
+    hasMore: boolean;
+}
+
+export interface AvroRecords {
+    schema: any;
+    records: any[];
+    offset: number;
+    hasMore: boolean;
+}
+
 export const tauriApi = {
+    /** Fails on the first bad NDJSON line; use `parseDocument` to keep the good lines and get every error. */
     parseContent: async (content: string, format: string): Promise<any> => {
         return await invoke('parse_content', { content, format });
     },
 
+    parseDocument: async (content: string, format: string, options?: ParseOptions): Promise<ParsedDocument> => {
+        return await invoke('parse_document', { content, format, options });
+    },
+
+    parseBytes: async (bytes: Uint8Array | number[], format: string, options?: ParseOptions): Promise<ParsedDocument> => {
+        return await invoke('parse_bytes', { bytes: Array.from(bytes), format, options });
+    },
+
+    listArchiveEntries: async (bytes: Uint8Array | number[]): Promise<ArchiveEntry[]> => {
+        return await invoke('list_archive_entries', { bytes: Array.from(bytes) });
+    },
+
+    listFileArchiveEntries: async (path: string): Promise<ArchiveEntry[]> => {
+        return await invoke('list_file_archive_entries', { path });
+    },
+
+    readAvroRecords: async (path: string, offset?: number, limit?: number): Promise<AvroRecords> => {
+        return await invoke('read_avro_records', { path, offset, limit });
+    },
+
+    protobufMessageTypes: async (options: ProtobufOptions): Promise<string[]> => {
+        return await invoke('protobuf_message_types', { options });
+    },
+
+    detectFormat: async (content: string, filename?: string): Promise<FormatDetection> => {
+        return await invoke('detect_format', { content, filename });
+    },
+
+    openDocument: async (path: string, format?: string, options?: ParseOptions): Promise<DocumentSummary> => {
+        return await invoke('open_document', { path, format, options });
+    },
+
+    closeDocument: async (handle: number): Promise<boolean> => {
+        return await invoke('close_document', { handle });
+    },
+
+    getDocumentValue: async (handle: number, pointer?: string): Promise<any> => {
+        return await invoke('get_document_value', { handle, pointer });
+    },
+
+    queryDocument: async (handle: number, filter: string, sortKeys?: boolean): Promise<QueryResult> => {
+        return await invoke('query_document', { handle, filter, sortKeys });
+    },
+
+    exportDocument: async (handle: number, targetFormat: string, options?: ConvertOptions): Promise<ConvertedDocument> => {
+        return await invoke('export_document', { handle, targetFormat, options });
+    },
+
+    saveDocument: async (handle: number, path: string, targetFormat?: string, options?: ConvertOptions): Promise<ConvertedDocument> => {
+        return await invoke('save_document', { handle, path, targetFormat, options });
+    },
+
+    documentSchema: async (handle: number): Promise<any> => {
+        return await invoke('document_schema', { handle });
+    },
+
     convertFormat: async (content: string, sourceFormat: string, targetFormat: string): Promise<string> => {
         return await invoke('convert_format', { content, sourceFormat, targetFormat });
     },
 
-    runJq: async (filter: string, json: any): Promise<any> => {
-        return await invoke('run_jq', { filter, json });
+    convertDocument: async (content: string, sourceFormat: string, targetFormat: string, options?: ConvertOptions): Promise<ConvertedDocument> => {
+        return await invoke('convert_document', { content, sourceFormat, targetFormat, options });
+    },
+
+    encodeText: async (text: string, encoding?: TextEncoding): Promise<Uint8Array> => {
+        return new Uint8Array(await invoke<number[]>('encode_text', { text, encoding }));
+    },
+
+    runJq: async (filter: string, json: any, sortKeys?: boolean): Promise<any> => {
+        return await invoke('run_jq', { filter, json, sortKeys });
+    },
+
+    runJqWithWarnings: async (filter: string, json: any, sortKeys?: boolean): Promise<QueryResult> => {
+        return await invoke('run_jq_with_warnings', { filter, json, sortKeys });
     },
 
     runJsonPath: async (path: string, json: any): Promise<any> => {
         return await invoke('run_jsonpath', { path, json });
     },
 
-    anonymizeData: async (json: any): Promise<any> => {
-        return await invoke('anonymize_data', { json });
+    anonymizeData: async (json: any, sortKeys?: boolean): Promise<any> => {
+        return await invoke('anonymize_data', { json, sortKeys });
+    },
+
+    unflattenJson: async (json: any, separator?: string): Promise<any> => {
+        return await invoke('unflatten_json', { json, separator });
     },
 
     decodeJwt: async (token: string): Promise<any> => {